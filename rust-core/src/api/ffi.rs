@@ -3,21 +3,57 @@
 //! Matches the FFI contract defined in `docs/model-design.md`: string-returning
 //! version function, explicit status codes (`DeltaCode`) and deterministic
 //! routing behaviour that can be audited from the PHP layer.
+//!
+//! The exports are plain `extern "C"` items so cbindgen and PHP see one
+//! calling convention, but they dereference raw pointers supplied by the
+//! caller. Null pointers are rejected; anything else is trusted to be a valid,
+//! NUL-terminated string (or buffer of the stated length) that outlives the
+//! call. Rust cannot check that, so `not_unsafe_ptr_arg_deref` is suppressed
+//! for this module and the contract lives here and in `docs/php-ffi.md`.
+
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
 
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
 use std::sync::OnceLock;
 
+use crate::artefact_cache_stats;
 use crate::common::error::{DeltaCode, DeltaError};
+use crate::consent_lookup_stats;
 use crate::core_data_ingest;
+use crate::core_infer_batch;
 use crate::core_infer_with_ctx;
+use crate::core_infer_with_model;
 use crate::core_load_model;
+use crate::core_promote_model;
+use crate::core_purpose;
+use crate::core_review;
+use crate::core_score_dataset;
 use crate::core_train;
+use crate::core_train_cancel;
+use crate::core_train_start;
+use crate::core_train_status;
+use crate::core_validate_config;
+use crate::core_verify_reproducible;
 use crate::data::domain::DatasetId;
+use crate::decide_review;
 use crate::export_datasheet;
 use crate::export_model_card;
+use crate::grant_consent;
+use crate::inference::domain::{batch_to_json, parse_batch};
+use crate::inference::review::ReviewStatus;
+use crate::load_pooled_model;
+use crate::pending_reviews;
+use crate::query_consent;
 use crate::register_active_model;
-use crate::training::domain::{ModelId, VersionName};
+use crate::reload_purposes;
+use crate::reload_router_rules;
+use crate::revoke_consent;
+use crate::set_consent_lookup;
+use crate::set_purpose_model;
+use crate::training::domain::{ModelId, ModelStage, VersionName};
+use crate::unload_pooled_model;
+use crate::worker_metrics;
 
 static API_VERSION: OnceLock<CString> = OnceLock::new();
 
@@ -78,6 +114,65 @@ pub extern "C" fn delta1_train(
     }
 }
 
+/// Start training in the background; poll with `delta1_train_status`.
+#[no_mangle]
+pub extern "C" fn delta1_train_start(
+    dataset_id: *const c_char,
+    train_cfg_json: *const c_char,
+    out_job_id: *mut *const c_char,
+) -> i32 {
+    if dataset_id.is_null() || train_cfg_json.is_null() || out_job_id.is_null() {
+        return DeltaCode::InvalidInput as i32;
+    }
+
+    let dataset = unsafe { CStr::from_ptr(dataset_id) }
+        .to_string_lossy()
+        .to_string();
+    let cfg = unsafe { CStr::from_ptr(train_cfg_json) }
+        .to_string_lossy()
+        .to_string();
+
+    match core_train_start(DatasetId::new(dataset), &cfg) {
+        Ok(job_id) => match assign_out_string(out_job_id, job_id) {
+            Ok(_) => DeltaCode::Ok as i32,
+            Err(err) => err.code as i32,
+        },
+        Err(err) => err.code as i32,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn delta1_train_status(job_id: *const c_char) -> *const c_char {
+    if job_id.is_null() {
+        return error_json(DeltaError::invalid("ffi_null"));
+    }
+
+    let job_id = unsafe { CStr::from_ptr(job_id) }
+        .to_string_lossy()
+        .to_string();
+
+    match core_train_status(&job_id) {
+        Ok(status) => string_to_raw(status.to_json()),
+        Err(err) => error_json(err),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn delta1_train_cancel(job_id: *const c_char) -> i32 {
+    if job_id.is_null() {
+        return DeltaCode::InvalidInput as i32;
+    }
+
+    let job_id = unsafe { CStr::from_ptr(job_id) }
+        .to_string_lossy()
+        .to_string();
+
+    match core_train_cancel(&job_id) {
+        Ok(_) => DeltaCode::Ok as i32,
+        Err(err) => err.code as i32,
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn delta1_load_model(model_id: *const c_char, version: *const c_char) -> i32 {
     if model_id.is_null() {
@@ -87,25 +182,141 @@ pub extern "C" fn delta1_load_model(model_id: *const c_char, version: *const c_c
     let model_id = unsafe { CStr::from_ptr(model_id) }
         .to_string_lossy()
         .to_string();
-    let version = if version.is_null() {
+    let version = optional_version(version);
+
+    let model_id = ModelId::new(model_id);
+    match core_load_model(&model_id, version.as_ref()) {
+        Ok(model) => match register_active_model(model) {
+            Ok(()) => DeltaCode::Ok as i32,
+            Err(err) => err.code as i32,
+        },
+        Err(err) => err.code as i32,
+    }
+}
+
+/// Load a model next to the already loaded ones without changing the fallback model.
+#[no_mangle]
+pub extern "C" fn delta1_pool_load(model_id: *const c_char, version: *const c_char) -> i32 {
+    if model_id.is_null() {
+        return DeltaCode::InvalidInput as i32;
+    }
+
+    let model_id = unsafe { CStr::from_ptr(model_id) }
+        .to_string_lossy()
+        .to_string();
+    let version = optional_version(version);
+
+    match core_load_model(&ModelId::new(model_id), version.as_ref()).and_then(load_pooled_model) {
+        Ok(()) => DeltaCode::Ok as i32,
+        Err(err) => err.code as i32,
+    }
+}
+
+/// Drop a loaded model; unloading a model that is not loaded is not an error.
+#[no_mangle]
+pub extern "C" fn delta1_pool_unload(model_id: *const c_char, version: *const c_char) -> i32 {
+    if model_id.is_null() || version.is_null() {
+        return DeltaCode::InvalidInput as i32;
+    }
+
+    let model_id = unsafe { CStr::from_ptr(model_id) }
+        .to_string_lossy()
+        .to_string();
+    let version = unsafe { CStr::from_ptr(version) }
+        .to_string_lossy()
+        .to_string();
+
+    match unload_pooled_model(&ModelId::new(model_id), &VersionName::new(version)) {
+        Ok(_) => DeltaCode::Ok as i32,
+        Err(err) => err.code as i32,
+    }
+}
+
+/// Make a loaded model the default for one purpose.
+#[no_mangle]
+pub extern "C" fn delta1_pool_set_purpose(
+    purpose_id: *const c_char,
+    model_id: *const c_char,
+    version: *const c_char,
+) -> i32 {
+    if purpose_id.is_null() || model_id.is_null() {
+        return DeltaCode::InvalidInput as i32;
+    }
+
+    let purpose = unsafe { CStr::from_ptr(purpose_id) }
+        .to_string_lossy()
+        .to_string();
+    let model_id = unsafe { CStr::from_ptr(model_id) }
+        .to_string_lossy()
+        .to_string();
+    let version = optional_version(version);
+
+    match set_purpose_model(&purpose, &ModelId::new(model_id), version.as_ref()) {
+        Ok(()) => DeltaCode::Ok as i32,
+        Err(err) => err.code as i32,
+    }
+}
+
+/// `None` for a null pointer, an empty string or `"latest"`.
+fn optional_version(version: *const c_char) -> Option<VersionName> {
+    if version.is_null() {
+        return None;
+    }
+    let raw = unsafe { CStr::from_ptr(version) }
+        .to_string_lossy()
+        .to_string();
+    if raw.is_empty() || raw == "latest" {
         None
     } else {
-        let raw = unsafe { CStr::from_ptr(version) }
-            .to_string_lossy()
-            .to_string();
-        if raw.is_empty() || raw == "latest" {
-            None
-        } else {
-            Some(VersionName::new(raw))
-        }
+        Some(VersionName::new(raw))
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn delta1_promote_model(
+    model_id: *const c_char,
+    version: *const c_char,
+    stage: *const c_char,
+    approver: *const c_char,
+) -> i32 {
+    if model_id.is_null() || version.is_null() || stage.is_null() {
+        return DeltaCode::InvalidInput as i32;
+    }
+
+    let model_id = unsafe { CStr::from_ptr(model_id) }
+        .to_string_lossy()
+        .to_string();
+    let version = unsafe { CStr::from_ptr(version) }
+        .to_string_lossy()
+        .to_string();
+    let stage = unsafe { CStr::from_ptr(stage) }
+        .to_string_lossy()
+        .to_string();
+    let approver = if approver.is_null() {
+        None
+    } else {
+        Some(
+            unsafe { CStr::from_ptr(approver) }
+                .to_string_lossy()
+                .to_string(),
+        )
     };
 
-    let model_id = ModelId::new(model_id);
-    match core_load_model(&model_id, version.as_ref()) {
-        Ok(model) => {
-            register_active_model(model);
-            DeltaCode::Ok as i32
-        }
+    let stage = match ModelStage::from_label(&stage) {
+        Some(stage) => stage,
+        None => return DeltaCode::InvalidInput as i32,
+    };
+    if version.is_empty() {
+        return DeltaCode::InvalidInput as i32;
+    }
+
+    match core_promote_model(
+        &ModelId::new(model_id),
+        &VersionName::new(version),
+        stage,
+        approver.as_deref(),
+    ) {
+        Ok(_) => DeltaCode::Ok as i32,
         Err(err) => err.code as i32,
     }
 }
@@ -132,6 +343,340 @@ pub extern "C" fn delta1_infer_with_ctx(
 
     match core_infer_with_ctx(&purpose, &subject, &input) {
         Ok(prediction) => string_to_raw(prediction.json),
+        Err(err) => string_to_raw(err.to_json()),
+    }
+}
+
+/// Score with a specific loaded model instead of the purpose default.
+#[no_mangle]
+pub extern "C" fn delta1_infer_with_model(
+    model_id: *const c_char,
+    version: *const c_char,
+    purpose_id: *const c_char,
+    subject_id: *const c_char,
+    input_json: *const c_char,
+) -> *const c_char {
+    if model_id.is_null() || purpose_id.is_null() || subject_id.is_null() || input_json.is_null() {
+        return error_json(DeltaError::invalid("ffi_null"));
+    }
+
+    let model_id = unsafe { CStr::from_ptr(model_id) }
+        .to_string_lossy()
+        .to_string();
+    let version = optional_version(version);
+    let purpose = unsafe { CStr::from_ptr(purpose_id) }
+        .to_string_lossy()
+        .to_string();
+    let subject = unsafe { CStr::from_ptr(subject_id) }
+        .to_string_lossy()
+        .to_string();
+    let input = unsafe { CStr::from_ptr(input_json) }
+        .to_string_lossy()
+        .to_string();
+
+    match core_infer_with_model(
+        &ModelId::new(model_id),
+        version.as_ref(),
+        &purpose,
+        &subject,
+        &input,
+    ) {
+        Ok(prediction) => string_to_raw(prediction.json),
+        Err(err) => string_to_raw(err.to_json()),
+    }
+}
+
+/// Record consent; `expires_at_ms` of 0 means no expiry.
+#[no_mangle]
+pub extern "C" fn delta1_consent_grant(
+    purpose_id: *const c_char,
+    subject_id: *const c_char,
+    expires_at_ms: u64,
+    source: *const c_char,
+    request_id: *const c_char,
+) -> i32 {
+    if purpose_id.is_null() || subject_id.is_null() || source.is_null() || request_id.is_null() {
+        return DeltaCode::InvalidInput as i32;
+    }
+
+    let purpose = unsafe { CStr::from_ptr(purpose_id) }.to_string_lossy();
+    let subject = unsafe { CStr::from_ptr(subject_id) }.to_string_lossy();
+    let source = unsafe { CStr::from_ptr(source) }.to_string_lossy();
+    let request = unsafe { CStr::from_ptr(request_id) }.to_string_lossy();
+    let expires = (expires_at_ms > 0).then_some(expires_at_ms as u128);
+
+    match grant_consent(&purpose, &subject, expires, &source, &request) {
+        Ok(_) => DeltaCode::Ok as i32,
+        Err(err) => err.code as i32,
+    }
+}
+
+/// Record that a subject withdrew consent.
+#[no_mangle]
+pub extern "C" fn delta1_consent_revoke(
+    purpose_id: *const c_char,
+    subject_id: *const c_char,
+    source: *const c_char,
+    request_id: *const c_char,
+) -> i32 {
+    if purpose_id.is_null() || subject_id.is_null() || source.is_null() || request_id.is_null() {
+        return DeltaCode::InvalidInput as i32;
+    }
+
+    let purpose = unsafe { CStr::from_ptr(purpose_id) }.to_string_lossy();
+    let subject = unsafe { CStr::from_ptr(subject_id) }.to_string_lossy();
+    let source = unsafe { CStr::from_ptr(source) }.to_string_lossy();
+    let request = unsafe { CStr::from_ptr(request_id) }.to_string_lossy();
+
+    match revoke_consent(&purpose, &subject, &source, &request) {
+        Ok(_) => DeltaCode::Ok as i32,
+        Err(err) => err.code as i32,
+    }
+}
+
+/// Current consent status; `"none"` when nothing was recorded.
+#[no_mangle]
+pub extern "C" fn delta1_consent_query(
+    purpose_id: *const c_char,
+    subject_id: *const c_char,
+) -> *const c_char {
+    if purpose_id.is_null() || subject_id.is_null() {
+        return error_json(DeltaError::invalid("ffi_null"));
+    }
+
+    let purpose = unsafe { CStr::from_ptr(purpose_id) }.to_string_lossy();
+    let subject = unsafe { CStr::from_ptr(subject_id) }.to_string_lossy();
+
+    match query_consent(&purpose, &subject) {
+        Ok(Some(record)) => string_to_raw(record.to_json()),
+        Ok(None) => string_to_raw(format!(
+            "{{\"ok\":true,\"purpose_id\":\"{}\",\"status\":\"none\"}}",
+            crate::common::json::escape(&purpose)
+        )),
+        Err(err) => error_json(err),
+    }
+}
+
+/// Re-read the purpose registry from `DELTA1_PURPOSES_FILE`.
+#[no_mangle]
+pub extern "C" fn delta1_purposes_reload() -> i32 {
+    match reload_purposes() {
+        Ok(_) => DeltaCode::Ok as i32,
+        Err(err) => err.code as i32,
+    }
+}
+
+/// Re-read the routing rules from `DELTA1_ROUTER_RULES_FILE`; a malformed
+/// file keeps the current rules.
+#[no_mangle]
+pub extern "C" fn delta1_router_reload() -> i32 {
+    match reload_router_rules() {
+        Ok(_) => DeltaCode::Ok as i32,
+        Err(err) => err.code as i32,
+    }
+}
+
+/// The registered purpose as JSON; unknown purposes return `InvalidPurpose`.
+#[no_mangle]
+pub extern "C" fn delta1_purpose_get(purpose_id: *const c_char) -> *const c_char {
+    if purpose_id.is_null() {
+        return error_json(DeltaError::invalid("ffi_null"));
+    }
+
+    let purpose = unsafe { CStr::from_ptr(purpose_id) }.to_string_lossy();
+
+    match core_purpose(&purpose) {
+        Ok(purpose) => string_to_raw(purpose.to_json()),
+        Err(err) => error_json(err),
+    }
+}
+
+/// Consent lookup provided by the host: receives the purpose and the keyed
+/// subject hash (HMAC-SHA256 under `DELTA1_SUBJECT_KEY`) and returns 1 (granted), 0 (not granted) or a negative value
+/// when the lookup failed. It is only called on the thread that called into
+/// the library, never from a worker.
+pub type ConsentLookupFn =
+    extern "C" fn(purpose_id: *const c_char, subject_hash: *const c_char) -> i32;
+
+/// Answer consent checks through `lookup`, cached for `ttl_s` seconds (at
+/// most 24 hours); a null `lookup` returns to the file store.
+#[no_mangle]
+pub extern "C" fn delta1_consent_set_lookup(lookup: Option<ConsentLookupFn>, ttl_s: u32) -> i32 {
+    let lookup = lookup.map(|lookup| {
+        Box::new(move |purpose: &str, subject_hash: &str| {
+            let purpose = CString::new(purpose).map_err(|_| DeltaError::invalid("ffi_nul_byte"))?;
+            let subject =
+                CString::new(subject_hash).map_err(|_| DeltaError::invalid("ffi_nul_byte"))?;
+            match lookup(purpose.as_ptr(), subject.as_ptr()) {
+                1 => Ok(true),
+                0 => Ok(false),
+                _ => Err(DeltaError::internal("consent_lookup_failed")),
+            }
+        }) as crate::inference::consent_lookup::ConsentLookup
+    });
+
+    match set_consent_lookup(lookup, u128::from(ttl_s) * 1_000) {
+        Ok(()) => DeltaCode::Ok as i32,
+        Err(err) => err.code as i32,
+    }
+}
+
+/// Cache hits, misses and lookup latency of the installed consent lookup.
+#[no_mangle]
+pub extern "C" fn delta1_consent_lookup_stats() -> *const c_char {
+    match consent_lookup_stats() {
+        Ok(Some(stats)) => string_to_raw(stats.to_json()),
+        Ok(None) => error_json(DeltaError::invalid("consent_lookup_unset")),
+        Err(err) => error_json(err),
+    }
+}
+
+/// Decisions waiting for a human reviewer as `{"ok":true,"reviews":[...]}`.
+#[no_mangle]
+pub extern "C" fn delta1_review_queue() -> *const c_char {
+    match pending_reviews() {
+        Ok(tickets) => {
+            let tickets = tickets
+                .iter()
+                .map(|ticket| ticket.to_json())
+                .collect::<Vec<_>>();
+            string_to_raw(format!(
+                "{{\"ok\":true,\"reviews\":[{}]}}",
+                tickets.join(",")
+            ))
+        }
+        Err(err) => error_json(err),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn delta1_review_get(review_id: *const c_char) -> *const c_char {
+    if review_id.is_null() {
+        return error_json(DeltaError::invalid("ffi_null"));
+    }
+
+    let review_id = unsafe { CStr::from_ptr(review_id) }.to_string_lossy();
+
+    match core_review(&review_id) {
+        Ok(Some(ticket)) => string_to_raw(ticket.to_json()),
+        Ok(None) => error_json(DeltaError::invalid("review_unknown")),
+        Err(err) => error_json(err),
+    }
+}
+
+/// Record a human verdict (`approved` or `rejected`) on a pending review.
+#[no_mangle]
+pub extern "C" fn delta1_review_decide(
+    review_id: *const c_char,
+    verdict: *const c_char,
+    reviewer: *const c_char,
+    note: *const c_char,
+) -> i32 {
+    if review_id.is_null() || verdict.is_null() || reviewer.is_null() {
+        return DeltaCode::InvalidInput as i32;
+    }
+
+    let review_id = unsafe { CStr::from_ptr(review_id) }.to_string_lossy();
+    let verdict = unsafe { CStr::from_ptr(verdict) }.to_string_lossy();
+    let reviewer = unsafe { CStr::from_ptr(reviewer) }.to_string_lossy();
+    let note = if note.is_null() {
+        String::new()
+    } else {
+        unsafe { CStr::from_ptr(note) }
+            .to_string_lossy()
+            .to_string()
+    };
+    let Some(verdict) = ReviewStatus::from_label(&verdict) else {
+        return DeltaCode::InvalidInput as i32;
+    };
+
+    match decide_review(&review_id, verdict, &reviewer, &note) {
+        Ok(_) => DeltaCode::Ok as i32,
+        Err(err) => err.code as i32,
+    }
+}
+
+/// Score a JSON array of `{"subject_id", "input"}` items; results keep the input order.
+#[no_mangle]
+pub extern "C" fn delta1_infer_batch(
+    purpose_id: *const c_char,
+    items_json: *const c_char,
+) -> *const c_char {
+    if purpose_id.is_null() || items_json.is_null() {
+        return error_json(DeltaError::invalid("ffi_null"));
+    }
+
+    let purpose = unsafe { CStr::from_ptr(purpose_id) }
+        .to_string_lossy()
+        .to_string();
+    let items = unsafe { CStr::from_ptr(items_json) }.to_string_lossy();
+    let items = match parse_batch(&items) {
+        Ok(items) => items,
+        Err(err) => return error_json(err),
+    };
+
+    match core_infer_batch(&purpose, &items) {
+        Ok(results) => string_to_raw(batch_to_json(&results)),
+        Err(err) => error_json(err),
+    }
+}
+
+/// Score every record of a stored dataset into a new dataset.
+#[no_mangle]
+pub extern "C" fn delta1_score_dataset(
+    purpose_id: *const c_char,
+    dataset_id: *const c_char,
+) -> *const c_char {
+    if purpose_id.is_null() || dataset_id.is_null() {
+        return error_json(DeltaError::invalid("ffi_null"));
+    }
+
+    let purpose = unsafe { CStr::from_ptr(purpose_id) }
+        .to_string_lossy()
+        .to_string();
+    let dataset = unsafe { CStr::from_ptr(dataset_id) }
+        .to_string_lossy()
+        .to_string();
+
+    match core_score_dataset(&purpose, &DatasetId::new(dataset)) {
+        Ok(report) => string_to_raw(report.to_json()),
+        Err(err) => error_json(err),
+    }
+}
+
+/// Validate a train config; returns the field errors or the normalised config as JSON.
+#[no_mangle]
+pub extern "C" fn delta1_train_validate(train_cfg_json: *const c_char) -> *const c_char {
+    if train_cfg_json.is_null() {
+        return error_json(DeltaError::invalid("ffi_null"));
+    }
+
+    let cfg = unsafe { CStr::from_ptr(train_cfg_json) }
+        .to_string_lossy()
+        .to_string();
+
+    string_to_raw(core_validate_config(&cfg))
+}
+
+/// Retrain a version from its run manifest; returns the reproducibility report JSON.
+#[no_mangle]
+pub extern "C" fn delta1_train_verify(
+    model_id: *const c_char,
+    version: *const c_char,
+) -> *const c_char {
+    if model_id.is_null() || version.is_null() {
+        return error_json(DeltaError::invalid("ffi_null"));
+    }
+
+    let model_id = unsafe { CStr::from_ptr(model_id) }
+        .to_string_lossy()
+        .to_string();
+    let version = unsafe { CStr::from_ptr(version) }
+        .to_string_lossy()
+        .to_string();
+
+    match core_verify_reproducible(&ModelId::new(model_id), &VersionName::new(version)) {
+        Ok(report) => string_to_raw(report.to_json()),
         Err(err) => error_json(err),
     }
 }
@@ -170,6 +715,16 @@ pub extern "C" fn delta1_export_datasheet(dataset_id: *const c_char) -> *const c
     }
 }
 
+#[no_mangle]
+pub extern "C" fn delta1_artefact_cache_stats() -> *const c_char {
+    string_to_raw(artefact_cache_stats().to_json())
+}
+
+#[no_mangle]
+pub extern "C" fn delta1_worker_metrics() -> *const c_char {
+    string_to_raw(worker_metrics().to_json())
+}
+
 #[no_mangle]
 pub extern "C" fn delta1_free_str(ptr: *const c_char) {
     if ptr.is_null() {
@@ -23,6 +23,7 @@ impl ReusableBuffer {
     }
 
     /// Mutable access to the underlying storage.
+    #[allow(clippy::should_implement_trait)]
     pub fn as_mut(&mut self) -> &mut Vec<u8> {
         &mut self.inner
     }
@@ -4,6 +4,7 @@
 //! TODO: Investigate hot-reload hooks and immutable snapshots for long-running workers.
 
 use std::env;
+use std::sync::OnceLock;
 
 /// Snapshot of configuration values consumed by the core.
 #[derive(Clone, Debug)]
@@ -11,6 +12,34 @@ pub struct AppCfg {
     pub data_root: String,
     pub region: String,
     pub log_level: u8,
+    /// Secret used to sign newly written artefacts (`DELTA1_SIGNING_KEY`).
+    pub signing_key: Option<String>,
+    /// Comma-separated secrets whose signatures are accepted (`DELTA1_TRUSTED_KEYS`).
+    pub trusted_keys: Vec<String>,
+    /// Secret keying the subject hashes in the consent ledger, review tickets
+    /// and consent lookups (`DELTA1_SUBJECT_KEY`).
+    pub subject_key: Option<String>,
+    /// `enforce` refuses unsigned or untrusted artefacts, `warn` only logs them.
+    pub signature_policy: String,
+    /// Memory budget of the inference artefact cache (`DELTA1_ARTEFACT_CACHE_MB`).
+    pub artefact_cache_mb: usize,
+    /// Inference worker threads (`DELTA1_THREADS`, default `min(8, physical cores)`).
+    pub threads: usize,
+    /// Jobs that may wait for a worker (`DELTA1_QUEUE_CAPACITY`).
+    pub queue_capacity: usize,
+    /// `block` waits for a free queue slot, `reject` fails (`DELTA1_QUEUE_FULL`,
+    /// case-insensitive; any other value rejects and is logged as an error).
+    pub queue_full: String,
+    /// Purpose registry file (`DELTA1_PURPOSES_FILE`, default `${DATA_ROOT}/purposes.json`).
+    pub purposes_file: String,
+    /// Routing rules file (`DELTA1_ROUTER_RULES_FILE`, default `${DATA_ROOT}/router.json`).
+    pub router_rules_file: String,
+    /// Lowest holdout AUC that passes evaluation (`DELTA1_EVAL_MIN_AUC`).
+    pub eval_min_auc: f64,
+    /// Highest holdout ECE that passes evaluation (`DELTA1_EVAL_MAX_ECE`).
+    pub eval_max_ece: f64,
+    /// Holdout rows an evaluation needs (`DELTA1_EVAL_MIN_ROWS`).
+    pub eval_min_rows: u32,
 }
 
 impl AppCfg {
@@ -23,15 +52,97 @@ impl AppCfg {
         // TODO: Add validation for the directory structure, including permissions and ownership.
         // TODO: Merge values from a configurable key=value file to avoid large environment surfaces.
 
+        let data_root = env_or("DELTA1_DATA_ROOT", "./data");
         Self {
-            data_root: env_or("DELTA1_DATA_ROOT", "./data"),
+            purposes_file: env::var("DELTA1_PURPOSES_FILE")
+                .unwrap_or_else(|_| format!("{data_root}/purposes.json")),
+            router_rules_file: env::var("DELTA1_ROUTER_RULES_FILE")
+                .unwrap_or_else(|_| format!("{data_root}/router.json")),
+            data_root,
             region: env_or("DELTA1_REGION", "eu"),
             log_level: env_or("DELTA1_LOG_LEVEL", "1").parse().unwrap_or(1),
+            signing_key: env::var("DELTA1_SIGNING_KEY")
+                .ok()
+                .filter(|key| !key.is_empty()),
+            trusted_keys: env_or("DELTA1_TRUSTED_KEYS", "")
+                .split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(str::to_string)
+                .collect(),
+            subject_key: env::var("DELTA1_SUBJECT_KEY")
+                .ok()
+                .filter(|key| !key.is_empty()),
+            signature_policy: env_or("DELTA1_SIGNATURE_POLICY", "warn"),
+            artefact_cache_mb: env_or("DELTA1_ARTEFACT_CACHE_MB", "256")
+                .parse()
+                .unwrap_or(256),
+            threads: env::var("DELTA1_THREADS")
+                .ok()
+                .and_then(|threads| threads.parse().ok())
+                .unwrap_or_else(|| physical_cores().min(8)),
+            queue_capacity: env_or("DELTA1_QUEUE_CAPACITY", "1024")
+                .parse()
+                .unwrap_or(1024),
+            queue_full: env_or("DELTA1_QUEUE_FULL", "block"),
+            eval_min_auc: env_or("DELTA1_EVAL_MIN_AUC", "0.6").parse().unwrap_or(0.6),
+            eval_max_ece: env_or("DELTA1_EVAL_MAX_ECE", "0.1").parse().unwrap_or(0.1),
+            eval_min_rows: env_or("DELTA1_EVAL_MIN_ROWS", "20").parse().unwrap_or(20),
         }
     }
 }
 
+/// Number of physical cores, falling back to the logical count where the
+/// topology is unknown. `/proc/cpuinfo` is read once per process.
+fn physical_cores() -> usize {
+    static CORES: OnceLock<usize> = OnceLock::new();
+    *CORES.get_or_init(count_physical_cores)
+}
+
+fn count_physical_cores() -> usize {
+    let logical = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let Ok(cpuinfo) = std::fs::read_to_string("/proc/cpuinfo") else {
+        return logical;
+    };
+    let mut cores = std::collections::HashSet::new();
+    let mut package = "";
+    for line in cpuinfo.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        match key.trim() {
+            "physical id" => package = value.trim(),
+            "core id" => {
+                cores.insert((package.to_string(), value.trim().to_string()));
+            }
+            _ => {}
+        }
+    }
+    if cores.is_empty() {
+        logical
+    } else {
+        cores.len().min(logical)
+    }
+}
+
 /// Convenience wrapper kept for compatibility with the documentation examples.
 pub fn load_cfg() -> AppCfg {
     AppCfg::load()
 }
+
+/// Point `DELTA1_DATA_ROOT`, `DELTA1_PURPOSES_FILE` and
+/// `DELTA1_ROUTER_RULES_FILE` at a per-process temporary directory so tests
+/// never touch a developer's data, even when their shell sets those variables,
+/// and set a test `DELTA1_SUBJECT_KEY`. Must run before any repository or
+/// store is initialised.
+#[cfg(test)]
+pub(crate) fn use_test_data_root() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| {
+        let root = env::temp_dir().join(format!("delta1-test-{}", std::process::id()));
+        env::set_var("DELTA1_PURPOSES_FILE", root.join("purposes.json"));
+        env::set_var("DELTA1_ROUTER_RULES_FILE", root.join("router.json"));
+        env::set_var("DELTA1_SUBJECT_KEY", "test-subject-key");
+        env::set_var("DELTA1_DATA_ROOT", root);
+    });
+}
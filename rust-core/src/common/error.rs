@@ -19,6 +19,10 @@ pub enum DeltaCode {
     InvalidInput = 4,
     /// Catch-all for bugs and unimplemented paths.
     Internal = 5,
+    /// The purpose is not registered (HTTP 422 in Gate 0).
+    InvalidPurpose = 6,
+    /// The decision was held back for human review (HTTP 202 in Gate 0).
+    HitlRequired = 7,
 }
 
 /// Canonical error type for the core.
@@ -54,6 +58,11 @@ impl DeltaError {
         Self::new(DeltaCode::NoConsent, "no_consent")
     }
 
+    /// Unknown purpose helper.
+    pub const fn invalid_purpose(msg: &'static str) -> Self {
+        Self::new(DeltaCode::InvalidPurpose, msg)
+    }
+
     /// Model missing helper.
     pub const fn model_missing(msg: &'static str) -> Self {
         Self::new(DeltaCode::ModelMissing, msg)
@@ -88,5 +97,7 @@ mod tests {
         assert_eq!(DeltaCode::ModelMissing as u32, 3);
         assert_eq!(DeltaCode::InvalidInput as u32, 4);
         assert_eq!(DeltaCode::Internal as u32, 5);
+        assert_eq!(DeltaCode::InvalidPurpose as u32, 6);
+        assert_eq!(DeltaCode::HitlRequired as u32, 7);
     }
 }
@@ -10,7 +10,7 @@ pub struct SimpleHash(u32);
 impl SimpleHash {
     /// Create a new hash state with the FNV offset basis.
     pub fn new() -> Self {
-        Self(216_613_626_1)
+        Self(2_166_136_261)
     }
 
     /// Feed bytes into the hash function.
@@ -3,7 +3,10 @@
 //! The goal is to avoid pulling additional dependencies while still being able
 //! to inspect a handful of keys inside configuration and request payloads.
 //! These helpers are **not** a general purpose parser – they assume well-formed
-//! JSON with double quoted keys and primitive values.
+//! JSON with double quoted keys and primitive values. [`parse`] offers a small
+//! strict parser for the few callers that need whole documents.
+
+use crate::common::error::{DeltaError, DeltaResult};
 
 /// Escape a string so it can be embedded into JSON output.
 pub fn escape(input: &str) -> String {
@@ -98,7 +101,7 @@ pub fn extract_object<'a>(source: &'a str, key: &str) -> Option<&'a str> {
     let mut depth = 0;
     let mut in_string = false;
     let mut escape = false;
-    let bytes = after[brace..].as_bytes();
+    let bytes = &after.as_bytes()[brace..];
     for (idx, &b) in bytes.iter().enumerate() {
         let ch = b as char;
         if escape {
@@ -194,3 +197,350 @@ pub fn build_string_array(items: &[String]) -> String {
     out.push(']');
     out
 }
+
+/// Parsed JSON document for the places that need more than single-key lookups
+/// (training configs, dataset records).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    /// Object members in document order.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Look up a member of an object value.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&[(String, Value)]> {
+        match self {
+            Value::Object(members) => Some(members),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// Compact serialisation with object keys sorted, so documents that differ
+    /// only in key order or whitespace produce the same string.
+    pub fn canonical(&self) -> String {
+        let mut out = String::new();
+        self.write_canonical(&mut out);
+        out
+    }
+
+    fn write_canonical(&self, out: &mut String) {
+        match self {
+            Value::Null => out.push_str("null"),
+            Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            Value::Number(n) => out.push_str(&n.to_string()),
+            Value::String(s) => {
+                out.push('"');
+                out.push_str(&escape(s));
+                out.push('"');
+            }
+            Value::Array(items) => {
+                out.push('[');
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        out.push(',');
+                    }
+                    item.write_canonical(out);
+                }
+                out.push(']');
+            }
+            Value::Object(members) => {
+                let mut sorted = members.iter().collect::<Vec<_>>();
+                sorted.sort_by(|a, b| a.0.cmp(&b.0));
+                out.push('{');
+                for (idx, (key, value)) in sorted.into_iter().enumerate() {
+                    if idx > 0 {
+                        out.push(',');
+                    }
+                    out.push('"');
+                    out.push_str(&escape(key));
+                    out.push_str("\":");
+                    value.write_canonical(out);
+                }
+                out.push('}');
+            }
+        }
+    }
+}
+
+/// Parse a complete JSON document.
+pub fn parse(source: &str) -> DeltaResult<Value> {
+    let mut parser = Parser {
+        bytes: source.as_bytes(),
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_ws();
+    if parser.pos != parser.bytes.len() {
+        return Err(DeltaError::invalid("json_trailing_characters"));
+    }
+    Ok(value)
+}
+
+const MAX_DEPTH: usize = 64;
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn skip_ws(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.bytes.get(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> DeltaResult<()> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(DeltaError::invalid("json_syntax"))
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Value) -> DeltaResult<Value> {
+        if self.bytes[self.pos..].starts_with(word.as_bytes()) {
+            self.pos += word.len();
+            Ok(value)
+        } else {
+            Err(DeltaError::invalid("json_syntax"))
+        }
+    }
+
+    fn value(&mut self) -> DeltaResult<Value> {
+        self.skip_ws();
+        match self.peek() {
+            Some(b'{') => self.nested(Self::object),
+            Some(b'[') => self.nested(Self::array),
+            Some(b'"') => self.string().map(Value::String),
+            Some(b't') => self.literal("true", Value::Bool(true)),
+            Some(b'f') => self.literal("false", Value::Bool(false)),
+            Some(b'n') => self.literal("null", Value::Null),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            _ => Err(DeltaError::invalid("json_syntax")),
+        }
+    }
+
+    fn nested(&mut self, inner: fn(&mut Self) -> DeltaResult<Value>) -> DeltaResult<Value> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(DeltaError::invalid("json_too_deep"));
+        }
+        let value = inner(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn object(&mut self) -> DeltaResult<Value> {
+        self.expect(b'{')?;
+        let mut members = Vec::new();
+        self.skip_ws();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(members));
+        }
+        loop {
+            self.skip_ws();
+            let key = self.string()?;
+            self.skip_ws();
+            self.expect(b':')?;
+            let value = self.value()?;
+            members.push((key, value));
+            self.skip_ws();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(members));
+                }
+                _ => return Err(DeltaError::invalid("json_syntax")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> DeltaResult<Value> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        self.skip_ws();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_ws();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                _ => return Err(DeltaError::invalid("json_syntax")),
+            }
+        }
+    }
+
+    fn number(&mut self) -> DeltaResult<Value> {
+        let start = self.pos;
+        while let Some(b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E') = self.peek() {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.pos])
+            .ok()
+            .and_then(|raw| raw.parse::<f64>().ok())
+            .filter(|n| n.is_finite())
+            .map(Value::Number)
+            .ok_or_else(|| DeltaError::invalid("json_number"))
+    }
+
+    fn string(&mut self) -> DeltaResult<String> {
+        self.expect(b'"')?;
+        let mut out = Vec::new();
+        loop {
+            let byte = self
+                .peek()
+                .ok_or_else(|| DeltaError::invalid("json_unterminated_string"))?;
+            self.pos += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let esc = self
+                        .peek()
+                        .ok_or_else(|| DeltaError::invalid("json_unterminated_string"))?;
+                    self.pos += 1;
+                    match esc {
+                        b'"' => out.push(b'"'),
+                        b'\\' => out.push(b'\\'),
+                        b'/' => out.push(b'/'),
+                        b'b' => out.push(0x08),
+                        b'f' => out.push(0x0c),
+                        b'n' => out.push(b'\n'),
+                        b'r' => out.push(b'\r'),
+                        b't' => out.push(b'\t'),
+                        b'u' => {
+                            let ch = self.unicode_escape()?;
+                            let mut buf = [0u8; 4];
+                            out.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                        }
+                        _ => return Err(DeltaError::invalid("json_escape")),
+                    }
+                }
+                _ => out.push(byte),
+            }
+        }
+        String::from_utf8(out).map_err(|_| DeltaError::invalid("json_utf8"))
+    }
+
+    fn hex4(&mut self) -> DeltaResult<u32> {
+        let raw = self
+            .bytes
+            .get(self.pos..self.pos + 4)
+            .and_then(|raw| std::str::from_utf8(raw).ok())
+            .and_then(|raw| u32::from_str_radix(raw, 16).ok())
+            .ok_or_else(|| DeltaError::invalid("json_escape"))?;
+        self.pos += 4;
+        Ok(raw)
+    }
+
+    fn unicode_escape(&mut self) -> DeltaResult<char> {
+        let high = self.hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if !self.bytes[self.pos..].starts_with(b"\\u") {
+                return Err(DeltaError::invalid("json_escape"));
+            }
+            self.pos += 2;
+            let low = self.hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(DeltaError::invalid("json_escape"));
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| DeltaError::invalid("json_escape"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_documents() {
+        let doc = parse(r#"{"a":[1,-2.5e1,true,null],"b":{"c":"x\"é"}}"#).unwrap();
+        let items = doc.get("a").and_then(Value::as_array).unwrap();
+        assert_eq!(items[1].as_f64(), Some(-25.0));
+        assert!(items[3].is_null());
+        let c = doc
+            .get("b")
+            .and_then(|b| b.get("c"))
+            .and_then(Value::as_str);
+        assert_eq!(c, Some("x\"é"));
+    }
+
+    #[test]
+    fn canonical_form_ignores_key_order_and_whitespace() {
+        let a = parse(r#"{"b": [1.0, 2.5], "a": {"y": null, "x": "q\""}}"#).unwrap();
+        let b = parse(r#"{"a":{"x":"q\"","y":null},"b":[1,2.5]}"#).unwrap();
+        assert_eq!(a.canonical(), b.canonical());
+        assert_eq!(a.canonical(), r#"{"a":{"x":"q\"","y":null},"b":[1,2.5]}"#);
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(parse("{\"a\":1,}").is_err());
+        assert!(parse("[1 2]").is_err());
+        assert!(parse("{\"a\":1} x").is_err());
+    }
+}
@@ -0,0 +1,125 @@
+//! Append-only JSONL ledgers shared by the consent store and the review queue.
+//!
+//! Every change is appended as one JSON line and the latest line per key wins,
+//! so the file doubles as the audit trail. Reads first apply the complete
+//! lines appended since the previous read, which makes changes written by
+//! other processes (PHP-FPM workers) visible immediately. Writes hold an
+//! exclusive lock on the file while they re-read it, decide and append, so a
+//! check such as "the ticket is still pending" holds across processes too.
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::hash::Hash;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+use super::error::{DeltaError, DeltaResult};
+
+/// One line of a ledger.
+pub trait LedgerEntry: Clone + Sized {
+    type Key: Eq + Hash;
+
+    /// Entries with the same key replace each other; the latest line wins.
+    fn key(&self) -> Self::Key;
+    fn to_line(&self) -> String;
+    /// Unreadable lines fail the read instead of being skipped, so a lost
+    /// revocation or verdict never goes unnoticed.
+    fn from_line(line: &str) -> DeltaResult<Self>;
+}
+
+struct State<E: LedgerEntry> {
+    entries: HashMap<E::Key, E>,
+    /// Bytes of the file already applied to `entries`.
+    offset: u64,
+}
+
+pub struct JsonlLedger<E: LedgerEntry> {
+    path: PathBuf,
+    state: Mutex<State<E>>,
+    poisoned: &'static str,
+}
+
+impl<E: LedgerEntry> JsonlLedger<E> {
+    /// Ledger at `path`; `poisoned` is the error reported when a thread
+    /// panicked while holding the in-process lock.
+    pub fn new(path: PathBuf, poisoned: &'static str) -> Self {
+        Self {
+            path,
+            state: Mutex::new(State {
+                entries: HashMap::new(),
+                offset: 0,
+            }),
+            poisoned,
+        }
+    }
+
+    /// Run `read` over the current entries.
+    pub fn read<T>(&self, read: impl FnOnce(&HashMap<E::Key, E>) -> T) -> DeltaResult<T> {
+        let mut state = self.lock()?;
+        self.refresh(&mut state)?;
+        Ok(read(&state.entries))
+    }
+
+    /// Append `entry` as the latest line for its key.
+    pub fn append(&self, entry: &E) -> DeltaResult<()> {
+        self.update(|_| Ok(entry.clone())).map(|_| ())
+    }
+
+    /// Derive the next entry from the current ones and append it, holding an
+    /// exclusive lock on the file from the re-read until the line is written.
+    pub fn update(
+        &self,
+        next: impl FnOnce(&HashMap<E::Key, E>) -> DeltaResult<E>,
+    ) -> DeltaResult<E> {
+        let mut state = self.lock()?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|_| DeltaError::io())?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|_| DeltaError::io())?;
+        // Released when `file` is dropped.
+        file.lock().map_err(|_| DeltaError::io())?;
+        self.refresh(&mut state)?;
+        let entry = next(&state.entries)?;
+        file.write_all(format!("{}\n", entry.to_line()).as_bytes())
+            .map_err(|_| DeltaError::io())?;
+        self.refresh(&mut state)?;
+        Ok(entry)
+    }
+
+    /// Apply the complete lines appended since the last refresh.
+    fn refresh(&self, state: &mut State<E>) -> DeltaResult<()> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(_) => return Err(DeltaError::io()),
+        };
+        file.seek(SeekFrom::Start(state.offset))
+            .map_err(|_| DeltaError::io())?;
+        let mut appended = String::new();
+        file.read_to_string(&mut appended)
+            .map_err(|_| DeltaError::io())?;
+
+        // A line still being written by another process ends without `\n`.
+        let complete = appended.rfind('\n').map_or(0, |end| end + 1);
+        for line in appended[..complete].lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = E::from_line(line)?;
+            state.entries.insert(entry.key(), entry);
+        }
+        state.offset += complete as u64;
+        Ok(())
+    }
+
+    fn lock(&self) -> DeltaResult<MutexGuard<'_, State<E>>> {
+        self.state
+            .lock()
+            .map_err(|_| DeltaError::internal(self.poisoned))
+    }
+}
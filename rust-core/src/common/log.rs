@@ -1,6 +1,5 @@
 //! Lightweight logging utilities emitting JSON lines.
 //!
-//! TODO: Wire structured context (dataset/model identifiers) into each log entry.
 //! TODO: Provide pluggable sinks once we move beyond stdout/stderr for observability.
 
 /// Emit a JSON line matching the documented schema.
@@ -11,3 +10,28 @@ pub fn log_json(level: &str, module: &str, event: &str, code: u32, dur_ms: u128)
     );
     // TODO: Add sampling and rate-limiting to prevent flooding when ingesting large batches.
 }
+
+/// Emit a JSON line like [`log_json`] with extra string fields, such as a
+/// request ID, appended after the fixed schema.
+pub fn log_json_ctx(
+    level: &str,
+    module: &str,
+    event: &str,
+    code: u32,
+    dur_ms: u128,
+    context: &[(&str, &str)],
+) {
+    let ts = crate::common::time::now_ms();
+    let mut line = format!(
+        "{{\"ts\":{ts},\"level\":\"{level}\",\"mod\":\"{module}\",\"ev\":\"{event}\",\"code\":{code},\"dur_ms\":{dur_ms}"
+    );
+    for (key, value) in context {
+        line.push_str(&format!(
+            ",\"{}\":\"{}\"",
+            crate::common::json::escape(key),
+            crate::common::json::escape(value)
+        ));
+    }
+    line.push('}');
+    println!("{line}");
+}
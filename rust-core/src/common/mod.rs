@@ -7,7 +7,9 @@ pub mod config;
 pub mod error;
 pub mod ids;
 pub mod json;
+pub mod ledger;
 pub mod log;
+pub mod sha256;
 pub mod time;
 
 pub use error::{DeltaCode, DeltaError, DeltaResult};
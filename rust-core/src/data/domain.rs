@@ -3,6 +3,8 @@
 //! TODO: Define schema parsing strategy that keeps zero-copy guarantees.
 //! TODO: Introduce dataset lifecycle states (draft, active, deprecated) once retention policies are clear.
 
+use std::path::Path;
+
 use crate::common::error::DeltaResult;
 
 /// Opaque identifier for datasets.
@@ -47,6 +49,12 @@ pub struct Dataset {
 pub trait DataRepo {
     fn put_dataset(&self, dataset: &Dataset) -> DeltaResult<()>;
     fn get_dataset(&self, id: DatasetId) -> DeltaResult<Dataset>;
+    /// Store the raw JSONL records of a dataset by copying them from `source`.
+    fn put_records(&self, id: &DatasetId, source: &Path) -> DeltaResult<()>;
+    /// Read back the non-empty record lines of a dataset.
+    fn get_records(&self, id: &DatasetId) -> DeltaResult<Vec<String>>;
+    /// Store JSONL records produced in-process, one line each.
+    fn put_record_lines(&self, id: &DatasetId, lines: &[String]) -> DeltaResult<()>;
     // TODO: Add streaming read/write APIs to avoid loading entire datasets in memory.
 }
 
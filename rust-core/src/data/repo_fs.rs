@@ -3,8 +3,8 @@
 //! TODO: Harden path handling and ensure directories are created with strict permissions.
 //! TODO: Implement periodic compaction/cleanup routines when datasets are retired.
 
-use std::fs::{self, OpenOptions};
-use std::io::{self, Write};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
 
 use crate::common::config::AppCfg;
@@ -28,6 +28,10 @@ impl FsDataRepo {
         self.root.join(format!("{}.meta", id.as_str()))
     }
 
+    fn records_path(&self, id: &DatasetId) -> PathBuf {
+        self.root.join(format!("{}.jsonl", id.as_str()))
+    }
+
     fn ensure_dirs(&self) -> io::Result<()> {
         fs::create_dir_all(&self.root)
     }
@@ -63,9 +67,68 @@ impl DataRepo for FsDataRepo {
         if !Path::new(&path).exists() {
             return Err(DeltaError::model_missing("dataset"));
         }
-        // TODO: Parse metadata files properly instead of returning a placeholder.
-        Err(DeltaError::not_implemented("FsDataRepo::get_dataset"))
+        let raw = fs::read_to_string(&path).map_err(|_| DeltaError::io())?;
+        parse_metadata(raw.trim_end(), id)
+    }
+
+    fn put_records(&self, id: &DatasetId, source: &Path) -> DeltaResult<()> {
+        self.ensure_dirs().map_err(|_| DeltaError::io())?;
+        fs::copy(source, self.records_path(id)).map_err(|_| DeltaError::io())?;
+        Ok(())
+    }
+
+    fn put_record_lines(&self, id: &DatasetId, lines: &[String]) -> DeltaResult<()> {
+        self.ensure_dirs().map_err(|_| DeltaError::io())?;
+        let mut body = String::new();
+        for line in lines {
+            body.push_str(line);
+            body.push('\n');
+        }
+        fs::write(self.records_path(id), body).map_err(|_| DeltaError::io())
     }
+
+    fn get_records(&self, id: &DatasetId) -> DeltaResult<Vec<String>> {
+        let file = match File::open(self.records_path(id)) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(DeltaError::model_missing("dataset"))
+            }
+            Err(_) => return Err(DeltaError::io()),
+        };
+        let mut records = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|_| DeltaError::io())?;
+            if !line.trim().is_empty() {
+                records.push(line);
+            }
+        }
+        Ok(records)
+    }
+}
+
+/// Parse the `key=value;...` line written by `put_dataset`; the schema is last
+/// because it may itself contain separators.
+fn parse_metadata(raw: &str, id: DatasetId) -> DeltaResult<Dataset> {
+    let corrupt = || DeltaError::internal("dataset_metadata_corrupt");
+    let (head, schema) = raw.split_once(";schema=").ok_or_else(corrupt)?;
+
+    let mut created_ms = None;
+    let mut rows = None;
+    for field in head.split(';') {
+        match field.split_once('=') {
+            Some(("id", value)) if value == id.as_str() => {}
+            Some(("created_ms", value)) => created_ms = value.parse().ok(),
+            Some(("rows", value)) => rows = value.parse().ok(),
+            _ => return Err(corrupt()),
+        }
+    }
+
+    Ok(Dataset::new(
+        id,
+        schema.to_string(),
+        created_ms.ok_or_else(corrupt)?,
+        rows.ok_or_else(corrupt)?,
+    ))
 }
 
 // TODO: Add fs-based locking to coordinate concurrent writers.
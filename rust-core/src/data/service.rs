@@ -6,12 +6,21 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
+use std::sync::OnceLock;
 
+use crate::common::config::AppCfg;
 use crate::common::error::{DeltaError, DeltaResult};
 use crate::common::ids::SimpleHash;
+use crate::common::json::{self, Value};
 use crate::common::time;
 
-use super::domain::{Dataset, DatasetId};
+use super::domain::{DataRepo, Dataset, DatasetId};
+use super::repo_fs::FsDataRepo;
+
+fn data_repo() -> &'static FsDataRepo {
+    static REPO: OnceLock<FsDataRepo> = OnceLock::new();
+    REPO.get_or_init(|| FsDataRepo::new(&AppCfg::load()))
+}
 
 /// Ingest a file into the system, returning the assigned dataset identifier.
 pub fn ingest_file(path: &str, schema_json: &str) -> DeltaResult<DatasetId> {
@@ -42,11 +51,60 @@ pub fn ingest_file(path: &str, schema_json: &str) -> DeltaResult<DatasetId> {
         rows,
     );
 
-    // TODO: Persist dataset metadata via DataRepo once wiring is in place.
+    let repo = data_repo();
+    repo.put_records(&dataset.id, Path::new(path))?;
+    repo.put_dataset(&dataset)?;
 
     Ok(dataset.id)
 }
 
+/// Store records produced in-process (e.g. batch predictions) as a new dataset.
+///
+/// The identifier is derived from the records like for ingested files.
+pub fn store_records(lines: &[String], schema_json: &str) -> DeltaResult<DatasetId> {
+    let mut hasher = SimpleHash::new();
+    for line in lines {
+        hasher.update(line.as_bytes());
+        hasher.update(b"\n");
+    }
+    let dataset = Dataset::new(
+        DatasetId::new(format!("ds-{}", hasher.finish_hex())),
+        schema_json.to_string(),
+        time::now_ms(),
+        lines.len() as u64,
+    );
+
+    let repo = data_repo();
+    repo.put_record_lines(&dataset.id, lines)?;
+    repo.put_dataset(&dataset)?;
+
+    Ok(dataset.id)
+}
+
+/// Load and parse the JSONL records of a previously ingested dataset.
+pub fn load_records(dataset_id: &DatasetId) -> DeltaResult<Vec<Value>> {
+    data_repo()
+        .get_records(dataset_id)?
+        .iter()
+        .map(|line| json::parse(line).map_err(|_| DeltaError::invalid("dataset_record_invalid")))
+        .collect()
+}
+
+/// Stored metadata of a previously ingested dataset.
+pub fn dataset(dataset_id: &DatasetId) -> DeltaResult<Dataset> {
+    data_repo().get_dataset(dataset_id.clone())
+}
+
+/// FNV-1a hash over the stored JSONL records, one `\n`-terminated line each.
+pub fn records_hash(dataset_id: &DatasetId) -> DeltaResult<String> {
+    let mut hasher = SimpleHash::new();
+    for line in data_repo().get_records(dataset_id)? {
+        hasher.update(line.as_bytes());
+        hasher.update(b"\n");
+    }
+    Ok(hasher.finish_hex())
+}
+
 /// Export a placeholder datasheet for the given dataset identifier.
 pub fn export_datasheet(dataset_id: &DatasetId) -> DeltaResult<String> {
     let sheet = format!(
@@ -59,3 +117,31 @@ pub fn export_datasheet(dataset_id: &DatasetId) -> DeltaResult<String> {
 }
 
 // TODO: Provide a dry-run API for validation without persistence side-effects.
+
+/// Write `lines` to a temporary file and ingest it; used by tests across domains.
+#[cfg(test)]
+pub(crate) fn ingest_fixture(name: &str, lines: &[&str]) -> DatasetId {
+    crate::common::config::use_test_data_root();
+    let dir = std::env::temp_dir().join(format!("delta1-fixtures-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("fixture dir");
+    let path = dir.join(format!("{name}.jsonl"));
+    std::fs::write(&path, lines.join("\n")).expect("fixture file");
+    ingest_file(path.to_str().expect("utf-8 path"), "{}").expect("fixture ingest")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ingested_datasets_are_persisted() {
+        let id = ingest_fixture("persisted", &["{\"a\":1}", "", "{\"a\":2}"]);
+        let dataset = data_repo().get_dataset(id.clone()).unwrap();
+        assert_eq!(dataset.rows, 3);
+        assert_eq!(dataset.schema.definition_json, "{}");
+
+        let records = load_records(&id).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].get("a").and_then(Value::as_f64), Some(2.0));
+    }
+}
@@ -3,14 +3,35 @@
 //! TODO: Define richer metric structures (AUC/F1 etc.) with deterministic serialisation.
 //! TODO: Incorporate fairness and bias auditing requirements.
 
+use crate::common::config::AppCfg;
 use crate::common::error::DeltaResult;
 use crate::training::domain::ModelVersion;
 
+/// Release thresholds the recorded holdout evaluation must meet.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EvalThresholds {
+    pub min_auc: f64,
+    pub max_ece: f64,
+    pub min_rows: u32,
+}
+
+impl EvalThresholds {
+    pub fn from_cfg(cfg: &AppCfg) -> Self {
+        Self {
+            min_auc: cfg.eval_min_auc,
+            max_ece: cfg.eval_max_ece,
+            min_rows: cfg.eval_min_rows,
+        }
+    }
+}
+
 /// Summary of evaluation metrics for a particular model.
 #[derive(Clone, Debug)]
 pub struct EvalSuite {
     pub model: ModelVersion,
     pub metrics_card: String,
+    /// Whether every release gate in the card passed.
+    pub passed: bool,
     // TODO: Store computed statistics in a structured format once schema is final.
 }
 
@@ -6,6 +6,6 @@
 pub mod domain;
 pub mod service;
 
-pub use domain::{DriftStats, EvalSuite};
+pub use domain::{DriftStats, EvalSuite, EvalThresholds};
 
 // TODO: Provide streaming evaluators once online metrics are specified.
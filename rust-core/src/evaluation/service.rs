@@ -3,17 +3,100 @@
 //! TODO: Implement metric calculators for accuracy, precision/recall, and fairness metrics.
 //! TODO: Persist evaluation cards to filesystem or object storage for auditability.
 
+use crate::common::config::AppCfg;
 use crate::common::error::{DeltaError, DeltaResult};
+use crate::common::json;
 use crate::training::domain::ModelVersion;
+use crate::training::service::{check_dp, check_fairness};
 
-use super::domain::{DriftStats, EvalSuite};
+use super::domain::{DriftStats, EvalSuite, EvalThresholds};
 
-/// Evaluate a model against reference datasets and produce a summary card.
+/// Evaluate a model under the thresholds configured in [`AppCfg`].
 pub fn evaluate(model: &ModelVersion) -> DeltaResult<EvalSuite> {
-    // TODO: Load evaluation dataset and compute real metrics.
+    evaluate_with(model, &EvalThresholds::from_cfg(&AppCfg::load()))
+}
+
+/// Check the evaluation recorded at training time against `thresholds` and
+/// produce a summary card.
+///
+/// The evidence is the model's holdout split: its metrics and the calibration
+/// ECE measured on rows the final fit never saw. Versions trained without a
+/// holdout (no `"calibration"` section) have no evidence and fail the
+/// `holdout` check. The card lists each release gate; promotion to
+/// production requires all of them to pass.
+pub fn evaluate_with(model: &ModelVersion, thresholds: &EvalThresholds) -> DeltaResult<EvalSuite> {
+    let holdout = model
+        .metadata
+        .metrics
+        .as_ref()
+        .filter(|metrics| metrics.split == "holdout" && metrics.rows >= thresholds.min_rows);
+    let ece = model.metadata.calibration.as_ref().map(|report| report.ece);
+    let checks = [
+        ("artefact", model.checksum.is_some()),
+        ("dp", check_dp(&model.metadata.dp).is_ok()),
+        (
+            "fairness",
+            check_fairness(model.metadata.fairness.as_ref()).is_ok(),
+        ),
+        ("holdout", holdout.is_some()),
+        (
+            "auc",
+            holdout.is_some_and(|metrics| metrics.auc >= thresholds.min_auc),
+        ),
+        ("ece", ece.is_some_and(|ece| ece <= thresholds.max_ece)),
+    ];
+    let passed = checks.iter().all(|(_, ok)| *ok);
+
+    let checks_json = checks
+        .iter()
+        .map(|(name, ok)| format!("\"{name}\":{ok}"))
+        .collect::<Vec<_>>()
+        .join(",");
+    let calibration = model
+        .metadata
+        .calibration
+        .as_ref()
+        .map(|report| {
+            let bins = report
+                .bins
+                .iter()
+                .map(|bin| bin.to_json())
+                .collect::<Vec<_>>()
+                .join(",");
+            format!(
+                "{{\"method\":\"{}\",\"holdout_rows\":{},\"ece\":{:.6},\"bins\":[{}]}}",
+                json::escape(&report.method),
+                report.holdout_rows,
+                report.ece,
+                bins
+            )
+        })
+        .unwrap_or_else(|| "null".to_string());
+    let metrics = holdout
+        .map(|metrics| {
+            format!(
+                "{{\"rows\":{},\"accuracy\":{:.6},\"log_loss\":{:.6},\"auc\":{:.6}}}",
+                metrics.rows, metrics.accuracy, metrics.log_loss, metrics.auc
+            )
+        })
+        .unwrap_or_else(|| "null".to_string());
+    let metrics_card = format!(
+        "{{\"model_id\":\"{}\",\"version\":\"{}\",\"checks\":{{{}}},\"thresholds\":{{\"min_auc\":{},\"max_ece\":{},\"min_rows\":{}}},\"holdout\":{},\"calibration\":{},\"passed\":{}}}",
+        json::escape(model.id.as_str()),
+        json::escape(model.version.as_str()),
+        checks_json,
+        thresholds.min_auc,
+        thresholds.max_ece,
+        thresholds.min_rows,
+        metrics,
+        calibration,
+        passed
+    );
+
     Ok(EvalSuite {
         model: model.clone(),
-        metrics_card: "{}".to_string(),
+        metrics_card,
+        passed,
     })
 }
 
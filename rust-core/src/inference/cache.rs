@@ -0,0 +1,279 @@
+//! Deserialised model artefacts shared by the inference engines.
+//!
+//! Entries are evicted least-recently-used first once their estimated size
+//! exceeds the budget (`DELTA1_ARTEFACT_CACHE_MB`); a single entry larger than
+//! the whole budget is served but not kept. Concurrent misses on the same key
+//! are single-flight: one caller reads the artefact, the others wait for its
+//! result. Invalidation during a load keeps that (possibly stale) result out
+//! of the cache.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+use crate::common::error::{DeltaError, DeltaResult};
+
+/// Counters and occupancy of an [`ArtefactCache`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub entries: usize,
+    pub bytes: usize,
+    pub budget_bytes: usize,
+}
+
+impl CacheStats {
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"hits\":{},\"misses\":{},\"evictions\":{},\"entries\":{},\"bytes\":{},\"budget_bytes\":{}}}",
+            self.hits, self.misses, self.evictions, self.entries, self.bytes, self.budget_bytes
+        )
+    }
+}
+
+struct Entry<V> {
+    value: Arc<V>,
+    bytes: usize,
+    last_used: u64,
+}
+
+struct State<K, V> {
+    entries: HashMap<K, Entry<V>>,
+    loading: HashSet<K>,
+    bytes: usize,
+    tick: u64,
+    /// Bumped by every invalidation so in-flight loads can tell they are stale.
+    generation: u64,
+}
+
+pub struct ArtefactCache<K, V> {
+    state: Mutex<State<K, V>>,
+    loaded: Condvar,
+    budget_bytes: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl<K: Clone + Eq + Hash, V> ArtefactCache<K, V> {
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            state: Mutex::new(State {
+                entries: HashMap::new(),
+                loading: HashSet::new(),
+                bytes: 0,
+                tick: 0,
+                generation: 0,
+            }),
+            loaded: Condvar::new(),
+            budget_bytes,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// The cached value for `key`, or the result of `load` (value and
+    /// estimated size in bytes). Failed loads are not cached.
+    pub fn get_or_load<F>(&self, key: &K, load: F) -> DeltaResult<Arc<V>>
+    where
+        F: FnOnce() -> DeltaResult<(V, usize)>,
+    {
+        let mut state = self.lock()?;
+        loop {
+            state.tick += 1;
+            let tick = state.tick;
+            if let Some(entry) = state.entries.get_mut(key) {
+                entry.last_used = tick;
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(Arc::clone(&entry.value));
+            }
+            if !state.loading.contains(key) {
+                break;
+            }
+            state = self
+                .loaded
+                .wait(state)
+                .map_err(|_| DeltaError::internal("artefact_cache_poisoned"))?;
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        state.loading.insert(key.clone());
+        let generation = state.generation;
+        drop(state);
+
+        let flight = Flight {
+            cache: self,
+            key: Some(key.clone()),
+        };
+        let (value, bytes) = load()?;
+        let value = Arc::new(value);
+
+        let mut state = flight.finish()?;
+        if state.generation == generation && bytes <= self.budget_bytes {
+            state.tick += 1;
+            let entry = Entry {
+                value: Arc::clone(&value),
+                bytes,
+                last_used: state.tick,
+            };
+            if let Some(old) = state.entries.insert(key.clone(), entry) {
+                state.bytes -= old.bytes;
+            }
+            state.bytes += bytes;
+            self.evict(&mut state, key);
+        }
+        Ok(value)
+    }
+
+    /// Drop every entry whose key matches; returns how many were dropped.
+    pub fn invalidate<P>(&self, matches: P) -> usize
+    where
+        P: Fn(&K) -> bool,
+    {
+        let Ok(mut state) = self.lock() else {
+            return 0;
+        };
+        state.generation += 1;
+        let keys = state
+            .entries
+            .keys()
+            .filter(|key| matches(key))
+            .cloned()
+            .collect::<Vec<_>>();
+        for key in &keys {
+            if let Some(entry) = state.entries.remove(key) {
+                state.bytes -= entry.bytes;
+            }
+        }
+        keys.len()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let (entries, bytes) = self
+            .lock()
+            .map(|state| (state.entries.len(), state.bytes))
+            .unwrap_or_default();
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            entries,
+            bytes,
+            budget_bytes: self.budget_bytes,
+        }
+    }
+
+    fn lock(&self) -> DeltaResult<MutexGuard<'_, State<K, V>>> {
+        self.state
+            .lock()
+            .map_err(|_| DeltaError::internal("artefact_cache_poisoned"))
+    }
+
+    /// Evict least-recently-used entries other than `keep` until within budget.
+    fn evict(&self, state: &mut State<K, V>, keep: &K) {
+        while state.bytes > self.budget_bytes {
+            let oldest = state
+                .entries
+                .iter()
+                .filter(|(key, _)| *key != keep)
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            let Some(oldest) = oldest else {
+                break;
+            };
+            if let Some(entry) = state.entries.remove(&oldest) {
+                state.bytes -= entry.bytes;
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Marks a key as loading; dropping it (also when the loader fails or
+/// panics) releases the key and wakes the waiting callers.
+struct Flight<'a, K: Clone + Eq + Hash, V> {
+    cache: &'a ArtefactCache<K, V>,
+    key: Option<K>,
+}
+
+impl<'a, K: Clone + Eq + Hash, V> Flight<'a, K, V> {
+    /// Release the key and keep the lock for storing the result.
+    fn finish(mut self) -> DeltaResult<MutexGuard<'a, State<K, V>>> {
+        let mut state = self.cache.lock()?;
+        if let Some(key) = self.key.take() {
+            state.loading.remove(&key);
+        }
+        self.cache.loaded.notify_all();
+        Ok(state)
+    }
+}
+
+impl<K: Clone + Eq + Hash, V> Drop for Flight<'_, K, V> {
+    fn drop(&mut self) {
+        let Some(key) = self.key.take() else {
+            return;
+        };
+        let mut state = match self.cache.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        state.loading.remove(&key);
+        self.cache.loaded.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Barrier;
+    use std::thread;
+
+    #[test]
+    fn evicts_least_recently_used_and_loads_once_per_key() {
+        let cache = ArtefactCache::<&str, u32>::new(10);
+        let load = |value: u32, bytes: usize| move || Ok((value, bytes));
+        assert_eq!(*cache.get_or_load(&"a", load(1, 4)).unwrap(), 1);
+        assert_eq!(*cache.get_or_load(&"b", load(2, 4)).unwrap(), 2);
+        assert_eq!(*cache.get_or_load(&"a", load(9, 4)).unwrap(), 1);
+        // `b` is now least recently used and makes room for `c`.
+        cache.get_or_load(&"c", load(3, 4)).unwrap();
+        assert_eq!(*cache.get_or_load(&"b", load(20, 4)).unwrap(), 20);
+        // Larger than the whole budget: served, never kept.
+        assert_eq!(*cache.get_or_load(&"huge", load(5, 11)).unwrap(), 5);
+        let failed = cache.get_or_load(&"d", || Err(DeltaError::model_missing("model_artefact")));
+        assert_eq!(failed.unwrap_err().msg, "model_artefact");
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (1, 6, 2));
+        assert_eq!((stats.entries, stats.bytes), (2, 8));
+        assert_eq!(cache.invalidate(|key| *key == "b"), 1);
+        assert_eq!(*cache.get_or_load(&"b", load(21, 4)).unwrap(), 21);
+
+        let cache = Arc::new(ArtefactCache::<&str, u32>::new(100));
+        let reads = Arc::new(AtomicU64::new(0));
+        let start = Arc::new(Barrier::new(8));
+        let handles = (0..8)
+            .map(|_| {
+                let (cache, reads, start) = (cache.clone(), reads.clone(), start.clone());
+                thread::spawn(move || {
+                    start.wait();
+                    *cache
+                        .get_or_load(&"shared", || {
+                            reads.fetch_add(1, Ordering::SeqCst);
+                            thread::sleep(std::time::Duration::from_millis(20));
+                            Ok((7, 1))
+                        })
+                        .unwrap()
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 7);
+        }
+        assert_eq!(reads.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.stats().hits, 7);
+    }
+}
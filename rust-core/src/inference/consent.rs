@@ -0,0 +1,356 @@
+//! File-backed consent store under `${DATA_ROOT}/consent/consent.jsonl`.
+//!
+//! Every status change is appended as one JSON line; the latest line per
+//! `(subject, purpose)` wins, so the file doubles as the audit trail:
+//!
+//! ```json
+//! {"subject":"9f86…","purpose":"credit","status":"granted","expires_at_ms":1767225600000,
+//!  "source":"portal","request_id":"req-1","updated_ms":1760000000000}
+//! ```
+//!
+//! Subject IDs are stored only as HMAC-SHA256 hashes keyed with
+//! `DELTA1_SUBJECT_KEY`, so the ledger cannot be reversed by hashing candidate
+//! IDs; without the key consent can be neither recorded nor checked. The file is a
+//! [`JsonlLedger`], so changes written by other processes (PHP-FPM workers)
+//! are visible immediately and unreadable lines make lookups fail instead of
+//! silently skipping a revocation.
+
+use std::path::PathBuf;
+
+use crate::common::config::AppCfg;
+use crate::common::error::{DeltaError, DeltaResult};
+use crate::common::json::{self, Value};
+use crate::common::ledger::{JsonlLedger, LedgerEntry};
+use crate::common::log;
+use crate::common::sha256::{hmac_sha256, to_hex};
+use crate::common::time;
+
+use super::domain::ConsentStore;
+
+/// Stored consent state of a subject for one purpose.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ConsentStatus {
+    Granted,
+    Denied,
+    Expired,
+}
+
+impl ConsentStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConsentStatus::Granted => "granted",
+            ConsentStatus::Denied => "denied",
+            ConsentStatus::Expired => "expired",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "granted" => Some(ConsentStatus::Granted),
+            "denied" => Some(ConsentStatus::Denied),
+            "expired" => Some(ConsentStatus::Expired),
+            _ => None,
+        }
+    }
+}
+
+/// Latest consent record of a subject for one purpose.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConsentRecord {
+    /// [`subject_hash`] of the subject ID.
+    pub subject_hash: String,
+    pub purpose_id: String,
+    pub status: ConsentStatus,
+    pub expires_at_ms: Option<u128>,
+    pub source: String,
+    pub request_id: String,
+    pub updated_ms: u128,
+}
+
+impl ConsentRecord {
+    /// The status at `now_ms`: granted consent past its expiry reads as expired.
+    pub fn status_at(&self, now_ms: u128) -> ConsentStatus {
+        match (self.status, self.expires_at_ms) {
+            (ConsentStatus::Granted, Some(expires)) if expires <= now_ms => ConsentStatus::Expired,
+            (status, _) => status,
+        }
+    }
+
+    /// Query view without the subject hash and request ID.
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"ok\":true,\"purpose_id\":\"{}\",\"status\":\"{}\",\"expires_at_ms\":{},\"source\":\"{}\",\"updated_ms\":{}}}",
+            json::escape(&self.purpose_id),
+            self.status_at(time::now_ms()).as_str(),
+            self.expires_at_ms
+                .map_or_else(|| "null".to_string(), |ms| ms.to_string()),
+            json::escape(&self.source),
+            self.updated_ms
+        )
+    }
+}
+
+impl LedgerEntry for ConsentRecord {
+    type Key = (String, String);
+
+    fn key(&self) -> Self::Key {
+        (self.subject_hash.clone(), self.purpose_id.clone())
+    }
+
+    fn to_line(&self) -> String {
+        format!(
+            "{{\"subject\":\"{}\",\"purpose\":\"{}\",\"status\":\"{}\",\"expires_at_ms\":{},\"source\":\"{}\",\"request_id\":\"{}\",\"updated_ms\":{}}}",
+            self.subject_hash,
+            json::escape(&self.purpose_id),
+            self.status.as_str(),
+            self.expires_at_ms
+                .map_or_else(|| "null".to_string(), |ms| ms.to_string()),
+            json::escape(&self.source),
+            json::escape(&self.request_id),
+            self.updated_ms
+        )
+    }
+
+    fn from_line(line: &str) -> DeltaResult<Self> {
+        let invalid = DeltaError::internal("consent_record_invalid");
+        let doc = json::parse(line).map_err(|_| invalid)?;
+        let text = |key: &str| {
+            doc.get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or(invalid)
+        };
+        let millis = |key: &str| match doc.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => value
+                .as_f64()
+                .filter(|ms| *ms >= 0.0)
+                .map(|ms| Some(ms as u128))
+                .ok_or(invalid),
+        };
+        Ok(Self {
+            subject_hash: text("subject")?,
+            purpose_id: text("purpose")?,
+            status: ConsentStatus::from_label(&text("status")?).ok_or(invalid)?,
+            expires_at_ms: millis("expires_at_ms")?,
+            source: text("source")?,
+            request_id: text("request_id")?,
+            updated_ms: millis("updated_ms")?.ok_or(invalid)?,
+        })
+    }
+}
+
+/// Hash under which a subject's consent is stored: HMAC-SHA256 of the ID
+/// under the subject key, hex encoded.
+pub fn subject_hash(key: &[u8], subject_id: &str) -> String {
+    to_hex(&hmac_sha256(key, subject_id.as_bytes()))
+}
+
+/// The subject key from `DELTA1_SUBJECT_KEY`.
+pub fn subject_key(cfg: &AppCfg) -> DeltaResult<Vec<u8>> {
+    cfg.subject_key
+        .as_ref()
+        .map(|key| key.as_bytes().to_vec())
+        .ok_or(DeltaError::policy_denied("subject_key_missing"))
+}
+
+pub struct FileConsentStore {
+    ledger: JsonlLedger<ConsentRecord>,
+    key: Option<Vec<u8>>,
+}
+
+impl FileConsentStore {
+    pub fn new(cfg: &AppCfg) -> Self {
+        Self::at(
+            PathBuf::from(&cfg.data_root)
+                .join("consent")
+                .join("consent.jsonl"),
+            subject_key(cfg).ok(),
+        )
+    }
+
+    fn at(path: PathBuf, key: Option<Vec<u8>>) -> Self {
+        Self {
+            ledger: JsonlLedger::new(path, "consent_store_poisoned"),
+            key,
+        }
+    }
+
+    /// [`subject_hash`] under this store's key; fails closed without one.
+    pub fn hash_subject(&self, subject_id: &str) -> DeltaResult<String> {
+        let key = self
+            .key
+            .as_ref()
+            .ok_or(DeltaError::policy_denied("subject_key_missing"))?;
+        Ok(subject_hash(key, subject_id))
+    }
+
+    /// Record consent for `purpose_id`, optionally until `expires_at_ms`.
+    pub fn grant(
+        &self,
+        purpose_id: &str,
+        subject_id: &str,
+        expires_at_ms: Option<u128>,
+        source: &str,
+        request_id: &str,
+    ) -> DeltaResult<ConsentRecord> {
+        if expires_at_ms.is_some_and(|expires| expires <= time::now_ms()) {
+            return Err(DeltaError::invalid("consent_expiry_past"));
+        }
+        self.record(
+            purpose_id,
+            subject_id,
+            ConsentStatus::Granted,
+            expires_at_ms,
+            source,
+            request_id,
+        )
+    }
+
+    /// Record that the subject withdrew or refused consent for `purpose_id`.
+    pub fn revoke(
+        &self,
+        purpose_id: &str,
+        subject_id: &str,
+        source: &str,
+        request_id: &str,
+    ) -> DeltaResult<ConsentRecord> {
+        self.record(
+            purpose_id,
+            subject_id,
+            ConsentStatus::Denied,
+            None,
+            source,
+            request_id,
+        )
+    }
+
+    /// The latest record for the subject and purpose, if any.
+    pub fn query(&self, purpose_id: &str, subject_id: &str) -> DeltaResult<Option<ConsentRecord>> {
+        let key = (self.hash_subject(subject_id)?, purpose_id.to_string());
+        self.ledger.read(|records| records.get(&key).cloned())
+    }
+
+    fn record(
+        &self,
+        purpose_id: &str,
+        subject_id: &str,
+        status: ConsentStatus,
+        expires_at_ms: Option<u128>,
+        source: &str,
+        request_id: &str,
+    ) -> DeltaResult<ConsentRecord> {
+        if subject_id.is_empty() || purpose_id.is_empty() {
+            return Err(DeltaError::invalid("consent_key_missing"));
+        }
+        if request_id.trim().is_empty() {
+            return Err(DeltaError::invalid("consent_request_id_missing"));
+        }
+        let record = ConsentRecord {
+            subject_hash: self.hash_subject(subject_id)?,
+            purpose_id: purpose_id.to_string(),
+            status,
+            expires_at_ms,
+            source: source.to_string(),
+            request_id: request_id.to_string(),
+            updated_ms: time::now_ms(),
+        };
+
+        let started = time::now_ms();
+        self.ledger.append(&record)?;
+        log::log_json_ctx(
+            "info",
+            "consent",
+            &format!("consent_{}", status.as_str()),
+            0,
+            time::now_ms().saturating_sub(started),
+            &[
+                ("purpose_id", purpose_id),
+                ("subject", &record.subject_hash),
+                ("source", source),
+                ("request_id", request_id),
+            ],
+        );
+        Ok(record)
+    }
+}
+
+impl ConsentStore for FileConsentStore {
+    fn is_granted(&self, purpose_id: &str, subject_id: &str) -> DeltaResult<bool> {
+        Ok(self
+            .query(purpose_id, subject_id)?
+            .is_some_and(|record| record.status_at(time::now_ms()) == ConsentStatus::Granted))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    const KEY: &[u8] = b"consent-test-key";
+
+    #[test]
+    fn status_changes_are_persisted_hashed_and_shared_between_stores() {
+        let path = std::env::temp_dir()
+            .join(format!("delta1-consent-{}", std::process::id()))
+            .join("consent.jsonl");
+        let _ = fs::remove_file(&path);
+        let store = FileConsentStore::at(path.clone(), Some(KEY.to_vec()));
+        assert!(!store.is_granted("credit", "alice").unwrap());
+
+        store
+            .grant("credit", "alice", None, "portal", "req-1")
+            .unwrap();
+        // A second process sees the change through the shared file.
+        let other = FileConsentStore::at(path.clone(), Some(KEY.to_vec()));
+        assert!(other.is_granted("credit", "alice").unwrap());
+        assert!(!other.is_granted("marketing", "alice").unwrap());
+        other
+            .revoke("credit", "alice", "helpdesk", "req-2")
+            .unwrap();
+        assert!(!store.is_granted("credit", "alice").unwrap());
+        let record = store.query("credit", "alice").unwrap().unwrap();
+        assert_eq!(
+            (record.status, record.source.as_str()),
+            (ConsentStatus::Denied, "helpdesk")
+        );
+
+        let soon = time::now_ms() + 60_000;
+        store
+            .grant("credit", "bob", Some(soon), "portal", "req-3")
+            .unwrap();
+        let record = store.query("credit", "bob").unwrap().unwrap();
+        assert_eq!(record.status_at(soon), ConsentStatus::Expired);
+        assert!(store.is_granted("credit", "bob").unwrap());
+        assert!(record.to_json().contains("\"status\":\"granted\""));
+
+        assert_eq!(
+            store
+                .grant("credit", "bob", Some(1), "portal", "req-4")
+                .unwrap_err()
+                .msg,
+            "consent_expiry_past"
+        );
+        assert_eq!(
+            store
+                .revoke("credit", "bob", "portal", " ")
+                .unwrap_err()
+                .msg,
+            "consent_request_id_missing"
+        );
+
+        let raw = fs::read_to_string(&path).unwrap();
+        assert_eq!(raw.lines().count(), 3);
+        assert!(!raw.contains("alice"));
+        assert!(raw.contains(&subject_hash(KEY, "alice")));
+        assert!(!raw.contains(&to_hex(&crate::common::sha256::sha256(b"alice"))));
+
+        let keyless = FileConsentStore::at(path.clone(), None);
+        assert_eq!(
+            keyless.is_granted("credit", "bob").unwrap_err().msg,
+            "subject_key_missing"
+        );
+    }
+}
@@ -0,0 +1,296 @@
+//! Consent answered by a caller-provided lookup, e.g. PHP querying PostgreSQL.
+//!
+//! Answers are cached per `(subject hash, purpose)` for the configured TTL.
+//! Once an entry is older than the TTL the lookup is asked again; if that
+//! fails, the cached answer is still used while it is at most 24 hours old
+//! (the Gate 0 limit). Beyond that the store fails closed and reports no
+//! consent.
+//!
+//! The cache holds at most [`MAX_CACHE_ENTRIES`] answers; when it is full,
+//! answers too old to serve even as a fallback are dropped first, then the
+//! oldest answer.
+//!
+//! The lookup receives the keyed subject hash ([`subject_hash`]), never the
+//! raw subject ID.
+//! Inference, batches included, calls it on the calling thread, so a host
+//! callback (PHP FFI) is never entered from a worker thread; calls are
+//! serialised.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+
+use crate::common::error::{DeltaError, DeltaResult};
+use crate::common::log;
+use crate::common::time;
+
+use super::consent::subject_hash;
+use super::domain::ConsentStore;
+
+/// Oldest cached answer used when the lookup fails.
+pub const MAX_STALE_MS: u128 = 24 * 60 * 60 * 1000;
+
+/// Most `(subject, purpose)` answers kept in the cache.
+pub const MAX_CACHE_ENTRIES: usize = 100_000;
+
+/// Answers `(purpose_id, subject_hash)` with whether consent is granted.
+pub type ConsentLookup = Box<dyn Fn(&str, &str) -> DeltaResult<bool> + Send + Sync>;
+
+/// Cache and lookup counters of a [`CallbackConsentStore`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ConsentLookupStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Failed lookups answered from a stale cache entry.
+    pub stale_hits: u64,
+    /// Failed lookups without a usable cache entry (answered as no consent).
+    pub failures: u64,
+    pub lookups: u64,
+    pub lookup_us_total: u64,
+    pub lookup_us_max: u64,
+}
+
+impl ConsentLookupStats {
+    pub fn to_json(&self) -> String {
+        let avg = self.lookup_us_total.checked_div(self.lookups).unwrap_or(0);
+        format!(
+            "{{\"hits\":{},\"misses\":{},\"stale_hits\":{},\"failures\":{},\"lookups\":{},\"lookup_us_avg\":{},\"lookup_us_max\":{}}}",
+            self.hits,
+            self.misses,
+            self.stale_hits,
+            self.failures,
+            self.lookups,
+            avg,
+            self.lookup_us_max
+        )
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    stale_hits: AtomicU64,
+    failures: AtomicU64,
+    lookups: AtomicU64,
+    lookup_us_total: AtomicU64,
+    lookup_us_max: AtomicU64,
+}
+
+#[derive(Clone, Copy)]
+struct Cached {
+    granted: bool,
+    fetched_ms: u128,
+}
+
+pub struct CallbackConsentStore {
+    lookup: Mutex<ConsentLookup>,
+    key: Vec<u8>,
+    ttl_ms: u128,
+    max_entries: usize,
+    cache: Mutex<HashMap<(String, String), Cached>>,
+    counters: Counters,
+}
+
+impl CallbackConsentStore {
+    /// `ttl_ms` is capped at [`MAX_STALE_MS`]; `key` is the subject key.
+    pub fn new(lookup: ConsentLookup, ttl_ms: u128, key: Vec<u8>) -> Self {
+        Self {
+            lookup: Mutex::new(lookup),
+            key,
+            ttl_ms: ttl_ms.min(MAX_STALE_MS),
+            max_entries: MAX_CACHE_ENTRIES,
+            cache: Mutex::new(HashMap::new()),
+            counters: Counters::default(),
+        }
+    }
+
+    pub fn stats(&self) -> ConsentLookupStats {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        let counters = &self.counters;
+        ConsentLookupStats {
+            hits: load(&counters.hits),
+            misses: load(&counters.misses),
+            stale_hits: load(&counters.stale_hits),
+            failures: load(&counters.failures),
+            lookups: load(&counters.lookups),
+            lookup_us_total: load(&counters.lookup_us_total),
+            lookup_us_max: load(&counters.lookup_us_max),
+        }
+    }
+
+    fn is_granted_at(&self, purpose_id: &str, subject_id: &str, now_ms: u128) -> DeltaResult<bool> {
+        let key = (subject_hash(&self.key, subject_id), purpose_id.to_string());
+        let cached = self.lock_cache()?.get(&key).copied();
+        if let Some(entry) =
+            cached.filter(|entry| now_ms.saturating_sub(entry.fetched_ms) < self.ttl_ms)
+        {
+            self.counters.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(entry.granted);
+        }
+        self.counters.misses.fetch_add(1, Ordering::Relaxed);
+
+        match self.ask(purpose_id, &key.0) {
+            Ok(granted) => {
+                let entry = Cached {
+                    granted,
+                    fetched_ms: now_ms,
+                };
+                self.remember(key, entry, now_ms)?;
+                Ok(granted)
+            }
+            Err(err) => match cached
+                .filter(|entry| now_ms.saturating_sub(entry.fetched_ms) <= MAX_STALE_MS)
+            {
+                Some(entry) => {
+                    self.counters.stale_hits.fetch_add(1, Ordering::Relaxed);
+                    log::log_json(
+                        "warn",
+                        "consent",
+                        "consent_lookup_stale",
+                        err.code as u32,
+                        0,
+                    );
+                    Ok(entry.granted)
+                }
+                None => {
+                    self.counters.failures.fetch_add(1, Ordering::Relaxed);
+                    log::log_json(
+                        "error",
+                        "consent",
+                        "consent_lookup_failed",
+                        err.code as u32,
+                        0,
+                    );
+                    Ok(false)
+                }
+            },
+        }
+    }
+
+    fn remember(&self, key: (String, String), entry: Cached, now_ms: u128) -> DeltaResult<()> {
+        let mut cache = self.lock_cache()?;
+        if cache.len() >= self.max_entries && !cache.contains_key(&key) {
+            cache.retain(|_, cached| now_ms.saturating_sub(cached.fetched_ms) <= MAX_STALE_MS);
+            if cache.len() >= self.max_entries {
+                let oldest = cache
+                    .iter()
+                    .min_by_key(|(_, cached)| cached.fetched_ms)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    cache.remove(&oldest);
+                }
+            }
+        }
+        cache.insert(key, entry);
+        Ok(())
+    }
+
+    fn ask(&self, purpose_id: &str, subject_hash: &str) -> DeltaResult<bool> {
+        let lookup = self
+            .lookup
+            .lock()
+            .map_err(|_| DeltaError::internal("consent_lookup_poisoned"))?;
+        let start = Instant::now();
+        let answer = lookup(purpose_id, subject_hash);
+        let elapsed = start.elapsed().as_micros() as u64;
+        self.counters.lookups.fetch_add(1, Ordering::Relaxed);
+        self.counters
+            .lookup_us_total
+            .fetch_add(elapsed, Ordering::Relaxed);
+        self.counters
+            .lookup_us_max
+            .fetch_max(elapsed, Ordering::Relaxed);
+        answer
+    }
+
+    fn lock_cache(&self) -> DeltaResult<MutexGuard<'_, HashMap<(String, String), Cached>>> {
+        self.cache
+            .lock()
+            .map_err(|_| DeltaError::internal("consent_cache_poisoned"))
+    }
+}
+
+impl ConsentStore for CallbackConsentStore {
+    fn is_granted(&self, purpose_id: &str, subject_id: &str) -> DeltaResult<bool> {
+        self.is_granted_at(purpose_id, subject_id, time::now_ms())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    #[test]
+    fn answers_are_cached_for_the_ttl_and_fail_closed_when_too_stale() {
+        let online = Arc::new(AtomicBool::new(true));
+        let calls = Arc::new(AtomicU64::new(0));
+        let (flag, counter) = (online.clone(), calls.clone());
+        let store = CallbackConsentStore::new(
+            Box::new(move |purpose, subject| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                assert_eq!(subject, subject_hash(b"key", "dave"));
+                if flag.load(Ordering::SeqCst) {
+                    Ok(purpose == "credit")
+                } else {
+                    Err(DeltaError::internal("consent_lookup_unavailable"))
+                }
+            }),
+            60_000,
+            b"key".to_vec(),
+        );
+
+        let start = 1_000_000;
+        assert!(store.is_granted_at("credit", "dave", start).unwrap());
+        assert!(!store.is_granted_at("marketing", "dave", start).unwrap());
+        assert!(store
+            .is_granted_at("credit", "dave", start + 59_999)
+            .unwrap());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Past the TTL the lookup is asked again; while it is down the
+        // cached answer serves for up to 24 hours.
+        online.store(false, Ordering::SeqCst);
+        assert!(store
+            .is_granted_at("credit", "dave", start + 60_000)
+            .unwrap());
+        assert!(store
+            .is_granted_at("credit", "dave", start + MAX_STALE_MS)
+            .unwrap());
+        assert!(!store
+            .is_granted_at("credit", "dave", start + MAX_STALE_MS + 1)
+            .unwrap());
+        assert!(!store.is_granted_at("research", "dave", start).unwrap());
+
+        let stats = store.stats();
+        assert_eq!((stats.hits, stats.misses), (1, 6));
+        assert_eq!((stats.stale_hits, stats.failures, stats.lookups), (2, 2, 6));
+        assert!(stats.to_json().contains("\"lookup_us_avg\":"));
+    }
+
+    #[test]
+    fn the_cache_stays_bounded() {
+        let mut store =
+            CallbackConsentStore::new(Box::new(|_, _| Ok(true)), 60_000, b"key".to_vec());
+        store.max_entries = 2;
+        let start = 1_000_000;
+        store.is_granted_at("credit", "a", start).unwrap();
+        store.is_granted_at("credit", "b", start + 1).unwrap();
+        store.is_granted_at("credit", "c", start + 2).unwrap();
+        assert_eq!(store.lock_cache().unwrap().len(), 2);
+
+        // `a` was the oldest answer and had to make room; `b` is still cached.
+        store.is_granted_at("credit", "b", start + 3).unwrap();
+        store.is_granted_at("credit", "a", start + 4).unwrap();
+        let stats = store.stats();
+        assert_eq!((stats.hits, stats.lookups), (1, 4));
+
+        // Answers past the stale limit are dropped before fresh ones.
+        let later = start + MAX_STALE_MS + 10;
+        store.is_granted_at("credit", "d", later).unwrap();
+        assert_eq!(store.lock_cache().unwrap().len(), 1);
+    }
+}
@@ -4,8 +4,10 @@
 //! utilities provided in `common::json`.
 
 use crate::common::error::{DeltaError, DeltaResult};
-use crate::common::json;
-use crate::training::domain::{ModelKind, ModelVersion};
+use crate::common::json::{self, Value};
+use crate::data::domain::DatasetId;
+use crate::training::domain::{ModelId, ModelKind, ModelVersion, VersionName};
+use crate::training::features::FieldIssue;
 
 /// Result of a single inference call, including WhyLog metadata for auditing.
 #[derive(Clone, Debug)]
@@ -14,6 +16,111 @@ pub struct Prediction {
     pub latency_ms: u32,
     pub confidence: f32,
     pub whylog: WhyLog,
+    /// Review ticket when the decision was held back for a human; `json`
+    /// then carries `"ok":false`, `HitlRequired` and
+    /// `"status":"needs_human_review"` instead of the output.
+    pub review_id: Option<String>,
+}
+
+/// Failed inference call. For `InvalidInput` caused by the record itself,
+/// `fields` names every offending input field.
+#[derive(Clone, Debug)]
+pub struct InferError {
+    pub error: DeltaError,
+    pub fields: Vec<FieldIssue>,
+}
+
+impl InferError {
+    /// `InvalidInput` for the given fields, coded after the first of them.
+    pub fn fields(fields: Vec<FieldIssue>) -> Self {
+        let code = fields.first().map_or("input_invalid", |issue| issue.code);
+        Self {
+            error: DeltaError::invalid(code),
+            fields,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let fields = self
+            .fields
+            .iter()
+            .map(FieldIssue::to_json)
+            .collect::<Vec<_>>();
+        format!(
+            "{{\"ok\":false,\"code\":{},\"msg\":\"{}\",\"fields\":[{}]}}",
+            self.error.code as u32,
+            self.error.msg,
+            fields.join(",")
+        )
+    }
+}
+
+impl From<DeltaError> for InferError {
+    fn from(error: DeltaError) -> Self {
+        Self {
+            error,
+            fields: Vec::new(),
+        }
+    }
+}
+
+/// Upper bound on the number of items in one batch call.
+pub const MAX_BATCH_ITEMS: usize = 10_000;
+
+/// Parse a batch request `[{"subject_id": "...", "input": {...}}, ...]` into
+/// `(subject, input JSON)` pairs.
+pub fn parse_batch(raw: &str) -> DeltaResult<Vec<(String, String)>> {
+    let doc = json::parse(raw).map_err(|_| DeltaError::invalid("batch_invalid"))?;
+    let items = doc.as_array().ok_or(DeltaError::invalid("batch_invalid"))?;
+    if items.len() > MAX_BATCH_ITEMS {
+        return Err(DeltaError::invalid("batch_too_large"));
+    }
+    items
+        .iter()
+        .map(|item| {
+            let subject = item.get("subject_id").and_then(Value::as_str);
+            let input = item
+                .get("input")
+                .filter(|input| input.as_object().is_some());
+            match (subject, input) {
+                (Some(subject), Some(input)) => Ok((subject.to_string(), input.canonical())),
+                _ => Err(DeltaError::invalid("batch_item_invalid")),
+            }
+        })
+        .collect()
+}
+
+/// `{"ok":true,"results":[...]}` with each item's prediction or error, in input order.
+pub fn batch_to_json(results: &[Result<Prediction, InferError>]) -> String {
+    let items = results
+        .iter()
+        .map(|result| match result {
+            Ok(prediction) => prediction.json.clone(),
+            Err(err) => err.to_json(),
+        })
+        .collect::<Vec<_>>();
+    format!("{{\"ok\":true,\"results\":[{}]}}", items.join(","))
+}
+
+/// Outcome of scoring a stored dataset into a new one.
+#[derive(Clone, Debug)]
+pub struct ScoreReport {
+    pub dataset_id: DatasetId,
+    pub source: DatasetId,
+    pub rows: usize,
+    pub failed: usize,
+}
+
+impl ScoreReport {
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"ok\":true,\"dataset_id\":\"{}\",\"source\":\"{}\",\"rows\":{},\"failed\":{}}}",
+            json::escape(self.dataset_id.as_str()),
+            json::escape(self.source.as_str()),
+            self.rows,
+            self.failed
+        )
+    }
 }
 
 /// Lightweight WhyLog representation tracking saliency and canonical hash.
@@ -49,30 +156,52 @@ impl InferenceContext {
 /// Router input summarising the request payload.
 #[derive(Clone, Debug, Default)]
 pub struct RouterContext {
+    pub purpose_id: String,
+    /// Top-level fields present in the input.
+    pub fields: Vec<String>,
     pub features_only: bool,
+    /// Set by `"agent": true` at the top level or under `context`.
+    pub agent: bool,
     pub text_length: usize,
+    /// Members of `context.subject`, e.g. `{"segment": "b2b"}`.
+    pub subject: Vec<(String, Value)>,
 }
 
 impl RouterContext {
     pub fn from_payload(payload: &str, ctx: &InferenceContext) -> Self {
-        let text_length = json::extract_string(payload, "text")
-            .map(|s| s.chars().count())
-            .unwrap_or(0);
-
-        let input_flag = json::extract_bool(payload, "features_only").unwrap_or(false);
-        let context_flag = json::extract_object(payload, "context")
-            .and_then(|section| json::extract_bool(section, "features_only"))
-            .unwrap_or(false);
+        let doc = json::parse(payload).unwrap_or(Value::Null);
+        let context = doc.get("context");
+        let flag = |key: &str| {
+            doc.get(key).and_then(Value::as_bool).unwrap_or(false)
+                || context
+                    .and_then(|context| context.get(key))
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false)
+        };
 
         Self {
-            features_only: ctx.features_only || input_flag || context_flag,
-            text_length,
+            purpose_id: ctx.purpose_id.clone(),
+            fields: doc
+                .as_object()
+                .map(|members| members.iter().map(|(key, _)| key.clone()).collect())
+                .unwrap_or_default(),
+            features_only: ctx.features_only || flag("features_only"),
+            agent: flag("agent"),
+            text_length: doc
+                .get("text")
+                .and_then(Value::as_str)
+                .map_or(0, |text| text.chars().count()),
+            subject: context
+                .and_then(|context| context.get("subject"))
+                .and_then(Value::as_object)
+                .map(<[_]>::to_vec)
+                .unwrap_or_default(),
         }
     }
 }
 
 /// Target model family selected by the router.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 pub enum RouteTarget {
     Tabular,
     Text,
@@ -87,84 +216,36 @@ impl RouteTarget {
     }
 }
 
-/// Reason the router picked a particular target.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub enum RouteReason {
-    FeatureOverride,
-    LongText,
-    DefaultTabular,
-}
-
-impl RouteReason {
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            RouteReason::FeatureOverride => "features_only",
-            RouteReason::LongText => "long_text",
-            RouteReason::DefaultTabular => "default",
-        }
-    }
-}
-
 /// Router decision result.
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct RouteDecision {
+    /// Model family the rule asks for; `None` keeps the family of the model
+    /// serving the purpose.
+    pub target: Option<RouteTarget>,
+    /// Name of the rule that fired, reported as `route_reason`.
+    pub reason: String,
+    /// Specific loaded model the rule routes to; the engine then follows the
+    /// model's family once it is resolved.
+    pub model: Option<(ModelId, Option<VersionName>)>,
+}
+
+/// The route a request actually took, reported as `route` and `route_reason`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Route {
     pub target: RouteTarget,
-    pub reason: RouteReason,
+    pub reason: String,
 }
 
-/// Router trait for SSM-style deterministic routing.
+/// Router trait for deterministic routing.
 pub trait ModelRouter {
     fn route(&self, ctx: &RouterContext) -> RouteDecision;
 }
 
-/// Single-state-machine router implementing the documented heuristics.
-#[derive(Default)]
-pub struct SSMRouter;
-
-impl SSMRouter {
-    pub fn new() -> Self {
-        Self
-    }
-}
-
-impl ModelRouter for SSMRouter {
-    fn route(&self, ctx: &RouterContext) -> RouteDecision {
-        if ctx.features_only {
-            return RouteDecision {
-                target: RouteTarget::Tabular,
-                reason: RouteReason::FeatureOverride,
-            };
-        }
-
-        if ctx.text_length > 256 {
-            return RouteDecision {
-                target: RouteTarget::Text,
-                reason: RouteReason::LongText,
-            };
-        }
-
-        RouteDecision {
-            target: RouteTarget::Tabular,
-            reason: RouteReason::DefaultTabular,
-        }
-    }
-}
-
 /// Interface for consent lookups.
 pub trait ConsentStore: Send + Sync {
     fn is_granted(&self, purpose_id: &str, subject_id: &str) -> DeltaResult<bool>;
 }
 
-/// Allow-all consent store placeholder until real storage is wired in.
-#[derive(Default)]
-pub struct AllowAllConsent;
-
-impl ConsentStore for AllowAllConsent {
-    fn is_granted(&self, _: &str, _: &str) -> DeltaResult<bool> {
-        Ok(true)
-    }
-}
-
 /// Engine response prior to final packaging into a prediction.
 #[derive(Clone, Debug)]
 pub struct EngineResponse {
@@ -177,7 +258,7 @@ pub struct EngineResponse {
 /// Engine abstraction to decouple service orchestration from concrete implementations.
 pub trait InferEngine {
     fn kind(&self) -> RouteTarget;
-    fn infer(&self, model: &ModelVersion, input: &str) -> DeltaResult<EngineResponse>;
+    fn infer(&self, model: &ModelVersion, input: &str) -> Result<EngineResponse, InferError>;
 }
 
 /// Helper to map model kind to router targets for verification.
@@ -188,13 +269,13 @@ pub fn route_target_for_model(kind: ModelKind) -> RouteTarget {
     }
 }
 
-/// Ensure the selected route matches the model family, otherwise return a fallback target.
-pub fn validate_route(model: &ModelVersion, decision: RouteDecision) -> RouteTarget {
-    let expected = route_target_for_model(model.kind);
-    if expected == decision.target {
-        decision.target
+/// Ensure `model` can be scored by the `target` engine; a text artefact
+/// cannot be read by the tabular engine or the other way around.
+pub fn ensure_compatible(model: &ModelVersion, target: RouteTarget) -> DeltaResult<()> {
+    if route_target_for_model(model.kind) == target {
+        Ok(())
     } else {
-        RouteTarget::Tabular
+        Err(DeltaError::model_missing("route_family_mismatch"))
     }
 }
 
@@ -211,15 +292,6 @@ pub fn build_context(purpose_id: &str, subject_id: &str, input: &str) -> Inferen
     )
 }
 
-/// Validate the active model matches the router decision or provide a fallback decision.
-pub fn ensure_compatible(model: &ModelVersion, decision: RouteDecision) -> RouteDecision {
-    let target = validate_route(model, decision);
-    RouteDecision {
-        target,
-        reason: decision.reason,
-    }
-}
-
 /// Utility to check consent and map the result to an error.
 pub fn ensure_consent(store: &dyn ConsentStore, ctx: &InferenceContext) -> DeltaResult<()> {
     if store.is_granted(&ctx.purpose_id, &ctx.subject_id)? {
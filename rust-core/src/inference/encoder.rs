@@ -0,0 +1,529 @@
+//! CPU transformer encoder for MiniLM-style text models.
+//!
+//! Runs a BERT-architecture (post-layer-norm) encoder from the `TNSR` section
+//! of a model artefact. Quantised tensors are dequantised to f32 once when the
+//! encoder is built. Expected tensor names, with linear weights stored as
+//! `[out, in]`:
+//!
+//! ```text
+//! embeddings.word [vocab, hidden]      embeddings.position [max_positions, hidden]
+//! embeddings.token_type [types, hidden] (optional)
+//! embeddings.ln.gamma / embeddings.ln.beta [hidden]
+//! layer.{i}.attn.{q,k,v,out}.weight [hidden, hidden] / .bias [hidden]
+//! layer.{i}.attn.ln.gamma / .beta [hidden]
+//! layer.{i}.ffn.up.weight [intermediate, hidden] / .bias [intermediate]
+//! layer.{i}.ffn.down.weight [hidden, intermediate] / .bias [hidden]
+//! layer.{i}.ffn.ln.gamma / .beta [hidden]
+//! head.weight [labels, hidden] / head.bias [labels] (optional)
+//! ```
+//!
+//! The attention head count comes from the `encoder.heads` metadata entry and
+//! the layer-norm epsilon from `encoder.layer_norm_eps` (default `1e-12`).
+//! Token states are mean-pooled into the sentence embedding.
+
+use crate::common::error::{DeltaError, DeltaResult};
+use crate::training::artefact::{corrupt, ModelArtefact};
+
+const DEFAULT_LAYER_NORM_EPS: f32 = 1e-12;
+
+struct Linear {
+    weight: Vec<f32>,
+    bias: Vec<f32>,
+    inputs: usize,
+    outputs: usize,
+}
+
+impl Linear {
+    fn load(
+        artefact: &ModelArtefact,
+        prefix: &str,
+        outputs: usize,
+        inputs: usize,
+    ) -> DeltaResult<Self> {
+        Ok(Self {
+            weight: tensor(artefact, &format!("{prefix}.weight"), &[outputs, inputs])?,
+            bias: tensor(artefact, &format!("{prefix}.bias"), &[outputs])?,
+            inputs,
+            outputs,
+        })
+    }
+
+    fn apply(&self, x: &[f32]) -> Vec<f32> {
+        (0..self.outputs)
+            .map(|o| {
+                let row = &self.weight[o * self.inputs..(o + 1) * self.inputs];
+                self.bias[o] + row.iter().zip(x).map(|(w, v)| w * v).sum::<f32>()
+            })
+            .collect()
+    }
+}
+
+struct LayerNorm {
+    gamma: Vec<f32>,
+    beta: Vec<f32>,
+    eps: f32,
+}
+
+impl LayerNorm {
+    fn load(artefact: &ModelArtefact, prefix: &str, hidden: usize, eps: f32) -> DeltaResult<Self> {
+        Ok(Self {
+            gamma: tensor(artefact, &format!("{prefix}.gamma"), &[hidden])?,
+            beta: tensor(artefact, &format!("{prefix}.beta"), &[hidden])?,
+            eps,
+        })
+    }
+
+    fn apply(&self, x: &mut [f32]) {
+        let n = x.len() as f32;
+        let mean = x.iter().sum::<f32>() / n;
+        let var = x.iter().map(|v| (v - mean) * (v - mean)).sum::<f32>() / n;
+        let inv = 1.0 / (var + self.eps).sqrt();
+        for (i, v) in x.iter_mut().enumerate() {
+            *v = (*v - mean) * inv * self.gamma[i] + self.beta[i];
+        }
+    }
+}
+
+struct Layer {
+    q: Linear,
+    k: Linear,
+    v: Linear,
+    out: Linear,
+    attn_ln: LayerNorm,
+    up: Linear,
+    down: Linear,
+    ffn_ln: LayerNorm,
+}
+
+/// Shape summary of a loaded encoder.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct EncoderConfig {
+    pub vocab_size: usize,
+    pub hidden: usize,
+    pub layers: usize,
+    pub heads: usize,
+    pub intermediate: usize,
+    pub max_positions: usize,
+    /// Outputs of the classification head; zero when the artefact has none.
+    pub labels: usize,
+}
+
+/// Transformer encoder with an optional linear classification head.
+pub struct Encoder {
+    config: EncoderConfig,
+    word: Vec<f32>,
+    position: Vec<f32>,
+    token_type: Option<Vec<f32>>,
+    embed_ln: LayerNorm,
+    layers: Vec<Layer>,
+    head: Option<Linear>,
+}
+
+impl Encoder {
+    /// Build the encoder from an artefact's tensors; shape mismatches are reported as corrupt.
+    pub fn from_artefact(artefact: &ModelArtefact) -> DeltaResult<Self> {
+        let word_tensor = artefact
+            .tensor("embeddings.word")
+            .ok_or_else(|| DeltaError::model_missing("encoder_weights"))?;
+        let (vocab_size, hidden) = match word_tensor.shape[..] {
+            [vocab, hidden] if vocab > 0 && hidden > 0 => (vocab, hidden),
+            _ => return Err(corrupt("artefact_tensor_shape")),
+        };
+        let heads = match artefact.meta("encoder.heads") {
+            Some(raw) => raw
+                .parse::<usize>()
+                .ok()
+                .filter(|h| *h > 0 && hidden % h == 0)
+                .ok_or_else(|| corrupt("artefact_metadata_invalid"))?,
+            None => return Err(corrupt("artefact_metadata_missing")),
+        };
+        let eps = match artefact.meta("encoder.layer_norm_eps") {
+            Some(raw) => raw
+                .parse::<f32>()
+                .ok()
+                .filter(|e| *e > 0.0)
+                .ok_or_else(|| corrupt("artefact_metadata_invalid"))?,
+            None => DEFAULT_LAYER_NORM_EPS,
+        };
+        let max_positions = match artefact.tensor("embeddings.position").map(|t| &t.shape[..]) {
+            Some([positions, h]) if *h == hidden && *positions > 0 => *positions,
+            Some(_) => return Err(corrupt("artefact_tensor_shape")),
+            None => return Err(corrupt("artefact_tensor_missing")),
+        };
+        let token_type = match artefact.tensor("embeddings.token_type") {
+            Some(t) if t.shape.len() == 2 && t.shape[0] > 0 && t.shape[1] == hidden => {
+                Some(t.to_f32()[..hidden].to_vec())
+            }
+            Some(_) => return Err(corrupt("artefact_tensor_shape")),
+            None => None,
+        };
+        let intermediate = match artefact
+            .tensor("layer.0.ffn.up.weight")
+            .map(|t| &t.shape[..])
+        {
+            Some([inter, h]) if *h == hidden && *inter > 0 => *inter,
+            Some(_) => return Err(corrupt("artefact_tensor_shape")),
+            None => return Err(corrupt("artefact_tensor_missing")),
+        };
+
+        let mut layers = Vec::new();
+        while artefact
+            .tensor(&format!("layer.{}.attn.q.weight", layers.len()))
+            .is_some()
+        {
+            let p = format!("layer.{}", layers.len());
+            layers.push(Layer {
+                q: Linear::load(artefact, &format!("{p}.attn.q"), hidden, hidden)?,
+                k: Linear::load(artefact, &format!("{p}.attn.k"), hidden, hidden)?,
+                v: Linear::load(artefact, &format!("{p}.attn.v"), hidden, hidden)?,
+                out: Linear::load(artefact, &format!("{p}.attn.out"), hidden, hidden)?,
+                attn_ln: LayerNorm::load(artefact, &format!("{p}.attn.ln"), hidden, eps)?,
+                up: Linear::load(artefact, &format!("{p}.ffn.up"), intermediate, hidden)?,
+                down: Linear::load(artefact, &format!("{p}.ffn.down"), hidden, intermediate)?,
+                ffn_ln: LayerNorm::load(artefact, &format!("{p}.ffn.ln"), hidden, eps)?,
+            });
+        }
+        if layers.is_empty() {
+            return Err(corrupt("artefact_tensor_missing"));
+        }
+
+        let head = match artefact.tensor("head.weight").map(|t| &t.shape[..]) {
+            Some([labels, h]) if *h == hidden && *labels > 0 => {
+                Some(Linear::load(artefact, "head", *labels, hidden)?)
+            }
+            Some(_) => return Err(corrupt("artefact_tensor_shape")),
+            None => None,
+        };
+
+        Ok(Self {
+            config: EncoderConfig {
+                vocab_size,
+                hidden,
+                layers: layers.len(),
+                heads,
+                intermediate,
+                max_positions,
+                labels: head.as_ref().map_or(0, |h| h.outputs),
+            },
+            word: word_tensor.to_f32(),
+            position: tensor(artefact, "embeddings.position", &[max_positions, hidden])?,
+            token_type,
+            embed_ln: LayerNorm::load(artefact, "embeddings.ln", hidden, eps)?,
+            layers,
+            head,
+        })
+    }
+
+    pub fn config(&self) -> EncoderConfig {
+        self.config
+    }
+
+    /// Final hidden state of every token.
+    pub fn token_states(&self, ids: &[u32]) -> DeltaResult<Vec<Vec<f32>>> {
+        let hidden = self.config.hidden;
+        if ids.is_empty() || ids.len() > self.config.max_positions {
+            return Err(DeltaError::invalid("encoder_sequence_length"));
+        }
+        let mut states = ids
+            .iter()
+            .enumerate()
+            .map(|(position, id)| {
+                let id = *id as usize;
+                if id >= self.config.vocab_size {
+                    return Err(DeltaError::invalid("encoder_token_out_of_range"));
+                }
+                let word = &self.word[id * hidden..(id + 1) * hidden];
+                let pos = &self.position[position * hidden..(position + 1) * hidden];
+                let mut state = word.iter().zip(pos).map(|(w, p)| w + p).collect::<Vec<_>>();
+                if let Some(token_type) = &self.token_type {
+                    state.iter_mut().zip(token_type).for_each(|(s, t)| *s += t);
+                }
+                self.embed_ln.apply(&mut state);
+                Ok(state)
+            })
+            .collect::<DeltaResult<Vec<_>>>()?;
+
+        for layer in &self.layers {
+            let attended = self.attention(layer, &states);
+            for (state, delta) in states.iter_mut().zip(attended) {
+                state.iter_mut().zip(delta).for_each(|(s, d)| *s += d);
+                layer.attn_ln.apply(state);
+            }
+            for state in states.iter_mut() {
+                let up = layer
+                    .up
+                    .apply(state)
+                    .into_iter()
+                    .map(gelu)
+                    .collect::<Vec<_>>();
+                let down = layer.down.apply(&up);
+                state.iter_mut().zip(down).for_each(|(s, d)| *s += d);
+                layer.ffn_ln.apply(state);
+            }
+        }
+        Ok(states)
+    }
+
+    fn attention(&self, layer: &Layer, states: &[Vec<f32>]) -> Vec<Vec<f32>> {
+        let hidden = self.config.hidden;
+        let head_dim = hidden / self.config.heads;
+        let scale = 1.0 / (head_dim as f32).sqrt();
+        let q = states.iter().map(|s| layer.q.apply(s)).collect::<Vec<_>>();
+        let k = states.iter().map(|s| layer.k.apply(s)).collect::<Vec<_>>();
+        let v = states.iter().map(|s| layer.v.apply(s)).collect::<Vec<_>>();
+
+        let mut context = vec![vec![0.0f32; hidden]; states.len()];
+        for head in 0..self.config.heads {
+            let range = head * head_dim..(head + 1) * head_dim;
+            for (t, ctx) in context.iter_mut().enumerate() {
+                let scores = k
+                    .iter()
+                    .map(|key| {
+                        q[t][range.clone()]
+                            .iter()
+                            .zip(&key[range.clone()])
+                            .map(|(a, b)| a * b)
+                            .sum::<f32>()
+                            * scale
+                    })
+                    .collect::<Vec<_>>();
+                let weights = softmax(&scores);
+                for (weight, value) in weights.iter().zip(&v) {
+                    for d in range.clone() {
+                        ctx[d] += weight * value[d];
+                    }
+                }
+            }
+        }
+        context.iter().map(|c| layer.out.apply(c)).collect()
+    }
+
+    /// Mean-pooled sentence embedding.
+    pub fn embed(&self, ids: &[u32]) -> DeltaResult<Vec<f32>> {
+        Ok(mean_pool(&self.token_states(ids)?))
+    }
+
+    /// Head probabilities plus each token's share of the top logit.
+    ///
+    /// Single-output heads use a sigmoid, wider heads a softmax. Because mean
+    /// pooling and the head are linear, the per-token contributions sum to the
+    /// pre-bias logit of the predicted class.
+    pub fn classify(&self, ids: &[u32]) -> DeltaResult<Classification> {
+        let head = self
+            .head
+            .as_ref()
+            .ok_or_else(|| DeltaError::model_missing("encoder_head"))?;
+        let states = self.token_states(ids)?;
+        let logits = head.apply(&mean_pool(&states));
+        let probabilities = if logits.len() == 1 {
+            vec![sigmoid(logits[0])]
+        } else {
+            softmax(&logits)
+        };
+        let label = if probabilities.len() == 1 {
+            0
+        } else {
+            (0..probabilities.len())
+                .max_by(|a, b| probabilities[*a].total_cmp(&probabilities[*b]))
+                .unwrap_or(0)
+        };
+        let row = &head.weight[label * head.inputs..(label + 1) * head.inputs];
+        let contributions = states
+            .iter()
+            .map(|state| {
+                state.iter().zip(row).map(|(s, w)| s * w).sum::<f32>() / states.len() as f32
+            })
+            .collect();
+        Ok(Classification {
+            probabilities,
+            label,
+            contributions,
+        })
+    }
+}
+
+/// Output of [`Encoder::classify`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Classification {
+    pub probabilities: Vec<f32>,
+    /// Index of the predicted class (always 0 for single-output heads).
+    pub label: usize,
+    /// Signed contribution of each input token to the predicted class logit.
+    pub contributions: Vec<f32>,
+}
+
+impl Classification {
+    /// Probability of the positive class for binary heads, of the predicted class otherwise.
+    pub fn score(&self) -> f32 {
+        match self.probabilities.len() {
+            1 => self.probabilities[0],
+            2 => self.probabilities[1],
+            _ => self.probabilities[self.label],
+        }
+    }
+
+    /// Probability of the predicted class.
+    pub fn confidence(&self) -> f32 {
+        match self.probabilities.len() {
+            1 => self.probabilities[0].max(1.0 - self.probabilities[0]),
+            _ => self.probabilities[self.label],
+        }
+    }
+}
+
+fn tensor(artefact: &ModelArtefact, name: &str, shape: &[usize]) -> DeltaResult<Vec<f32>> {
+    match artefact.tensor(name) {
+        Some(t) if t.shape == shape => Ok(t.to_f32()),
+        Some(_) => Err(corrupt("artefact_tensor_shape")),
+        None => Err(corrupt("artefact_tensor_missing")),
+    }
+}
+
+fn mean_pool(states: &[Vec<f32>]) -> Vec<f32> {
+    let mut pooled = vec![0.0f32; states.first().map_or(0, Vec::len)];
+    for state in states {
+        pooled.iter_mut().zip(state).for_each(|(p, s)| *p += s);
+    }
+    let n = states.len().max(1) as f32;
+    pooled.iter_mut().for_each(|p| *p /= n);
+    pooled
+}
+
+fn softmax(x: &[f32]) -> Vec<f32> {
+    let max = x.iter().fold(f32::NEG_INFINITY, |m, v| m.max(*v));
+    let exp = x.iter().map(|v| (v - max).exp()).collect::<Vec<_>>();
+    let sum = exp.iter().sum::<f32>();
+    exp.into_iter().map(|e| e / sum).collect()
+}
+
+/// Tanh approximation of GELU, as used by the original BERT release.
+fn gelu(x: f32) -> f32 {
+    const SQRT_2_OVER_PI: f32 = 0.797_884_6;
+    0.5 * x * (1.0 + (SQRT_2_OVER_PI * (x + 0.044_715 * x * x * x)).tanh())
+}
+
+fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+/// Deterministic toy encoder used by tests across the crate.
+#[cfg(test)]
+pub(crate) fn test_artefact(vocab: usize, labels: usize) -> ModelArtefact {
+    use crate::training::domain::ModelKind;
+    use crate::training::tensor::Tensor;
+
+    let (hidden, intermediate, positions) = (8, 16, 32);
+    let mut seed = 7u32;
+    let mut values = |n: usize| {
+        (0..n)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                ((seed >> 16) % 2000) as f32 / 2000.0 - 0.5
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut artefact = ModelArtefact::new(ModelKind::TextMiniLm);
+    let mut push = |name: &str, shape: Vec<usize>, data: Vec<f32>| {
+        artefact.tensors.push(Tensor::f32(name, shape, data))
+    };
+    push(
+        "embeddings.word",
+        vec![vocab, hidden],
+        values(vocab * hidden),
+    );
+    push(
+        "embeddings.position",
+        vec![positions, hidden],
+        values(positions * hidden),
+    );
+    push("embeddings.ln.gamma", vec![hidden], vec![1.0; hidden]);
+    push("embeddings.ln.beta", vec![hidden], vec![0.0; hidden]);
+    for layer in 0..2 {
+        for part in ["q", "k", "v", "out"] {
+            let p = format!("layer.{layer}.attn.{part}");
+            push(
+                &format!("{p}.weight"),
+                vec![hidden, hidden],
+                values(hidden * hidden),
+            );
+            push(&format!("{p}.bias"), vec![hidden], values(hidden));
+        }
+        for (part, outputs, inputs) in
+            [("up", intermediate, hidden), ("down", hidden, intermediate)]
+        {
+            let p = format!("layer.{layer}.ffn.{part}");
+            push(
+                &format!("{p}.weight"),
+                vec![outputs, inputs],
+                values(outputs * inputs),
+            );
+            push(&format!("{p}.bias"), vec![outputs], values(outputs));
+        }
+        for ln in ["attn.ln", "ffn.ln"] {
+            push(
+                &format!("layer.{layer}.{ln}.gamma"),
+                vec![hidden],
+                vec![1.0; hidden],
+            );
+            push(
+                &format!("layer.{layer}.{ln}.beta"),
+                vec![hidden],
+                vec![0.0; hidden],
+            );
+        }
+    }
+    if labels > 0 {
+        push("head.weight", vec![labels, hidden], values(labels * hidden));
+        push("head.bias", vec![labels], values(labels));
+    }
+    artefact.set_meta("encoder.heads", "2");
+    artefact
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forward_pass_is_deterministic_and_attributes_tokens() {
+        let encoder = Encoder::from_artefact(&test_artefact(10, 1)).unwrap();
+        assert_eq!(encoder.config().layers, 2);
+        let ids = [2, 5, 6, 3];
+        let first = encoder.classify(&ids).unwrap();
+        assert_eq!(first, encoder.classify(&ids).unwrap());
+        assert!(first.score() > 0.0 && first.score() < 1.0);
+        assert_eq!(first.contributions.len(), ids.len());
+
+        // Rows of a layer-normed state are zero-mean with unit variance.
+        let state = &encoder.token_states(&ids).unwrap()[0];
+        let mean = state.iter().sum::<f32>() / state.len() as f32;
+        assert!(mean.abs() < 1e-4);
+
+        let err = encoder.embed(&[2, 42]).unwrap_err();
+        assert_eq!(err.msg, "encoder_token_out_of_range");
+    }
+
+    #[test]
+    fn quantised_weights_track_the_f32_encoder() {
+        let artefact = test_artefact(10, 2);
+        let reference = Encoder::from_artefact(&artefact).unwrap();
+        let mut int8 = artefact.clone();
+        int8.tensors = int8.tensors.iter().map(|t| t.quantize_int8()).collect();
+        let quantised = Encoder::from_artefact(&int8).unwrap();
+
+        let ids = [2, 4, 7, 3];
+        let a = reference.embed(&ids).unwrap();
+        let b = quantised.embed(&ids).unwrap();
+        let drift = a
+            .iter()
+            .zip(&b)
+            .map(|(x, y)| (x - y).abs())
+            .fold(0.0, f32::max);
+        assert!(drift < 0.1, "int8 drift {drift}");
+
+        let classification = quantised.classify(&ids).unwrap();
+        assert_eq!(classification.probabilities.len(), 2);
+        let total = classification.probabilities.iter().sum::<f32>();
+        assert!((total - 1.0).abs() < 1e-5);
+    }
+}
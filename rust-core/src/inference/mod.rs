@@ -1,10 +1,19 @@
 //! Inference domain providing synchronous and batch prediction APIs.
 //!
-//! TODO: Thread safe caches for model artefacts to reduce repeated disk reads.
 //! TODO: Make worker pool configurable per endpoint once routing rules exist.
 
+pub mod cache;
+pub mod consent;
+pub mod consent_lookup;
 pub mod domain;
+pub mod encoder;
+pub mod pool;
+pub mod purpose;
+pub mod review;
+pub mod risk;
+pub mod router;
 pub mod service;
+pub mod tokenizer;
 pub mod workers;
 
 pub use domain::Prediction;
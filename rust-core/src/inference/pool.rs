@@ -0,0 +1,175 @@
+//! Models loaded for inference, keyed by `(ModelId, VersionName)`.
+//!
+//! One process can serve several products at once: every purpose may name its
+//! own default model, and purposes without a mapping use the fallback model,
+//! which is the one most recently registered as active. Unloading a model
+//! clears the fallback when it pointed there, but keeps purpose mappings, so
+//! requests for that purpose fail with `purpose_model_not_loaded` instead of
+//! silently switching to another product's model.
+//!
+//! Models of both families can be loaded together; each is scored by the
+//! engine of its own family, so text and tabular products share a process.
+
+use std::collections::HashMap;
+
+use crate::common::error::{DeltaError, DeltaResult};
+use crate::training::domain::{ModelId, ModelVersion, VersionName};
+
+type Key = (String, String);
+
+fn key(id: &ModelId, version: &VersionName) -> Key {
+    (id.as_str().to_string(), version.as_str().to_string())
+}
+
+#[derive(Debug, Default)]
+pub struct ModelPool {
+    models: HashMap<Key, ModelVersion>,
+    purposes: HashMap<String, Key>,
+    fallback: Option<Key>,
+}
+
+impl ModelPool {
+    /// Add or replace a loaded version.
+    pub fn load(&mut self, model: ModelVersion) {
+        self.models.insert(key(&model.id, &model.version), model);
+    }
+
+    /// Remove a loaded version; returns whether it was loaded.
+    pub fn unload(&mut self, id: &ModelId, version: &VersionName) -> bool {
+        let key = key(id, version);
+        if self.fallback.as_ref() == Some(&key) {
+            self.fallback = None;
+        }
+        self.models.remove(&key).is_some()
+    }
+
+    /// A loaded version; without `version` the newest loaded version of `id`.
+    pub fn get(&self, id: &ModelId, version: Option<&VersionName>) -> Option<ModelVersion> {
+        match version.filter(|version| !version.as_str().is_empty()) {
+            Some(version) => self.models.get(&key(id, version)).cloned(),
+            None => self.newest(|model| model.id == *id),
+        }
+    }
+
+    /// The most recently created loaded version that `accept`s.
+    pub fn newest(&self, accept: impl Fn(&ModelVersion) -> bool) -> Option<ModelVersion> {
+        self.models
+            .values()
+            .filter(|model| accept(model))
+            .max_by(|a, b| {
+                a.created_ms
+                    .cmp(&b.created_ms)
+                    .then_with(|| a.version.as_str().cmp(b.version.as_str()))
+            })
+            .cloned()
+    }
+
+    /// Serve purposes without their own mapping from this loaded version.
+    pub fn set_fallback(&mut self, id: &ModelId, version: &VersionName) -> DeltaResult<()> {
+        let key = self.loaded_key(id, version)?;
+        self.fallback = Some(key);
+        Ok(())
+    }
+
+    /// Serve `purpose_id` from this loaded version.
+    pub fn set_purpose(
+        &mut self,
+        purpose_id: &str,
+        id: &ModelId,
+        version: &VersionName,
+    ) -> DeltaResult<()> {
+        let key = self.loaded_key(id, version)?;
+        self.purposes.insert(purpose_id.to_string(), key);
+        Ok(())
+    }
+
+    /// The model serving `purpose_id`.
+    pub fn for_purpose(&self, purpose_id: &str) -> DeltaResult<ModelVersion> {
+        if let Some(key) = self.purposes.get(purpose_id) {
+            return self
+                .models
+                .get(key)
+                .cloned()
+                .ok_or_else(|| DeltaError::model_missing("purpose_model_not_loaded"));
+        }
+        self.fallback
+            .as_ref()
+            .and_then(|key| self.models.get(key))
+            .cloned()
+            .ok_or_else(|| DeltaError::model_missing("active_model"))
+    }
+
+    /// Every loaded version, ordered by model id and creation time.
+    pub fn loaded(&self) -> Vec<ModelVersion> {
+        let mut models = self.models.values().cloned().collect::<Vec<_>>();
+        models.sort_by(|a, b| {
+            a.id.as_str()
+                .cmp(b.id.as_str())
+                .then(a.created_ms.cmp(&b.created_ms))
+        });
+        models
+    }
+
+    fn loaded_key(&self, id: &ModelId, version: &VersionName) -> DeltaResult<Key> {
+        let key = key(id, version);
+        if self.models.contains_key(&key) {
+            Ok(key)
+        } else {
+            Err(DeltaError::model_missing("model_not_loaded"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::training::domain::ModelKind;
+
+    fn model(id: &str, version: &str, created_ms: u128) -> ModelVersion {
+        ModelVersion {
+            id: ModelId::new(id),
+            version: VersionName::new(version),
+            kind: ModelKind::TabularLogistic,
+            artefact_path: String::new(),
+            created_ms,
+            lifecycle: Default::default(),
+            metadata: Default::default(),
+            checksum: None,
+        }
+    }
+
+    #[test]
+    fn purposes_route_to_their_own_models() {
+        let mut pool = ModelPool::default();
+        pool.load(model("fraud", "v1", 1));
+        pool.load(model("fraud", "v2", 2));
+        pool.load(model("support", "v1", 3));
+        let (fraud, support) = (ModelId::new("fraud"), ModelId::new("support"));
+        let v1 = VersionName::new("v1");
+        assert_eq!(pool.get(&fraud, None).unwrap().version.as_str(), "v2");
+        assert_eq!(pool.for_purpose("any").unwrap_err().msg, "active_model");
+
+        pool.set_fallback(&fraud, &v1).unwrap();
+        pool.set_purpose("helpdesk", &support, &v1).unwrap();
+        assert_eq!(pool.for_purpose("screening").unwrap().id, fraud);
+        assert_eq!(pool.for_purpose("helpdesk").unwrap().id, support);
+        assert_eq!(
+            pool.set_purpose("x", &support, &VersionName::new("v9"))
+                .unwrap_err()
+                .msg,
+            "model_not_loaded"
+        );
+
+        assert!(pool.unload(&support, &v1));
+        assert_eq!(
+            pool.for_purpose("helpdesk").unwrap_err().msg,
+            "purpose_model_not_loaded"
+        );
+        assert!(pool.unload(&fraud, &v1));
+        assert_eq!(
+            pool.for_purpose("screening").unwrap_err().msg,
+            "active_model"
+        );
+        assert_eq!(pool.loaded().len(), 1);
+    }
+}
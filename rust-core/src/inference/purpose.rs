@@ -0,0 +1,216 @@
+//! Registered processing purposes, loaded from `DELTA1_PURPOSES_FILE`
+//! (default `${DATA_ROOT}/purposes.json`):
+//!
+//! ```json
+//! {"purposes": [{"id": "credit", "description": "Kredietbeoordeling",
+//!   "data_categories": ["financial"], "models": ["tabular"], "retention_days": 30}]}
+//! ```
+//!
+//! `models` lists model families (`tabular`, `text`) or model kinds
+//! (`tabular-logreg`, `tabular-gbdt`, `text-minilm`) the purpose may use.
+//! Inference for a purpose that is not registered fails with
+//! `InvalidPurpose`; using a model outside its families is `PolicyDenied`.
+//! Without a registry file no purpose is registered. An optional `"risk"`
+//! block configures human review, see [`super::risk`].
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::common::error::{DeltaError, DeltaResult};
+use crate::common::json::{self, Value};
+use crate::common::log;
+use crate::training::domain::ModelKind;
+
+use super::domain::{route_target_for_model, RouteTarget};
+use super::risk::RiskPolicy;
+
+/// One registered purpose.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Purpose {
+    pub id: String,
+    pub description: String,
+    pub data_categories: Vec<String>,
+    /// Model families or kinds, see the module docs.
+    pub models: Vec<String>,
+    pub retention_days: u32,
+    pub risk: RiskPolicy,
+}
+
+impl Purpose {
+    /// Whether models of `kind` may serve this purpose.
+    pub fn allows(&self, kind: ModelKind) -> bool {
+        let family = route_target_for_model(kind).as_str();
+        self.models
+            .iter()
+            .any(|model| model == family || model == kind.as_str())
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"ok\":true,\"id\":\"{}\",\"description\":\"{}\",\"data_categories\":{},\"models\":{},\"retention_days\":{},\"risk\":{}}}",
+            json::escape(&self.id),
+            json::escape(&self.description),
+            json::build_string_array(&self.data_categories),
+            json::build_string_array(&self.models),
+            self.retention_days,
+            self.risk.to_json()
+        )
+    }
+
+    fn from_value(value: &Value) -> DeltaResult<Self> {
+        let invalid = DeltaError::invalid("purpose_registry_invalid");
+        let text = |key: &str| value.get(key).and_then(Value::as_str).ok_or(invalid);
+        let list = |key: &str| {
+            value
+                .get(key)
+                .and_then(Value::as_array)
+                .ok_or(invalid)?
+                .iter()
+                .map(|item| item.as_str().map(str::to_string).ok_or(invalid))
+                .collect::<DeltaResult<Vec<_>>>()
+        };
+        let id = text("id")?.trim();
+        let models = list("models")?;
+        let known = |model: &String| {
+            model == RouteTarget::Tabular.as_str()
+                || model == RouteTarget::Text.as_str()
+                || ModelKind::from_label(model).is_some()
+        };
+        if id.is_empty() || models.is_empty() || !models.iter().all(known) {
+            return Err(invalid);
+        }
+        let retention_days = value
+            .get("retention_days")
+            .and_then(Value::as_f64)
+            .filter(|days| *days >= 0.0 && days.fract() == 0.0 && *days <= f64::from(u32::MAX))
+            .ok_or(invalid)?;
+        Ok(Self {
+            id: id.to_string(),
+            description: text("description")?.to_string(),
+            data_categories: list("data_categories")?,
+            models,
+            retention_days: retention_days as u32,
+            risk: RiskPolicy::from_value(value.get("risk"))?,
+        })
+    }
+}
+
+/// Purposes by ID.
+#[derive(Clone, Debug, Default)]
+pub struct PurposeRegistry {
+    purposes: HashMap<String, Purpose>,
+}
+
+impl PurposeRegistry {
+    /// Parse a registry document; duplicate IDs are rejected.
+    pub fn from_json(raw: &str) -> DeltaResult<Self> {
+        let invalid = DeltaError::invalid("purpose_registry_invalid");
+        let doc = json::parse(raw).map_err(|_| invalid)?;
+        let entries = doc
+            .get("purposes")
+            .and_then(Value::as_array)
+            .ok_or(invalid)?;
+        let mut purposes = HashMap::new();
+        for entry in entries {
+            let purpose = Purpose::from_value(entry)?;
+            if purposes.insert(purpose.id.clone(), purpose).is_some() {
+                return Err(DeltaError::invalid("purpose_duplicate"));
+            }
+        }
+        Ok(Self { purposes })
+    }
+
+    /// Read the registry file; a missing file yields an empty registry.
+    pub fn load(path: &Path) -> DeltaResult<Self> {
+        match fs::read_to_string(path) {
+            Ok(raw) => Self::from_json(&raw),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                log::log_json("warn", "inference", "purpose_registry_missing", 0, 0);
+                Ok(Self::default())
+            }
+            Err(_) => Err(DeltaError::io()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.purposes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.purposes.is_empty()
+    }
+
+    /// The registered purpose, or `InvalidPurpose`.
+    pub fn get(&self, purpose_id: &str) -> DeltaResult<&Purpose> {
+        self.purposes
+            .get(purpose_id)
+            .ok_or(DeltaError::invalid_purpose("purpose_unknown"))
+    }
+
+    /// Check that `purpose_id` is registered and may use models of `kind`.
+    pub fn check(&self, purpose_id: &str, kind: ModelKind) -> DeltaResult<&Purpose> {
+        let purpose = self.get(purpose_id)?;
+        if purpose.allows(kind) {
+            Ok(purpose)
+        } else {
+            Err(DeltaError::policy_denied("purpose_model_not_allowed"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::error::DeltaCode;
+
+    #[test]
+    fn purposes_are_parsed_and_limit_model_families() {
+        let registry = PurposeRegistry::from_json(
+            "{\"purposes\":[\
+             {\"id\":\"credit\",\"description\":\"Kredietbeoordeling\",\"data_categories\":[\"financial\"],\"models\":[\"tabular\"],\"retention_days\":30},\
+             {\"id\":\"support\",\"description\":\"Klantvragen\",\"data_categories\":[],\"models\":[\"text-minilm\"],\"retention_days\":7,\"risk\":{\"high_below\":0.7,\"review_at\":\"limited\"}}]}",
+        )
+        .unwrap();
+        assert_eq!(registry.len(), 2);
+        let credit = registry
+            .check("credit", ModelKind::TabularGradientBoosting)
+            .unwrap();
+        assert_eq!(credit.retention_days, 30);
+        assert!(credit
+            .to_json()
+            .contains("\"data_categories\":[\"financial\"]"));
+        let support = registry.check("support", ModelKind::TextMiniLm).unwrap();
+        assert_eq!(
+            support.risk.review_at,
+            crate::training::domain::RiskLevel::Limited
+        );
+        assert_eq!(credit.risk, RiskPolicy::default());
+
+        let denied = registry
+            .check("support", ModelKind::TabularLogistic)
+            .unwrap_err();
+        assert_eq!(denied.msg, "purpose_model_not_allowed");
+        let unknown = registry
+            .check("marketing", ModelKind::TabularLogistic)
+            .unwrap_err();
+        assert_eq!(unknown.code as u32, DeltaCode::InvalidPurpose as u32);
+
+        for raw in [
+            "{\"purposes\":[{\"id\":\"x\",\"description\":\"\",\"data_categories\":[],\"models\":[\"image\"],\"retention_days\":1}]}",
+            "{\"purposes\":[{\"id\":\"x\",\"description\":\"\",\"data_categories\":[],\"models\":[\"text\"],\"retention_days\":-1}]}",
+            "{\"purposes\":{}}",
+        ] {
+            assert_eq!(
+                PurposeRegistry::from_json(raw).unwrap_err().msg,
+                "purpose_registry_invalid"
+            );
+        }
+        let twice = "{\"purposes\":[{\"id\":\"x\",\"description\":\"\",\"data_categories\":[],\"models\":[\"text\"],\"retention_days\":1},{\"id\":\"x\",\"description\":\"\",\"data_categories\":[],\"models\":[\"text\"],\"retention_days\":1}]}";
+        assert_eq!(
+            PurposeRegistry::from_json(twice).unwrap_err().msg,
+            "purpose_duplicate"
+        );
+    }
+}
@@ -0,0 +1,399 @@
+//! Human review queue under `${DATA_ROOT}/reviews/reviews.jsonl`.
+//!
+//! Decisions the risk classifier holds back are opened as tickets. Every
+//! change appends the full ticket as one JSON line and the latest line per
+//! `review_id` wins, so the file is also the audit trail linking the human
+//! verdict to the WhyLog hash of the withheld decision. Like the consent
+//! store the file is a [`JsonlLedger`]: lookups first apply lines appended by
+//! other processes, and a verdict is checked and written under an exclusive
+//! file lock, so two workers can never both decide the same ticket.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::common::config::AppCfg;
+use crate::common::error::{DeltaError, DeltaResult};
+use crate::common::json::{self, Value};
+use crate::common::ledger::{JsonlLedger, LedgerEntry};
+use crate::common::log;
+use crate::common::sha256::{sha256, to_hex};
+use crate::common::time;
+use crate::training::domain::RiskLevel;
+
+/// State of a review ticket.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ReviewStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ReviewStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewStatus::Pending => "pending",
+            ReviewStatus::Approved => "approved",
+            ReviewStatus::Rejected => "rejected",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "pending" => Some(ReviewStatus::Pending),
+            "approved" => Some(ReviewStatus::Approved),
+            "rejected" => Some(ReviewStatus::Rejected),
+            _ => None,
+        }
+    }
+}
+
+/// A decision waiting for, or decided by, a human reviewer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReviewTicket {
+    pub review_id: String,
+    pub purpose_id: String,
+    /// Keyed hash of the subject ID, see [`super::consent::subject_hash`].
+    pub subject_hash: String,
+    pub model_id: String,
+    pub version: String,
+    /// WhyLog hash of the withheld decision.
+    pub whylog_hash: String,
+    pub risk: RiskLevel,
+    pub reasons: Vec<String>,
+    /// The withheld model response (JSON object) shown to the reviewer.
+    pub suggestion: String,
+    pub status: ReviewStatus,
+    pub reviewer: String,
+    pub note: String,
+    pub created_ms: u128,
+    pub decided_ms: Option<u128>,
+}
+
+impl ReviewTicket {
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"review_id\":\"{}\",\"purpose_id\":\"{}\",\"subject\":\"{}\",\"model_id\":\"{}\",\"version\":\"{}\",\"whylog_hash\":\"{}\",\"risk\":\"{}\",\"reasons\":{},\"suggestion\":{},\"status\":\"{}\",\"reviewer\":\"{}\",\"note\":\"{}\",\"created_ms\":{},\"decided_ms\":{}}}",
+            json::escape(&self.review_id),
+            json::escape(&self.purpose_id),
+            self.subject_hash,
+            json::escape(&self.model_id),
+            json::escape(&self.version),
+            self.whylog_hash,
+            self.risk.as_str(),
+            json::build_string_array(&self.reasons),
+            self.suggestion,
+            self.status.as_str(),
+            json::escape(&self.reviewer),
+            json::escape(&self.note),
+            self.created_ms,
+            self.decided_ms
+                .map_or_else(|| "null".to_string(), |ms| ms.to_string())
+        )
+    }
+}
+
+impl LedgerEntry for ReviewTicket {
+    type Key = String;
+
+    fn key(&self) -> Self::Key {
+        self.review_id.clone()
+    }
+
+    fn to_line(&self) -> String {
+        self.to_json()
+    }
+
+    fn from_line(line: &str) -> DeltaResult<Self> {
+        let invalid = DeltaError::internal("review_record_invalid");
+        let doc = json::parse(line).map_err(|_| invalid)?;
+        let text = |key: &str| {
+            doc.get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or(invalid)
+        };
+        let millis = |key: &str| match doc.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => value
+                .as_f64()
+                .filter(|ms| *ms >= 0.0)
+                .map(|ms| Some(ms as u128))
+                .ok_or(invalid),
+        };
+        let reasons = doc
+            .get("reasons")
+            .and_then(Value::as_array)
+            .ok_or(invalid)?
+            .iter()
+            .map(|reason| reason.as_str().map(str::to_string).ok_or(invalid))
+            .collect::<DeltaResult<Vec<_>>>()?;
+        let suggestion = doc
+            .get("suggestion")
+            .filter(|value| value.as_object().is_some())
+            .ok_or(invalid)?
+            .canonical();
+        Ok(Self {
+            review_id: text("review_id")?,
+            purpose_id: text("purpose_id")?,
+            subject_hash: text("subject")?,
+            model_id: text("model_id")?,
+            version: text("version")?,
+            whylog_hash: text("whylog_hash")?,
+            risk: RiskLevel::from_label(&text("risk")?).ok_or(invalid)?,
+            reasons,
+            suggestion,
+            status: ReviewStatus::from_label(&text("status")?).ok_or(invalid)?,
+            reviewer: text("reviewer")?,
+            note: text("note")?,
+            created_ms: millis("created_ms")?.ok_or(invalid)?,
+            decided_ms: millis("decided_ms")?,
+        })
+    }
+}
+
+/// Fields of a new ticket; the store assigns the ID and timestamps.
+#[derive(Clone, Debug)]
+pub struct ReviewRequest {
+    pub purpose_id: String,
+    pub subject_hash: String,
+    pub model_id: String,
+    pub version: String,
+    pub whylog_hash: String,
+    pub risk: RiskLevel,
+    pub reasons: Vec<String>,
+    pub suggestion: String,
+}
+
+pub struct FileReviewStore {
+    ledger: JsonlLedger<ReviewTicket>,
+}
+
+impl FileReviewStore {
+    pub fn new(cfg: &AppCfg) -> Self {
+        Self::at(
+            PathBuf::from(&cfg.data_root)
+                .join("reviews")
+                .join("reviews.jsonl"),
+        )
+    }
+
+    fn at(path: PathBuf) -> Self {
+        Self {
+            ledger: JsonlLedger::new(path, "review_store_poisoned"),
+        }
+    }
+
+    /// Queue a withheld decision for review.
+    pub fn open(&self, request: ReviewRequest) -> DeltaResult<ReviewTicket> {
+        static SEQ: AtomicU64 = AtomicU64::new(0);
+        let suggestion = json::parse(&request.suggestion)
+            .ok()
+            .filter(|value| value.as_object().is_some())
+            .ok_or(DeltaError::internal("review_suggestion_invalid"))?
+            .canonical();
+        let created_ms = time::now_ms();
+        let seed = format!(
+            "{}:{}:{}:{}",
+            request.whylog_hash,
+            created_ms,
+            std::process::id(),
+            SEQ.fetch_add(1, Ordering::Relaxed)
+        );
+        let ticket = ReviewTicket {
+            review_id: format!("rev-{}", &to_hex(&sha256(seed.as_bytes()))[..20]),
+            purpose_id: request.purpose_id,
+            subject_hash: request.subject_hash,
+            model_id: request.model_id,
+            version: request.version,
+            whylog_hash: request.whylog_hash,
+            risk: request.risk,
+            reasons: request.reasons,
+            suggestion,
+            status: ReviewStatus::Pending,
+            reviewer: String::new(),
+            note: String::new(),
+            created_ms,
+            decided_ms: None,
+        };
+
+        self.ledger.append(&ticket)?;
+        log::log_json_ctx(
+            "info",
+            "review",
+            "review_opened",
+            0,
+            time::now_ms().saturating_sub(created_ms),
+            &[
+                ("review_id", &ticket.review_id),
+                ("purpose_id", &ticket.purpose_id),
+                ("risk", ticket.risk.as_str()),
+                ("whylog_hash", &ticket.whylog_hash),
+            ],
+        );
+        Ok(ticket)
+    }
+
+    /// Record a reviewer's verdict (`Approved` or `Rejected`) on a pending ticket.
+    pub fn decide(
+        &self,
+        review_id: &str,
+        verdict: ReviewStatus,
+        reviewer: &str,
+        note: &str,
+    ) -> DeltaResult<ReviewTicket> {
+        if verdict == ReviewStatus::Pending {
+            return Err(DeltaError::invalid("review_verdict_invalid"));
+        }
+        if reviewer.trim().is_empty() {
+            return Err(DeltaError::invalid("review_reviewer_missing"));
+        }
+
+        let started = time::now_ms();
+        let ticket = self.ledger.update(|tickets| {
+            let mut ticket = tickets
+                .get(review_id)
+                .cloned()
+                .ok_or(DeltaError::invalid("review_unknown"))?;
+            if ticket.status != ReviewStatus::Pending {
+                return Err(DeltaError::policy_denied("review_already_decided"));
+            }
+            ticket.status = verdict;
+            ticket.reviewer = reviewer.to_string();
+            ticket.note = note.to_string();
+            ticket.decided_ms = Some(time::now_ms());
+            Ok(ticket)
+        })?;
+
+        log::log_json_ctx(
+            "info",
+            "review",
+            "review_decided",
+            0,
+            time::now_ms().saturating_sub(started),
+            &[
+                ("review_id", review_id),
+                ("status", verdict.as_str()),
+                ("reviewer", reviewer),
+                ("whylog_hash", &ticket.whylog_hash),
+            ],
+        );
+        Ok(ticket)
+    }
+
+    pub fn get(&self, review_id: &str) -> DeltaResult<Option<ReviewTicket>> {
+        self.ledger.read(|tickets| tickets.get(review_id).cloned())
+    }
+
+    /// Pending tickets, oldest first.
+    pub fn pending(&self) -> DeltaResult<Vec<ReviewTicket>> {
+        let mut pending = self.ledger.read(|tickets| {
+            tickets
+                .values()
+                .filter(|ticket| ticket.status == ReviewStatus::Pending)
+                .cloned()
+                .collect::<Vec<_>>()
+        })?;
+        pending.sort_by(|a, b| (a.created_ms, &a.review_id).cmp(&(b.created_ms, &b.review_id)));
+        Ok(pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn request(whylog_hash: &str) -> ReviewRequest {
+        ReviewRequest {
+            purpose_id: "credit".to_string(),
+            subject_hash: "ab".repeat(32),
+            model_id: "m1".to_string(),
+            version: "v1".to_string(),
+            whylog_hash: whylog_hash.to_string(),
+            risk: RiskLevel::High,
+            reasons: vec!["pii:email".to_string()],
+            suggestion: "{\"score\":0.9}".to_string(),
+        }
+    }
+
+    #[test]
+    fn tickets_are_queued_decided_once_and_shared_between_stores() {
+        let path = std::env::temp_dir()
+            .join(format!("delta1-reviews-{}", std::process::id()))
+            .join("reviews.jsonl");
+        let _ = fs::remove_file(&path);
+        let store = FileReviewStore::at(path.clone());
+        let first = store.open(request("h1")).unwrap();
+        let second = store.open(request("h2")).unwrap();
+        assert_ne!(first.review_id, second.review_id);
+
+        let other = FileReviewStore::at(path.clone());
+        let pending = other.pending().unwrap();
+        assert_eq!(pending.len(), 2);
+        assert!(pending.contains(&first) && pending.contains(&second));
+
+        let decided = other
+            .decide(
+                &first.review_id,
+                ReviewStatus::Rejected,
+                "anna",
+                "geen match",
+            )
+            .unwrap();
+        assert_eq!(decided.whylog_hash, "h1");
+        assert!(decided.decided_ms.is_some());
+        assert_eq!(store.pending().unwrap(), vec![second.clone()]);
+        assert_eq!(
+            store
+                .get(&first.review_id)
+                .unwrap()
+                .map(|ticket| ticket.status),
+            Some(ReviewStatus::Rejected)
+        );
+
+        let err = store
+            .decide(&first.review_id, ReviewStatus::Approved, "bob", "")
+            .unwrap_err();
+        assert_eq!(err.msg, "review_already_decided");
+        let err = store
+            .decide("rev-missing", ReviewStatus::Approved, "bob", "")
+            .unwrap_err();
+        assert_eq!(err.msg, "review_unknown");
+        let err = store
+            .decide(&second.review_id, ReviewStatus::Approved, " ", "")
+            .unwrap_err();
+        assert_eq!(err.msg, "review_reviewer_missing");
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 3);
+    }
+
+    #[test]
+    fn concurrent_verdicts_on_one_ticket_record_exactly_one() {
+        let path = std::env::temp_dir()
+            .join(format!("delta1-reviews-race-{}", std::process::id()))
+            .join("reviews.jsonl");
+        let _ = fs::remove_file(&path);
+        let ticket = FileReviewStore::at(path.clone())
+            .open(request("h3"))
+            .unwrap();
+
+        // Separate stores stand in for separate worker processes.
+        let decided = (0..8)
+            .map(|i| {
+                let path = path.clone();
+                let review_id = ticket.review_id.clone();
+                std::thread::spawn(move || {
+                    FileReviewStore::at(path)
+                        .decide(&review_id, ReviewStatus::Approved, &format!("r{i}"), "")
+                        .is_ok()
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .filter(|ok| *ok)
+            .count();
+        assert_eq!(decided, 1);
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+    }
+}
@@ -0,0 +1,303 @@
+//! Risk classification of individual decisions (human-in-the-loop).
+//!
+//! Every inference is rated `minimal`, `limited` or `high` from three signals:
+//! the confidence band the prediction falls in, personal data detected in the
+//! input's text values and the risk class on the model card. The highest
+//! signal wins. Decisions at or above the purpose's `review_at` level are not
+//! returned; they go to the review queue instead.
+//!
+//! Purposes configure this under `"risk"` in the registry:
+//!
+//! ```json
+//! {"high_below": 0.6, "limited_below": 0.8, "pii": "high", "review_at": "high"}
+//! ```
+//!
+//! Without a `"risk"` block confidence is not rated, detected PII is only
+//! listed in the reasons and high-risk decisions need review. Escalating on
+//! PII is opt-in per purpose via `"pii"`, since the heuristics cannot tell a
+//! customer's phone number from every look-alike identifier.
+
+use crate::common::error::{DeltaError, DeltaResult};
+use crate::common::json::{self, Value};
+use crate::training::domain::RiskLevel;
+
+/// Per-purpose thresholds of the risk classifier.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RiskPolicy {
+    /// Confidence below which a decision is high risk.
+    pub high_below: f32,
+    /// Confidence below which a decision is at least limited risk.
+    pub limited_below: f32,
+    /// Risk of a decision whose input contains personal data.
+    pub pii: RiskLevel,
+    /// Lowest risk level that needs human review.
+    pub review_at: RiskLevel,
+}
+
+impl Default for RiskPolicy {
+    fn default() -> Self {
+        Self {
+            high_below: 0.0,
+            limited_below: 0.0,
+            pii: RiskLevel::Minimal,
+            review_at: RiskLevel::High,
+        }
+    }
+}
+
+impl RiskPolicy {
+    /// Parse a registry `"risk"` block; every key is optional.
+    pub fn from_value(value: Option<&Value>) -> DeltaResult<Self> {
+        let invalid = DeltaError::invalid("purpose_risk_invalid");
+        let defaults = Self::default();
+        let Some(value) = value else {
+            return Ok(defaults);
+        };
+        if value.as_object().is_none() {
+            return Err(invalid);
+        }
+        let band = |key: &str| match value.get(key) {
+            None => Ok(None),
+            Some(band) => band
+                .as_f64()
+                .filter(|band| (0.0..=1.0).contains(band))
+                .map(|band| Some(band as f32))
+                .ok_or(invalid),
+        };
+        let level = |key: &str, default: RiskLevel| match value.get(key) {
+            None => Ok(default),
+            Some(level) => level
+                .as_str()
+                .and_then(RiskLevel::from_label)
+                .ok_or(invalid),
+        };
+
+        let high_below = band("high_below")?.unwrap_or(defaults.high_below);
+        let limited_below = band("limited_below")?.unwrap_or(high_below);
+        if limited_below < high_below {
+            return Err(invalid);
+        }
+        Ok(Self {
+            high_below,
+            limited_below,
+            pii: level("pii", defaults.pii)?,
+            review_at: level("review_at", defaults.review_at)?,
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"high_below\":{:.4},\"limited_below\":{:.4},\"pii\":\"{}\",\"review_at\":\"{}\"}}",
+            self.high_below,
+            self.limited_below,
+            self.pii.as_str(),
+            self.review_at.as_str()
+        )
+    }
+
+    /// Rate a decision made with `confidence` by a model of risk class
+    /// `model_risk` on an input containing the `pii` kinds.
+    pub fn classify(
+        &self,
+        confidence: f32,
+        model_risk: Option<RiskLevel>,
+        pii: &[&'static str],
+    ) -> RiskAssessment {
+        let mut level = RiskLevel::Minimal;
+        let mut reasons = Vec::new();
+        let mut raise = |candidate: RiskLevel, reason: String| {
+            level = level.max(candidate);
+            reasons.push(reason);
+        };
+
+        if confidence < self.high_below {
+            raise(RiskLevel::High, "confidence_band".to_string());
+        } else if confidence < self.limited_below {
+            raise(RiskLevel::Limited, "confidence_band".to_string());
+        }
+        for kind in pii {
+            raise(self.pii, format!("pii:{kind}"));
+        }
+        if let Some(risk) = model_risk.filter(|risk| *risk > RiskLevel::Minimal) {
+            raise(risk, "model_risk".to_string());
+        }
+
+        RiskAssessment {
+            level,
+            reasons,
+            review: level >= self.review_at,
+        }
+    }
+}
+
+/// Outcome of [`RiskPolicy::classify`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RiskAssessment {
+    pub level: RiskLevel,
+    /// Signals that raised the level, e.g. `confidence_band` or `pii:email`.
+    pub reasons: Vec<String>,
+    /// Whether the decision must go to a human instead of the caller.
+    pub review: bool,
+}
+
+/// Kinds of personal data (`email`, `iban`, `phone`) found in the string
+/// values of `record`, sorted and without duplicates.
+pub fn detect_pii(record: &Value) -> Vec<&'static str> {
+    let mut found = Vec::new();
+    collect_pii(record, &mut found);
+    found.sort_unstable();
+    found.dedup();
+    found
+}
+
+fn collect_pii(value: &Value, found: &mut Vec<&'static str>) {
+    match value {
+        Value::String(text) => {
+            for token in text.split(|c: char| c.is_whitespace() || ",;<>()\"'".contains(c)) {
+                if is_email(token) {
+                    found.push("email");
+                }
+                if is_iban(token) {
+                    found.push("iban");
+                }
+            }
+            if has_phone_number(text) {
+                found.push("phone");
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_pii(item, found)),
+        Value::Object(members) => members
+            .iter()
+            .for_each(|(_, member)| collect_pii(member, found)),
+        _ => {}
+    }
+}
+
+fn is_email(token: &str) -> bool {
+    let token = token.trim_end_matches(['.', ':', '!', '?']);
+    let Some((local, domain)) = token.split_once('@') else {
+        return false;
+    };
+    let Some((host, tld)) = domain.rsplit_once('.') else {
+        return false;
+    };
+    !local.is_empty()
+        && !host.is_empty()
+        && tld.len() >= 2
+        && tld.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// IBAN with a valid ISO 7064 mod-97 check.
+fn is_iban(token: &str) -> bool {
+    let bytes = token.as_bytes();
+    if !(15..=34).contains(&bytes.len())
+        || !bytes[..2].iter().all(u8::is_ascii_uppercase)
+        || !bytes[2..4].iter().all(u8::is_ascii_digit)
+        || !bytes
+            .iter()
+            .all(|b| b.is_ascii_digit() || b.is_ascii_uppercase())
+    {
+        return false;
+    }
+    let rearranged = bytes[4..].iter().chain(&bytes[..4]);
+    let remainder = rearranged.fold(0u32, |acc, b| match b {
+        b'0'..=b'9' => (acc * 10 + u32::from(b - b'0')) % 97,
+        _ => (acc * 100 + u32::from(b - b'A') + 10) % 97,
+    });
+    remainder == 1
+}
+
+/// A run of 9 to 15 digits, optionally separated by spaces or dashes, that
+/// looks like a formatted phone number: international with a leading `+`, or
+/// national with a leading `0` and at least one separator. Bare zero-prefixed
+/// digit runs are usually order or account numbers and do not count.
+fn has_phone_number(text: &str) -> bool {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut start = 0;
+    while start < chars.len() {
+        let preceded_by_digit = start > 0 && chars[start - 1].is_ascii_digit();
+        if (chars[start] == '+' || chars[start] == '0') && !preceded_by_digit {
+            let international = chars[start] == '+';
+            let mut digits = usize::from(!international);
+            let mut separated = false;
+            let mut end = start + 1;
+            while end < chars.len()
+                && (chars[end].is_ascii_digit() || chars[end] == ' ' || chars[end] == '-')
+            {
+                if chars[end].is_ascii_digit() {
+                    digits += 1;
+                } else if end + 1 < chars.len() && chars[end + 1].is_ascii_digit() {
+                    separated = true;
+                }
+                end += 1;
+            }
+            if (9..=15).contains(&digits) && (international || separated) {
+                return true;
+            }
+            start = end.max(start + 1);
+        } else {
+            start += 1;
+        }
+    }
+    false
+}
+
+/// `"risk"` and `"risk_reasons"` members appended to a response.
+pub fn to_json_members(assessment: &RiskAssessment) -> String {
+    format!(
+        "\"risk\":\"{}\",\"risk_reasons\":{}",
+        assessment.level.as_str(),
+        json::build_string_array(&assessment.reasons)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decisions_are_rated_by_confidence_pii_and_model_risk() {
+        let record = json::parse(
+            "{\"amount\":20240115,\"note\":\"Mail jan@example.nl of bel +31 6 1234 5678.\",\
+              \"refund\":[\"NL91ABNA0417164300\",\"NL00ABNA0417164300\"],\"date\":\"2024-01-15 10:00\"}",
+        )
+        .unwrap();
+        assert_eq!(detect_pii(&record), vec!["email", "iban", "phone"]);
+        let plain = json::parse("{\"text\":\"order 2024-0001 shipped\"}").unwrap();
+        assert!(detect_pii(&plain).is_empty());
+        let national = json::parse("{\"text\":\"bel 020-123 4567\"}").unwrap();
+        assert_eq!(detect_pii(&national), vec!["phone"]);
+        // Zero-prefixed order and account numbers are not phone numbers.
+        let ids =
+            json::parse("{\"order\":\"0012345678\",\"text\":\"account 0612345678901\"}").unwrap();
+        assert!(detect_pii(&ids).is_empty());
+
+        let policy = RiskPolicy::from_value(Some(
+            &json::parse("{\"high_below\":0.6,\"limited_below\":0.8,\"pii\":\"limited\"}").unwrap(),
+        ))
+        .unwrap();
+        let minimal = policy.classify(0.9, None, &[]);
+        assert_eq!((minimal.level, minimal.review), (RiskLevel::Minimal, false));
+        let limited = policy.classify(0.9, Some(RiskLevel::Minimal), &["email"]);
+        assert_eq!(limited.level, RiskLevel::Limited);
+        assert_eq!(limited.reasons, vec!["pii:email"]);
+        let high = policy.classify(0.55, Some(RiskLevel::Limited), &[]);
+        assert_eq!((high.level, high.review), (RiskLevel::High, true));
+        assert_eq!(high.reasons, vec!["confidence_band", "model_risk"]);
+
+        // The default policy only reviews high-risk models; PII is listed
+        // but escalates only where a purpose opts in.
+        let defaults = RiskPolicy::from_value(None).unwrap();
+        assert!(!defaults.classify(0.5, None, &[]).review);
+        let listed = defaults.classify(0.99, None, &["phone"]);
+        assert_eq!((listed.level, listed.review), (RiskLevel::Minimal, false));
+        assert_eq!(listed.reasons, vec!["pii:phone"]);
+        assert!(defaults.classify(0.99, Some(RiskLevel::High), &[]).review);
+
+        let invalid = json::parse("{\"high_below\":0.8,\"limited_below\":0.6}").unwrap();
+        assert_eq!(
+            RiskPolicy::from_value(Some(&invalid)).unwrap_err().msg,
+            "purpose_risk_invalid"
+        );
+    }
+}
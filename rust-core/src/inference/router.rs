@@ -0,0 +1,318 @@
+//! Declarative routing rules, loaded from `DELTA1_ROUTER_RULES_FILE`
+//! (default `${DATA_ROOT}/router.json`):
+//!
+//! ```json
+//! {"rules": [
+//!   {"name": "features_only", "when": {"features_only": true}, "target": "tabular"},
+//!   {"name": "agent", "when": {"agent": true}, "target": "text"},
+//!   {"name": "b2b_credit", "when": {"purpose": "credit", "subject": {"segment": "b2b"},
+//!     "fields": ["amount"]}, "model": {"id": "credit-b2b", "version": "v3"}},
+//!   {"name": "long_text", "when": {"text_length_gt": 256}, "target": "text"}],
+//!  "default": {"target": "tabular"}}
+//! ```
+//!
+//! Rules are evaluated in order and the first whose conditions all hold
+//! fires; without a match the default applies. `when` accepts `fields` (all
+//! present at the top level), `text_length_gt`, `text_length_lt`, `purpose`
+//! (one ID or a list), `subject` (members of `context.subject` that must
+//! equal the given values), `agent` and `features_only`. A rule routes to a
+//! model family (`"target"`) or to a specific loaded model (`"model"`, newest
+//! version when `version` is omitted). The fired rule's name is reported as
+//! `route_reason`, the default as `default`. Without a `"default"` the model
+//! serving the purpose answers in its own family.
+//!
+//! Without a rules file the built-in rules route long texts (more than 256
+//! characters) to a text model and anything marked `features_only` to a
+//! tabular model; everything else goes to the purpose's model.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::common::error::{DeltaError, DeltaResult};
+use crate::common::json::{self, Value};
+use crate::training::domain::{ModelId, VersionName};
+
+use super::domain::{ModelRouter, RouteDecision, RouteTarget, RouterContext};
+
+/// Rules used when no rules file exists.
+const BUILTIN_RULES: &str = "{\"rules\":[\
+    {\"name\":\"features_only\",\"when\":{\"features_only\":true},\"target\":\"tabular\"},\
+    {\"name\":\"long_text\",\"when\":{\"text_length_gt\":256},\"target\":\"text\"}]}";
+
+/// Where a rule sends the request.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RuleTarget {
+    Family(RouteTarget),
+    Model(ModelId, Option<VersionName>),
+    /// The model serving the purpose, in its own family.
+    Purpose,
+}
+
+/// Conditions of a rule; unset conditions always hold.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RuleMatch {
+    pub fields: Vec<String>,
+    pub text_length_gt: Option<usize>,
+    pub text_length_lt: Option<usize>,
+    pub purposes: Vec<String>,
+    pub subject: Vec<(String, Value)>,
+    pub agent: Option<bool>,
+    pub features_only: Option<bool>,
+}
+
+impl RuleMatch {
+    pub fn matches(&self, ctx: &RouterContext) -> bool {
+        self.fields.iter().all(|field| ctx.fields.contains(field))
+            && self.text_length_gt.is_none_or(|min| ctx.text_length > min)
+            && self.text_length_lt.is_none_or(|max| ctx.text_length < max)
+            && (self.purposes.is_empty() || self.purposes.contains(&ctx.purpose_id))
+            && self.subject.iter().all(|(key, expected)| {
+                ctx.subject
+                    .iter()
+                    .any(|(name, value)| name == key && value == expected)
+            })
+            && self.agent.is_none_or(|agent| ctx.agent == agent)
+            && self
+                .features_only
+                .is_none_or(|features_only| ctx.features_only == features_only)
+    }
+
+    fn from_value(value: Option<&Value>) -> DeltaResult<Self> {
+        let invalid = DeltaError::invalid("router_rules_invalid");
+        let Some(value) = value else {
+            return Ok(Self::default());
+        };
+        let members = value.as_object().ok_or(invalid)?;
+        let strings = |value: &Value| {
+            value
+                .as_array()
+                .ok_or(invalid)?
+                .iter()
+                .map(|item| item.as_str().map(str::to_string).ok_or(invalid))
+                .collect::<DeltaResult<Vec<_>>>()
+        };
+        let length = |value: &Value| {
+            value
+                .as_f64()
+                .filter(|length| *length >= 0.0 && length.fract() == 0.0)
+                .map(|length| length as usize)
+                .ok_or(invalid)
+        };
+
+        let mut rule = Self::default();
+        for (key, value) in members {
+            match key.as_str() {
+                "fields" => rule.fields = strings(value)?,
+                "text_length_gt" => rule.text_length_gt = Some(length(value)?),
+                "text_length_lt" => rule.text_length_lt = Some(length(value)?),
+                "purpose" => {
+                    rule.purposes = match value.as_str() {
+                        Some(purpose) => vec![purpose.to_string()],
+                        None => strings(value)?,
+                    }
+                }
+                "subject" => rule.subject = value.as_object().ok_or(invalid)?.to_vec(),
+                "agent" => rule.agent = Some(value.as_bool().ok_or(invalid)?),
+                "features_only" => rule.features_only = Some(value.as_bool().ok_or(invalid)?),
+                // An unknown condition must not silently match everything.
+                _ => return Err(DeltaError::invalid("router_condition_unknown")),
+            }
+        }
+        Ok(rule)
+    }
+}
+
+/// One named routing rule.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RouteRule {
+    pub name: String,
+    pub when: RuleMatch,
+    pub target: RuleTarget,
+}
+
+/// Ordered routing rules with a default target.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleRouter {
+    rules: Vec<RouteRule>,
+    default: RuleTarget,
+}
+
+impl Default for RuleRouter {
+    fn default() -> Self {
+        Self::from_json(BUILTIN_RULES).expect("built-in router rules")
+    }
+}
+
+impl RuleRouter {
+    /// Parse a rules document; rule names must be unique and non-empty.
+    pub fn from_json(raw: &str) -> DeltaResult<Self> {
+        let invalid = DeltaError::invalid("router_rules_invalid");
+        let doc = json::parse(raw).map_err(|_| invalid)?;
+        let entries = doc.get("rules").and_then(Value::as_array).ok_or(invalid)?;
+
+        let mut rules: Vec<RouteRule> = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let name = entry
+                .get("name")
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .ok_or(invalid)?;
+            if name == "default" || rules.iter().any(|rule| rule.name == name) {
+                return Err(DeltaError::invalid("router_rule_duplicate"));
+            }
+            rules.push(RouteRule {
+                name: name.to_string(),
+                when: RuleMatch::from_value(entry.get("when"))?,
+                target: parse_target(entry)?,
+            });
+        }
+        let default = match doc.get("default") {
+            Some(default) => parse_target(default)?,
+            None => RuleTarget::Purpose,
+        };
+        Ok(Self { rules, default })
+    }
+
+    /// Read the rules file; a missing file yields the built-in rules.
+    pub fn load(path: &Path) -> DeltaResult<Self> {
+        match fs::read_to_string(path) {
+            Ok(raw) => Self::from_json(&raw),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(_) => Err(DeltaError::io()),
+        }
+    }
+
+    pub fn rules(&self) -> &[RouteRule] {
+        &self.rules
+    }
+}
+
+impl ModelRouter for RuleRouter {
+    fn route(&self, ctx: &RouterContext) -> RouteDecision {
+        let (reason, target) = self
+            .rules
+            .iter()
+            .find(|rule| rule.when.matches(ctx))
+            .map_or(("default", &self.default), |rule| {
+                (rule.name.as_str(), &rule.target)
+            });
+        let (target, model) = match target {
+            RuleTarget::Family(family) => (Some(*family), None),
+            RuleTarget::Model(id, version) => (None, Some((id.clone(), version.clone()))),
+            RuleTarget::Purpose => (None, None),
+        };
+        RouteDecision {
+            target,
+            reason: reason.to_string(),
+            model,
+        }
+    }
+}
+
+/// `"target": "tabular" | "text"` or `"model": {"id", "version"?}`, exactly one.
+fn parse_target(value: &Value) -> DeltaResult<RuleTarget> {
+    let invalid = DeltaError::invalid("router_target_invalid");
+    match (value.get("target"), value.get("model")) {
+        (Some(target), None) => match target.as_str() {
+            Some("tabular") => Ok(RuleTarget::Family(RouteTarget::Tabular)),
+            Some("text") => Ok(RuleTarget::Family(RouteTarget::Text)),
+            _ => Err(invalid),
+        },
+        (None, Some(model)) => {
+            let id = model
+                .get("id")
+                .and_then(Value::as_str)
+                .filter(|id| !id.is_empty())
+                .ok_or(invalid)?;
+            let version = match model.get("version") {
+                None | Some(Value::Null) => None,
+                Some(version) => Some(VersionName::new(version.as_str().ok_or(invalid)?)),
+            };
+            Ok(RuleTarget::Model(ModelId::new(id), version))
+        }
+        _ => Err(invalid),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inference::domain::InferenceContext;
+
+    fn ctx(purpose_id: &str, payload: &str) -> RouterContext {
+        RouterContext::from_payload(payload, &InferenceContext::new(purpose_id, "s", false))
+    }
+
+    #[test]
+    fn rules_fire_in_order_and_name_the_route_reason() {
+        let builtin = RuleRouter::default();
+        let long = format!("{{\"text\":\"{}\"}}", "a".repeat(257));
+        assert_eq!(
+            builtin.route(&ctx("p", &long)).target,
+            Some(RouteTarget::Text)
+        );
+        let forced = format!(
+            "{{\"text\":\"{}\",\"context\":{{\"features_only\":true}}}}",
+            "a".repeat(257)
+        );
+        assert_eq!(builtin.route(&ctx("p", &forced)).reason, "features_only");
+        let plain = builtin.route(&ctx("p", "{\"amount\":1}"));
+        assert_eq!((plain.reason.as_str(), plain.target), ("default", None));
+
+        let router = RuleRouter::from_json(
+            "{\"rules\":[\
+              {\"name\":\"agent\",\"when\":{\"agent\":true},\"target\":\"text\"},\
+              {\"name\":\"b2b\",\"when\":{\"purpose\":[\"credit\"],\"fields\":[\"amount\"],\"subject\":{\"segment\":\"b2b\"}},\
+               \"model\":{\"id\":\"credit-b2b\",\"version\":\"v3\"}},\
+              {\"name\":\"short\",\"when\":{\"text_length_gt\":0,\"text_length_lt\":10},\"model\":{\"id\":\"faq\"}}],\
+             \"default\":{\"target\":\"text\"}}",
+        )
+        .unwrap();
+        assert_eq!(router.rules().len(), 3);
+
+        let agent = router.route(&ctx("credit", "{\"amount\":1,\"agent\":true}"));
+        assert_eq!(
+            (agent.reason.as_str(), agent.target),
+            ("agent", Some(RouteTarget::Text))
+        );
+        let b2b = router.route(&ctx(
+            "credit",
+            "{\"amount\":1,\"context\":{\"subject\":{\"segment\":\"b2b\"}}}",
+        ));
+        assert_eq!(b2b.reason, "b2b");
+        assert_eq!(
+            b2b.model,
+            Some((ModelId::new("credit-b2b"), Some(VersionName::new("v3"))))
+        );
+        let other_segment = router.route(&ctx(
+            "credit",
+            "{\"amount\":1,\"context\":{\"subject\":{\"segment\":\"retail\"}}}",
+        ));
+        assert_eq!(
+            (other_segment.reason.as_str(), other_segment.target),
+            ("default", Some(RouteTarget::Text))
+        );
+        let short = router.route(&ctx("support", "{\"text\":\"hoi\"}"));
+        assert_eq!(short.model, Some((ModelId::new("faq"), None)));
+
+        for (raw, msg) in [
+            (
+                "{\"rules\":[{\"name\":\"x\",\"when\":{\"lenght\":1},\"target\":\"text\"}]}",
+                "router_condition_unknown",
+            ),
+            (
+                "{\"rules\":[{\"name\":\"x\",\"target\":\"text\",\"model\":{\"id\":\"m\"}}]}",
+                "router_target_invalid",
+            ),
+            (
+                "{\"rules\":[{\"name\":\"x\",\"target\":\"text\"},{\"name\":\"x\",\"target\":\"text\"}]}",
+                "router_rule_duplicate",
+            ),
+            ("{\"rules\":{}}", "router_rules_invalid"),
+        ] {
+            assert_eq!(RuleRouter::from_json(raw).unwrap_err().msg, msg);
+        }
+    }
+}
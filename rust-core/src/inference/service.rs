@@ -1,123 +1,646 @@
 //! Inference orchestration utilities bridging models, routers and engines.
 //!
-//! Implements the SSMRouter rules from the model design, performs consent
-//! checks, falls back to the tabular logistic baseline when the text engine
-//! fails and generates WhyLog hashes using the crate-local `SimpleHash`.
+//! Routes requests with the configurable rules of `router.rs`, performs
+//! purpose and consent checks, scores with the engine of the model's family,
+//! holds high-risk decisions back for human review and generates WhyLog
+//! hashes using the crate-local `SimpleHash`.
 
-use std::sync::{Mutex, OnceLock};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, RwLock};
 
-use crate::common::error::{DeltaError, DeltaResult};
+use crate::common::config::AppCfg;
+use crate::common::error::{DeltaCode, DeltaError, DeltaResult};
 use crate::common::ids::SimpleHash;
-use crate::common::json;
+use crate::common::json::{self, Value};
+use crate::common::log;
 use crate::common::time;
-use crate::training::domain::{ModelId, ModelKind, ModelVersion, VersionName};
+use crate::data::domain::DatasetId;
+use crate::data::service as data_service;
+use crate::training::artefact::ModelArtefact;
+use crate::training::calibration::Calibrator;
+use crate::training::domain::{FeatureKind, ModelId, ModelKind, ModelVersion, VersionName};
+use crate::training::features::{FeaturePipeline, FieldIssue};
+use crate::training::service as training_service;
+use crate::training::trainers;
 
+use super::cache::{ArtefactCache, CacheStats};
+use super::consent::{self, ConsentRecord, FileConsentStore};
+use super::consent_lookup::{CallbackConsentStore, ConsentLookup, ConsentLookupStats};
 use super::domain::{
-    build_context, ensure_compatible, ensure_consent, AllowAllConsent, ConsentStore,
-    EngineResponse, InferEngine, ModelRouter, Prediction, RouteDecision, RouteTarget,
-    RouterContext, SSMRouter, WhyLog,
+    build_context, ensure_compatible, ensure_consent, route_target_for_model, ConsentStore,
+    EngineResponse, InferEngine, InferError, ModelRouter, Prediction, Route, RouteDecision,
+    RouteTarget, RouterContext, ScoreReport, WhyLog, MAX_BATCH_ITEMS,
 };
+use super::encoder::Encoder;
+use super::pool::ModelPool;
+use super::purpose::{Purpose, PurposeRegistry};
+use super::review::{FileReviewStore, ReviewRequest, ReviewStatus, ReviewTicket};
+use super::risk::{self, RiskAssessment};
+use super::router::RuleRouter;
+use super::tokenizer::WordPieceTokenizer;
+use super::workers::{Pool, PoolCfg, PoolMetrics};
 
-static ACTIVE_MODEL: OnceLock<Mutex<Option<ModelVersion>>> = OnceLock::new();
-static ROUTER: OnceLock<SSMRouter> = OnceLock::new();
-static CONSENT: OnceLock<AllowAllConsent> = OnceLock::new();
+static POOL: OnceLock<Mutex<ModelPool>> = OnceLock::new();
+static ROUTER: OnceLock<RwLock<Arc<RuleRouter>>> = OnceLock::new();
+static CONSENT: OnceLock<Arc<FileConsentStore>> = OnceLock::new();
+static CONSENT_LOOKUP: RwLock<Option<Arc<CallbackConsentStore>>> = RwLock::new(None);
 static ENGINES: OnceLock<EngineRegistry> = OnceLock::new();
+static WORKERS: OnceLock<Pool> = OnceLock::new();
+static ARTEFACTS: OnceLock<ArtefactCache<CacheKey, LoadedModel>> = OnceLock::new();
+static PURPOSES: OnceLock<RwLock<PurposeRegistry>> = OnceLock::new();
+static REVIEWS: OnceLock<FileReviewStore> = OnceLock::new();
 
-/// Register the model that should be used for subsequent inference calls.
-pub fn register_active_model(model: ModelVersion) {
-    let lock = ACTIVE_MODEL.get_or_init(|| Mutex::new(None));
-    if let Ok(mut guard) = lock.lock() {
-        *guard = Some(model);
-    }
+/// Model id, version and the engine that deserialised the artefact.
+type CacheKey = (String, String, RouteTarget);
+
+/// A version's artefact as prepared by one of the engines.
+enum LoadedModel {
+    Tabular(TabularModel),
+    Text(Box<TextModel>),
 }
 
-fn active_model() -> Option<ModelVersion> {
-    let lock = ACTIVE_MODEL.get_or_init(|| Mutex::new(None));
-    match lock.lock() {
-        Ok(guard) => guard.clone(),
-        Err(_) => None,
-    }
+fn lock_pool() -> DeltaResult<MutexGuard<'static, ModelPool>> {
+    POOL.get_or_init(|| Mutex::new(ModelPool::default()))
+        .lock()
+        .map_err(|_| DeltaError::internal("model_pool_poisoned"))
+}
+
+/// Load a model into the pool and serve every purpose without its own
+/// default from it.
+///
+/// The artefact signature is checked first; under an enforcing signature
+/// policy unsigned or untrusted versions are refused.
+pub fn register_active_model(model: ModelVersion) -> DeltaResult<()> {
+    let (id, version) = (model.id.clone(), model.version.clone());
+    load_pooled_model(model)?;
+    lock_pool()?.set_fallback(&id, &version)
+}
+
+/// Load a model into the pool next to the ones already loaded.
+pub fn load_pooled_model(model: ModelVersion) -> DeltaResult<()> {
+    training_service::verify_signature(&model)?;
+    lock_pool()?.load(model);
+    Ok(())
+}
+
+/// Drop a model from the pool; returns whether it was loaded.
+pub fn unload_pooled_model(id: &ModelId, version: &VersionName) -> DeltaResult<bool> {
+    let unloaded = lock_pool()?.unload(id, version);
+    evict_artefacts(id, Some(version));
+    Ok(unloaded)
+}
+
+/// Drop cached artefacts of `id` (only `version` when given), e.g. after the
+/// version was archived or re-signed; returns how many entries were dropped.
+pub fn evict_artefacts(id: &ModelId, version: Option<&VersionName>) -> usize {
+    artefacts().invalidate(|(model_id, model_version, _)| {
+        model_id == id.as_str() && version.is_none_or(|version| model_version == version.as_str())
+    })
+}
+
+/// Hit and miss counters and occupancy of the artefact cache.
+pub fn artefact_cache_stats() -> CacheStats {
+    artefacts().stats()
+}
+
+fn artefacts() -> &'static ArtefactCache<CacheKey, LoadedModel> {
+    ARTEFACTS.get_or_init(|| {
+        ArtefactCache::new(AppCfg::load().artefact_cache_mb.saturating_mul(1 << 20))
+    })
+}
+
+/// The artefact of `model` as prepared by the `target` engine, read from disk
+/// by `load` on a cache miss.
+fn cached_model<F>(
+    model: &ModelVersion,
+    target: RouteTarget,
+    load: F,
+) -> DeltaResult<Arc<LoadedModel>>
+where
+    F: FnOnce() -> DeltaResult<(LoadedModel, usize)>,
+{
+    let key = (
+        model.id.as_str().to_string(),
+        model.version.as_str().to_string(),
+        target,
+    );
+    artefacts().get_or_load(&key, load)
+}
+
+/// Serve `purpose_id` from a loaded model; without `version` the newest
+/// loaded version of `id`.
+pub fn set_purpose_model(
+    purpose_id: &str,
+    id: &ModelId,
+    version: Option<&VersionName>,
+) -> DeltaResult<()> {
+    let mut pool = lock_pool()?;
+    let model = pool
+        .get(id, version)
+        .ok_or_else(|| DeltaError::model_missing("model_not_loaded"))?;
+    check_purpose(purpose_id, model.kind)?;
+    pool.set_purpose(purpose_id, &model.id, &model.version)
+}
+
+fn purposes() -> &'static RwLock<PurposeRegistry> {
+    PURPOSES.get_or_init(|| {
+        let cfg = AppCfg::load();
+        // A malformed file registers nothing, so inference fails closed
+        // until `reload_purposes` succeeds.
+        RwLock::new(PurposeRegistry::load(Path::new(&cfg.purposes_file)).unwrap_or_default())
+    })
 }
 
-fn router() -> &'static SSMRouter {
-    ROUTER.get_or_init(SSMRouter::new)
+/// Re-read `DELTA1_PURPOSES_FILE`; returns the number of registered
+/// purposes. A malformed file keeps the current registry.
+pub fn reload_purposes() -> DeltaResult<usize> {
+    let cfg = AppCfg::load();
+    let registry = PurposeRegistry::load(Path::new(&cfg.purposes_file))?;
+    let count = registry.len();
+    *purposes()
+        .write()
+        .map_err(|_| DeltaError::internal("purpose_registry_poisoned"))? = registry;
+    Ok(count)
 }
 
-fn consent_store() -> &'static dyn ConsentStore {
-    CONSENT.get_or_init(AllowAllConsent::default)
+/// The registered purpose `purpose_id`, or `InvalidPurpose`.
+pub fn purpose(purpose_id: &str) -> DeltaResult<Purpose> {
+    purposes()
+        .read()
+        .map_err(|_| DeltaError::internal("purpose_registry_poisoned"))?
+        .get(purpose_id)
+        .cloned()
+}
+
+/// Gate 0: `purpose_id` must be registered and allow models of `kind`.
+fn check_purpose(purpose_id: &str, kind: ModelKind) -> DeltaResult<Purpose> {
+    purposes()
+        .read()
+        .map_err(|_| DeltaError::internal("purpose_registry_poisoned"))?
+        .check(purpose_id, kind)
+        .cloned()
+}
+
+fn reviews() -> &'static FileReviewStore {
+    REVIEWS.get_or_init(|| FileReviewStore::new(&AppCfg::load()))
+}
+
+/// Decisions waiting for a human reviewer, oldest first.
+pub fn pending_reviews() -> DeltaResult<Vec<ReviewTicket>> {
+    reviews().pending()
+}
+
+/// The review ticket `review_id`, if any.
+pub fn review(review_id: &str) -> DeltaResult<Option<ReviewTicket>> {
+    reviews().get(review_id)
+}
+
+/// Record a reviewer's verdict on a pending ticket; the ticket keeps the
+/// WhyLog hash of the decision it was opened for.
+pub fn decide_review(
+    review_id: &str,
+    verdict: ReviewStatus,
+    reviewer: &str,
+    note: &str,
+) -> DeltaResult<ReviewTicket> {
+    reviews().decide(review_id, verdict, reviewer, note)
+}
+
+/// Every model currently loaded for inference.
+pub fn loaded_models() -> DeltaResult<Vec<ModelVersion>> {
+    Ok(lock_pool()?.loaded())
+}
+
+fn routes() -> &'static RwLock<Arc<RuleRouter>> {
+    ROUTER.get_or_init(|| {
+        let cfg = AppCfg::load();
+        // A broken rules file must not stop inference; the built-in rules
+        // apply until `reload_router_rules` succeeds.
+        let rules = RuleRouter::load(Path::new(&cfg.router_rules_file)).unwrap_or_else(|err| {
+            log::log_json("error", "inference", err.msg, err.code as u32, 0);
+            RuleRouter::default()
+        });
+        RwLock::new(Arc::new(rules))
+    })
+}
+
+fn router() -> DeltaResult<Arc<RuleRouter>> {
+    let rules = routes()
+        .read()
+        .map_err(|_| DeltaError::internal("router_poisoned"))?;
+    Ok(Arc::clone(&rules))
+}
+
+/// Re-read `DELTA1_ROUTER_RULES_FILE`; returns the number of rules. A
+/// malformed file keeps the current rules.
+pub fn reload_router_rules() -> DeltaResult<usize> {
+    let cfg = AppCfg::load();
+    let rules = RuleRouter::load(Path::new(&cfg.router_rules_file))?;
+    let count = rules.rules().len();
+    *routes()
+        .write()
+        .map_err(|_| DeltaError::internal("router_poisoned"))? = Arc::new(rules);
+    Ok(count)
+}
+
+/// The lookup store when one is installed, otherwise the file store.
+fn consent_store() -> DeltaResult<Arc<dyn ConsentStore>> {
+    let lookup = CONSENT_LOOKUP
+        .read()
+        .map_err(|_| DeltaError::internal("consent_store_poisoned"))?;
+    Ok(match lookup.as_ref() {
+        Some(store) => Arc::clone(store) as Arc<dyn ConsentStore>,
+        None => Arc::clone(file_consent()) as Arc<dyn ConsentStore>,
+    })
+}
+
+fn file_consent() -> &'static Arc<FileConsentStore> {
+    CONSENT.get_or_init(|| Arc::new(FileConsentStore::new(&AppCfg::load())))
+}
+
+/// Answer consent checks with `lookup`, caching answers for `ttl_ms` (at
+/// most 24 hours); `None` returns to the file store.
+pub fn set_consent_lookup(lookup: Option<ConsentLookup>, ttl_ms: u128) -> DeltaResult<()> {
+    let store = match lookup {
+        Some(lookup) => {
+            let key = consent::subject_key(&AppCfg::load())?;
+            Some(Arc::new(CallbackConsentStore::new(lookup, ttl_ms, key)))
+        }
+        None => None,
+    };
+    *CONSENT_LOOKUP
+        .write()
+        .map_err(|_| DeltaError::internal("consent_store_poisoned"))? = store;
+    Ok(())
+}
+
+/// Cache and latency counters of the installed consent lookup, if any.
+pub fn consent_lookup_stats() -> DeltaResult<Option<ConsentLookupStats>> {
+    let lookup = CONSENT_LOOKUP
+        .read()
+        .map_err(|_| DeltaError::internal("consent_store_poisoned"))?;
+    Ok(lookup.as_ref().map(|store| store.stats()))
+}
+
+/// Record that `subject_id` consents to `purpose_id`, optionally until
+/// `expires_at_ms`; `request_id` is logged with the change. The purpose must
+/// be registered (`InvalidPurpose` otherwise); revoking is always possible.
+pub fn grant_consent(
+    purpose_id: &str,
+    subject_id: &str,
+    expires_at_ms: Option<u128>,
+    source: &str,
+    request_id: &str,
+) -> DeltaResult<ConsentRecord> {
+    purpose(purpose_id)?;
+    file_consent().grant(purpose_id, subject_id, expires_at_ms, source, request_id)
+}
+
+/// Record that `subject_id` withdrew consent for `purpose_id`.
+pub fn revoke_consent(
+    purpose_id: &str,
+    subject_id: &str,
+    source: &str,
+    request_id: &str,
+) -> DeltaResult<ConsentRecord> {
+    file_consent().revoke(purpose_id, subject_id, source, request_id)
+}
+
+/// The latest consent record of `subject_id` for `purpose_id`, if any.
+pub fn query_consent(purpose_id: &str, subject_id: &str) -> DeltaResult<Option<ConsentRecord>> {
+    file_consent().query(purpose_id, subject_id)
 }
 
 fn engines() -> &'static EngineRegistry {
     ENGINES.get_or_init(EngineRegistry::default)
 }
 
-/// Perform a single inference call using the currently active model.
+/// Perform a single inference call with the model serving `purpose_id`.
 pub fn infer_with_ctx(
     purpose_id: &str,
     subject_id: &str,
     input_json: &str,
-) -> DeltaResult<Prediction> {
-    let model = active_model().ok_or_else(|| DeltaError::model_missing("active_model"))?;
-    let context = build_context(purpose_id, subject_id, input_json);
+) -> Result<Prediction, InferError> {
+    let model = lock_pool()?.for_purpose(purpose_id)?;
+    infer_on(&model, purpose_id, subject_id, input_json)
+}
+
+/// Perform a single inference call with a specific loaded model; without
+/// `version` the newest loaded version of `model_id` is used.
+pub fn infer_with_model(
+    model_id: &ModelId,
+    version: Option<&VersionName>,
+    purpose_id: &str,
+    subject_id: &str,
+    input_json: &str,
+) -> Result<Prediction, InferError> {
+    let model = lock_pool()?
+        .get(model_id, version)
+        .ok_or_else(|| DeltaError::model_missing("model_not_loaded"))?;
+    infer_routed(&model, purpose_id, subject_id, input_json, true)
+}
+
+/// Score `(subject, input JSON)` items for `purpose_id` on the worker pool.
+///
+/// Every item is scored with the model serving the purpose when the call
+/// starts. Results keep the input order; a failing item only fails its own
+/// slot.
+pub fn infer_batch(
+    purpose_id: &str,
+    items: &[(String, String)],
+) -> DeltaResult<Vec<Result<Prediction, InferError>>> {
+    if items.len() > MAX_BATCH_ITEMS {
+        return Err(DeltaError::invalid("batch_too_large"));
+    }
+    let model = Arc::new(lock_pool()?.for_purpose(purpose_id)?);
+    check_purpose(purpose_id, model.kind)?;
+    infer_batch_on(&model, purpose_id, items)
+}
+
+fn infer_batch_on(
+    model: &Arc<ModelVersion>,
+    purpose_id: &str,
+    items: &[(String, String)],
+) -> DeltaResult<Vec<Result<Prediction, InferError>>> {
+    let store = consent_store()?;
+    Ok(infer_batch_in(
+        workers(),
+        store.as_ref(),
+        model,
+        purpose_id,
+        items,
+    ))
+}
+
+/// Items are queued in windows of at most the pool's queue capacity, so a
+/// large batch never fails with `worker_queue_full` on its own jobs.
+///
+/// Consent is checked on the calling thread before an item is queued: a
+/// host lookup (PHP FFI) may only be called from the host's own thread.
+fn infer_batch_in(
+    pool: &Pool,
+    store: &dyn ConsentStore,
+    model: &Arc<ModelVersion>,
+    purpose_id: &str,
+    items: &[(String, String)],
+) -> Vec<Result<Prediction, InferError>> {
+    let window = pool.metrics().queue_capacity.max(1);
+    let mut results = Vec::with_capacity(items.len());
+    for chunk in items.chunks(window) {
+        let handles = chunk
+            .iter()
+            .cloned()
+            .map(|(subject_id, input_json)| {
+                let context = build_context(purpose_id, &subject_id, &input_json);
+                ensure_consent(store, &context)?;
+                let (model, purpose_id) = (Arc::clone(model), purpose_id.to_string());
+                pool.submit(move || {
+                    infer_consented(&model, &purpose_id, &subject_id, &input_json, false)
+                })
+            })
+            .collect::<Vec<_>>();
+        results.extend(
+            handles
+                .into_iter()
+                .map(|handle| handle.and_then(|handle| handle.join())?),
+        );
+    }
+    results
+}
+
+/// Score every record of a stored dataset for `purpose_id` and store the
+/// outcomes as a new dataset.
+///
+/// Output rows are `{"row", "subject_id", "prediction"}` or `{"row",
+/// "subject_id", "error"}` in source order; the subject is the record's
+/// `subject_id` field, if any. The new dataset's schema records the source
+/// dataset and the model version used.
+pub fn score_dataset(purpose_id: &str, dataset_id: &DatasetId) -> DeltaResult<ScoreReport> {
+    let model = Arc::new(lock_pool()?.for_purpose(purpose_id)?);
+    score_dataset_with(&model, purpose_id, dataset_id)
+}
+
+fn score_dataset_with(
+    model: &Arc<ModelVersion>,
+    purpose_id: &str,
+    dataset_id: &DatasetId,
+) -> DeltaResult<ScoreReport> {
+    check_purpose(purpose_id, model.kind)?;
+    let records = data_service::load_records(dataset_id)?;
+    if records.len() > MAX_BATCH_ITEMS {
+        return Err(DeltaError::invalid("batch_too_large"));
+    }
+    let items = records
+        .iter()
+        .map(|record| {
+            let subject_id = record.get("subject_id").and_then(Value::as_str);
+            (
+                subject_id.unwrap_or_default().to_string(),
+                record.canonical(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut lines = Vec::with_capacity(items.len());
+    let mut failed = 0;
+    let results = infer_batch_on(model, purpose_id, &items)?;
+    for (row, ((subject_id, _), result)) in items.iter().zip(results).enumerate() {
+        let outcome = match result {
+            Ok(prediction) => format!("\"prediction\":{}", prediction.json),
+            Err(err) => {
+                failed += 1;
+                format!("\"error\":{}", err.to_json())
+            }
+        };
+        lines.push(format!(
+            "{{\"row\":{},\"subject_id\":\"{}\",{}}}",
+            row,
+            json::escape(subject_id),
+            outcome
+        ));
+    }
 
-    ensure_consent(consent_store(), &context)?;
+    let schema = format!(
+        "{{\"scored_from\":\"{}\",\"model_id\":\"{}\",\"version\":\"{}\",\"purpose_id\":\"{}\"}}",
+        json::escape(dataset_id.as_str()),
+        json::escape(model.id.as_str()),
+        json::escape(model.version.as_str()),
+        json::escape(purpose_id)
+    );
+    Ok(ScoreReport {
+        dataset_id: data_service::store_records(&lines, &schema)?,
+        source: dataset_id.clone(),
+        rows: lines.len(),
+        failed,
+    })
+}
+
+/// Queue depth and utilisation of the batch worker pool.
+pub fn worker_metrics() -> PoolMetrics {
+    workers().metrics()
+}
+
+/// Shared pool for batch scoring, sized by `DELTA1_THREADS`.
+fn workers() -> &'static Pool {
+    WORKERS.get_or_init(|| Pool::new(PoolCfg::from_app(&AppCfg::load())))
+}
 
+/// Score with the purpose's model, or with the model a routing rule names.
+fn infer_on(
+    model: &ModelVersion,
+    purpose_id: &str,
+    subject_id: &str,
+    input_json: &str,
+) -> Result<Prediction, InferError> {
+    infer_routed(model, purpose_id, subject_id, input_json, false)
+}
+
+/// With `pinned` the caller chose the model, so routing rules do not
+/// replace it.
+fn infer_routed(
+    model: &ModelVersion,
+    purpose_id: &str,
+    subject_id: &str,
+    input_json: &str,
+    pinned: bool,
+) -> Result<Prediction, InferError> {
+    check_purpose(purpose_id, model.kind)?;
+    let context = build_context(purpose_id, subject_id, input_json);
+    ensure_consent(consent_store()?.as_ref(), &context)?;
+    infer_consented(model, purpose_id, subject_id, input_json, pinned)
+}
+
+/// [`infer_routed`] once consent for `subject_id` has been checked.
+fn infer_consented(
+    model: &ModelVersion,
+    purpose_id: &str,
+    subject_id: &str,
+    input_json: &str,
+    pinned: bool,
+) -> Result<Prediction, InferError> {
+    let context = build_context(purpose_id, subject_id, input_json);
     let router_ctx = RouterContext::from_payload(input_json, &context);
-    let decision = ensure_compatible(&model, router().route(&router_ctx));
-    let engines = engines();
+    let routed = router()?.route(&router_ctx);
+    let (model, decision) = resolve_route(model, purpose_id, routed, pinned)?;
+    let model = &model;
+    let purpose = check_purpose(purpose_id, model.kind)?;
 
     let start = time::now_ms();
-    let response = match engines.infer(decision.target, &model, input_json) {
-        Ok(resp) => resp,
-        Err(err) => {
-            if decision.target == RouteTarget::Text {
-                engines.infer(RouteTarget::Tabular, &model, input_json)?
-            } else {
-                return Err(err);
-            }
-        }
-    };
+    let response = engines().infer(decision.target, model, input_json)?;
     let latency = time::now_ms().saturating_sub(start) as u32;
 
-    let mut body = merge_payload(&response.payload, &model, decision, response.confidence);
+    let pii = json::parse(input_json)
+        .map(|record| risk::detect_pii(&record))
+        .unwrap_or_default();
+    let assessment = purpose
+        .risk
+        .classify(response.confidence, model.metadata.card.risk, &pii);
+
+    let mut body = merge_payload(
+        &response.payload,
+        model,
+        &decision,
+        response.confidence,
+        &assessment,
+    );
     let whylog = build_whylog(&body, &response);
     append_whylog_hash(&mut body, &whylog.hash);
 
+    let mut review_id = None;
+    if assessment.review {
+        let ticket = reviews().open(ReviewRequest {
+            purpose_id: purpose_id.to_string(),
+            subject_hash: file_consent().hash_subject(subject_id)?,
+            model_id: model.id.as_str().to_string(),
+            version: model.version.as_str().to_string(),
+            whylog_hash: whylog.hash.clone(),
+            risk: assessment.level,
+            reasons: assessment.reasons.clone(),
+            suggestion: body,
+        })?;
+        body = review_payload(&ticket, &decision, &assessment);
+        review_id = Some(ticket.review_id);
+    }
+
     Ok(Prediction {
         json: body,
         latency_ms: latency,
         confidence: response.confidence,
         whylog,
+        review_id,
     })
 }
 
-/// Convenience wrapper that reuses the active model but accepts typed identifiers.
-pub fn infer_with_model(
-    model_id: &ModelId,
-    version: Option<&VersionName>,
+/// The model and engine that serve a routing decision.
+///
+/// A rule naming a model swaps in that loaded model unless the caller pinned
+/// one; a pinned model is then reported with the reason `pinned`. A rule
+/// asking for another family than the model's swaps in the newest loaded
+/// model of that family the purpose allows, or fails with
+/// `route_family_unavailable`. The engine is always the family of the model
+/// that scores, so `route` and `route_reason` describe the route taken.
+fn resolve_route(
+    model: &ModelVersion,
     purpose_id: &str,
-    subject_id: &str,
-    input_json: &str,
-) -> DeltaResult<Prediction> {
-    let active = active_model().ok_or_else(|| DeltaError::model_missing("active_model"))?;
-    if active.id.as_str() != model_id.as_str() {
-        return Err(DeltaError::model_missing("active_model_mismatch"));
-    }
-    if let Some(ver) = version {
-        if !ver.as_str().is_empty() && active.version.as_str() != ver.as_str() {
-            return Err(DeltaError::model_missing("active_version_mismatch"));
+    decision: RouteDecision,
+    pinned: bool,
+) -> DeltaResult<(ModelVersion, Route)> {
+    let RouteDecision {
+        target,
+        mut reason,
+        model: named,
+    } = decision;
+    let family = route_target_for_model(model.kind);
+    let model = match (named, target) {
+        (Some((id, version)), _) if !pinned => lock_pool()?
+            .get(&id, version.as_ref())
+            .ok_or_else(|| DeltaError::model_missing("route_model_not_loaded"))?,
+        (Some((id, version)), _) => {
+            if id != model.id || version.is_some_and(|version| version != model.version) {
+                reason = "pinned".to_string();
+            }
+            model.clone()
         }
-    }
-    infer_with_ctx(purpose_id, subject_id, input_json)
+        (None, Some(target)) if target != family && !pinned => {
+            let purpose = purpose(purpose_id)?;
+            lock_pool()?
+                .newest(|candidate| {
+                    route_target_for_model(candidate.kind) == target
+                        && purpose.allows(candidate.kind)
+                })
+                .ok_or_else(|| DeltaError::model_missing("route_family_unavailable"))?
+        }
+        _ => model.clone(),
+    };
+    let target = target.unwrap_or_else(|| route_target_for_model(model.kind));
+    ensure_compatible(&model, target)?;
+    Ok((model, Route { target, reason }))
+}
+
+/// Response for a decision held back for review: the ticket instead of the
+/// model output.
+fn review_payload(ticket: &ReviewTicket, decision: &Route, assessment: &RiskAssessment) -> String {
+    format!(
+        "{{\"ok\":false,\"code\":{},\"msg\":\"needs_human_review\",\"status\":\"needs_human_review\",\"review_id\":\"{}\",\"model_id\":\"{}\",\"version\":\"{}\",\"route\":\"{}\",\"route_reason\":\"{}\",{},\"whylog_hash\":\"{}\"}}",
+        DeltaCode::HitlRequired as u32,
+        ticket.review_id,
+        json::escape(&ticket.model_id),
+        json::escape(&ticket.version),
+        decision.target.as_str(),
+        decision.reason.as_str(),
+        risk::to_json_members(assessment),
+        ticket.whylog_hash
+    )
 }
 
 fn merge_payload(
     engine_payload: &str,
     model: &ModelVersion,
-    decision: RouteDecision,
+    decision: &Route,
     confidence: f32,
+    assessment: &RiskAssessment,
 ) -> String {
-    let mut base = engine_payload.trim().trim().to_string();
+    let mut base = engine_payload.trim().to_string();
     if !base.starts_with('{') {
         base.insert(0, '{');
     }
@@ -131,12 +654,13 @@ fn merge_payload(
     }
 
     body.push_str(&format!(
-        "\"model_id\":\"{}\",\"version\":\"{}\",\"route\":\"{}\",\"route_reason\":\"{}\",\"confidence\":{:.4}",
+        "\"model_id\":\"{}\",\"version\":\"{}\",\"route\":\"{}\",\"route_reason\":\"{}\",\"confidence\":{:.4},{}",
         json::escape(model.id.as_str()),
         json::escape(model.version.as_str()),
         decision.target.as_str(),
         decision.reason.as_str(),
         confidence,
+        risk::to_json_members(assessment),
     ));
     body.push('}');
     body
@@ -173,7 +697,7 @@ impl EngineRegistry {
         target: RouteTarget,
         model: &ModelVersion,
         payload: &str,
-    ) -> DeltaResult<EngineResponse> {
+    ) -> Result<EngineResponse, InferError> {
         match target {
             RouteTarget::Tabular => self.tabular.infer(model, payload),
             RouteTarget::Text => self.text.infer(model, payload),
@@ -184,31 +708,97 @@ impl EngineRegistry {
 #[derive(Default)]
 struct TabularEngine;
 
+/// Tabular artefact with the feature pipeline used to score it.
+struct TabularModel {
+    artefact: ModelArtefact,
+    pipeline: FeaturePipeline,
+}
+
+impl TabularModel {
+    fn load(model: &ModelVersion) -> DeltaResult<Self> {
+        let artefact = training_service::load_verified_artefact(model)?;
+        let pipeline = match &artefact.pipeline {
+            Some(pipeline) => pipeline.clone(),
+            None => FeaturePipeline::from_manifest(&artefact.features)?,
+        };
+        let expected_weights = match artefact.kind {
+            ModelKind::TabularGradientBoosting => 1,
+            _ => pipeline.width() + 1,
+        };
+        if artefact.weights.len() != expected_weights {
+            return Err(DeltaError::model_missing("artefact_weights_mismatch"));
+        }
+        Ok(Self { artefact, pipeline })
+    }
+}
+
 impl super::domain::InferEngine for TabularEngine {
     fn kind(&self) -> RouteTarget {
         RouteTarget::Tabular
     }
 
-    fn infer(&self, model: &ModelVersion, input: &str) -> DeltaResult<EngineResponse> {
-        let mut features = json::top_level_keys(input);
-        features.retain(|key| key != "context" && key != "text");
-        let saliency = features.iter().take(5).cloned().collect::<Vec<_>>();
-        let score = deterministic_score(model, input);
+    fn infer(&self, model: &ModelVersion, input: &str) -> Result<EngineResponse, InferError> {
+        let loaded = cached_model(model, RouteTarget::Tabular, || {
+            let tabular = TabularModel::load(model)?;
+            let bytes = tabular.artefact.encode().len();
+            Ok((LoadedModel::Tabular(tabular), bytes))
+        })?;
+        let LoadedModel::Tabular(TabularModel { artefact, pipeline }) = loaded.as_ref() else {
+            return Err(DeltaError::internal("artefact_cache_kind").into());
+        };
+
+        let record = parse_record(input)?;
+        let vector = pipeline
+            .transform_checked(&record)
+            .map_err(InferError::fields)?;
+        let raw = trainers::predict_proba(&artefact.weights, &artefact.trees, &vector);
+        let score = match &artefact.calibration {
+            Some(calibrator) => calibrator.apply(raw),
+            None => raw,
+        };
+        let contributions = trainers::contributions(&artefact.weights, &artefact.trees, &vector);
+        let saliency = top_features(pipeline.output_names(), &contributions, 5);
         let payload = format!(
-            "{{\"ok\":true,\"mode\":\"tabular\",\"score\":{:.4},\"features\":{}}}",
+            "{{\"ok\":true,\"mode\":\"tabular\",\"score\":{:.4},\"calibrated\":{},\"features\":{}}}",
             score,
+            artefact.calibration.is_some(),
             json::build_string_array(&saliency)
         );
 
+        // The confidence is the probability of the predicted class.
         Ok(EngineResponse {
             payload,
-            confidence: 0.5 + score * 0.5,
+            confidence: score.max(1.0 - score),
             saliency,
             rationale: "tabular-local-surrogate".to_string(),
         })
     }
 }
 
+/// The input as a JSON object.
+fn parse_record(input: &str) -> Result<Value, InferError> {
+    json::parse(input)
+        .ok()
+        .filter(|record| record.as_object().is_some())
+        .ok_or_else(|| DeltaError::invalid("input_invalid").into())
+}
+
+/// Names of the `limit` pipeline outputs that moved the score most, by the
+/// magnitude of their [`trainers::contributions`], strongest first.
+fn top_features(names: Vec<String>, contributions: &[f32], limit: usize) -> Vec<String> {
+    let mut ranked = names
+        .into_iter()
+        .zip(contributions.iter().map(|c| c.abs()))
+        .filter(|(_, weight)| *weight > 0.0)
+        .collect::<Vec<_>>();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked
+        .into_iter()
+        .take(limit)
+        .map(|(name, _)| name)
+        .collect()
+}
+
 #[derive(Default)]
 struct TextEngine;
 
@@ -217,46 +807,154 @@ impl super::domain::InferEngine for TextEngine {
         RouteTarget::Text
     }
 
-    fn infer(&self, model: &ModelVersion, input: &str) -> DeltaResult<EngineResponse> {
-        let text = json::extract_string(input, "text")
-            .ok_or_else(|| DeltaError::invalid("text_required"))?;
-        let tokens = text
-            .split_whitespace()
-            .filter(|token| !token.is_empty())
-            .map(|token| token.to_string())
+    fn infer(&self, model: &ModelVersion, input: &str) -> Result<EngineResponse, InferError> {
+        let record = parse_record(input)?;
+        let loaded = cached_model(model, RouteTarget::Text, || {
+            let (text_model, bytes) = TextModel::load(model)?;
+            Ok((LoadedModel::Text(Box::new(text_model)), bytes))
+        })?;
+        let LoadedModel::Text(text_model) = loaded.as_ref() else {
+            return Err(DeltaError::internal("artefact_cache_kind").into());
+        };
+        let field = text_model.text_field.as_str();
+        let text = match record.get(field) {
+            Some(Value::String(text)) => text.clone(),
+            None | Some(Value::Null) => {
+                return Err(InferError::fields(vec![FieldIssue::new(
+                    field,
+                    "feature_missing",
+                )]))
+            }
+            Some(_) => {
+                return Err(InferError::fields(vec![FieldIssue::new(
+                    field,
+                    "feature_type_mismatch",
+                )]))
+            }
+        };
+        let encoder = text_model
+            .encoder
+            .as_ref()
+            .filter(|encoder| encoder.config().labels > 0)
+            .ok_or(DeltaError::model_missing("text_head_missing"))?;
+
+        let encoding = text_model.tokenizer.encode(&text);
+        let mut classification = encoder.classify(&encoding.ids)?;
+        if let Some(calibrator) = &text_model.calibration {
+            if let [p] = classification.probabilities.as_mut_slice() {
+                *p = calibrator.apply(*p);
+            }
+        }
+
+        let mut tokens = encoding
+            .content_indices()
+            .map(|i| {
+                (
+                    encoding.tokens[i].clone(),
+                    classification.contributions[i].abs(),
+                )
+            })
+            .collect::<Vec<_>>();
+        tokens.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let saliency = tokens
+            .into_iter()
+            .take(5)
+            .map(|(token, _)| token)
             .collect::<Vec<_>>();
-        let saliency = tokens.iter().take(5).cloned().collect::<Vec<_>>();
-        let score = deterministic_score(model, input);
+        let score = classification.score();
         let payload = format!(
-            "{{\"ok\":true,\"mode\":\"text\",\"score\":{:.4},\"tokens\":{}}}",
+            "{{\"ok\":true,\"mode\":\"text\",\"score\":{:.4},\"label\":{},\"tokens\":{},\"token_count\":{},\"truncated\":{}}}",
             score,
-            json::build_string_array(&saliency)
+            classification.label,
+            json::build_string_array(&saliency),
+            encoding.len(),
+            encoding.truncated
         );
 
         Ok(EngineResponse {
             payload,
-            confidence: 0.4 + score * 0.6,
+            confidence: classification.confidence(),
             saliency,
-            rationale: "minilm-q4-saliency".to_string(),
+            rationale: "minilm-token-attribution".to_string(),
         })
     }
 }
 
-fn deterministic_score(model: &ModelVersion, input: &str) -> f32 {
-    let mut hasher = SimpleHash::new();
-    hasher.update(model.id.as_str().as_bytes());
-    hasher.update(input.as_bytes());
-    let raw = hasher.finish32();
-    (raw % 10_000) as f32 / 10_000.0
+/// Record field holding the input of text models without a text feature in their manifest.
+const TEXT_FIELD: &str = "text";
+
+/// Tokenizer and (when the artefact carries weights) encoder of a text model version.
+struct TextModel {
+    /// Record field holding the text: the manifest's text feature, else `"text"`.
+    text_field: String,
+    tokenizer: WordPieceTokenizer,
+    encoder: Option<Encoder>,
+    calibration: Option<Calibrator>,
 }
 
-/// Helper used in tests to clear the active model state.
-#[cfg(test)]
-pub(crate) fn reset_state() {
-    if let Some(lock) = ACTIVE_MODEL.get() {
-        if let Ok(mut guard) = lock.lock() {
-            *guard = None;
+impl TextModel {
+    /// Load `tokenizer.json` and the encoder tensors, with the estimated size
+    /// of the dequantised weights.
+    fn load(model: &ModelVersion) -> DeltaResult<(Self, usize)> {
+        let raw = match training_service::load_asset(model, "tokenizer.json") {
+            Ok(raw) => raw,
+            Err(err) if err.msg == "model_asset" => {
+                return Err(DeltaError::model_missing("tokenizer_missing"))
+            }
+            Err(err) => return Err(err),
+        };
+        let mut bytes = raw.len();
+        let raw = String::from_utf8(raw).map_err(|_| DeltaError::invalid("tokenizer_invalid"))?;
+        let tokenizer = WordPieceTokenizer::from_json(&raw)?;
+
+        let artefact = match training_service::load_verified_artefact(model) {
+            Ok(artefact) => Some(artefact),
+            Err(err) if err.msg == "model_artefact" => None,
+            Err(err) => return Err(err),
+        };
+        let text_field = artefact
+            .as_ref()
+            .and_then(|artefact| {
+                let mut text = artefact
+                    .features
+                    .iter()
+                    .filter(|feature| feature.kind == FeatureKind::Text);
+                match (text.next(), text.next()) {
+                    (Some(feature), None) => Some(feature.name.clone()),
+                    _ => None,
+                }
+            })
+            .unwrap_or_else(|| TEXT_FIELD.to_string());
+        let (encoder, calibration) = match artefact {
+            Some(artefact) if !artefact.tensors.is_empty() => {
+                bytes += artefact
+                    .tensors
+                    .iter()
+                    .map(|tensor| tensor.shape.iter().product::<usize>() * 4)
+                    .sum::<usize>();
+                (
+                    Some(Encoder::from_artefact(&artefact)?),
+                    artefact.calibration,
+                )
+            }
+            _ => (None, None),
+        };
+        if let Some(encoder) = &encoder {
+            let config = encoder.config();
+            if tokenizer.max_length() > config.max_positions
+                || tokenizer.vocab_size() > config.vocab_size
+            {
+                return Err(DeltaError::model_missing("tokenizer_encoder_mismatch"));
+            }
         }
+
+        let text_model = Self {
+            text_field,
+            tokenizer,
+            encoder,
+            calibration,
+        };
+        Ok((text_model, bytes))
     }
 }
 
@@ -264,29 +962,577 @@ pub(crate) fn reset_state() {
 mod tests {
     use super::*;
 
-    fn test_model() -> ModelVersion {
-        ModelVersion {
-            id: ModelId::new("tabular-logreg-test"),
-            version: VersionName::new("v1"),
-            kind: ModelKind::TabularLogistic,
-            artefact_path: "models/test.bin".to_string(),
-            metadata: crate::training::domain::ModelMetadata::default(),
+    /// Register the purposes used by these tests in the test data root.
+    fn register_purposes() {
+        static INIT: std::sync::Once = std::sync::Once::new();
+        INIT.call_once(|| {
+            crate::common::config::use_test_data_root();
+            let cfg = AppCfg::load();
+            let entry = |id: &str, models: &str| {
+                format!("{{\"id\":\"{id}\",\"description\":\"test\",\"data_categories\":[],\"models\":[{models}],\"retention_days\":30}}")
+            };
+            // Every `triage` decision falls in the high-risk confidence band.
+            let triage = entry("triage", "\"tabular\"")
+                .replace("}", ",\"risk\":{\"high_below\":1.0,\"pii\":\"minimal\"}}");
+            // `screening` opts in to holding back decisions on PII.
+            let screening = entry("screening", "\"tabular\"")
+                .replace("}", ",\"risk\":{\"pii\":\"high\"}}");
+            let raw = format!(
+                "{{\"purposes\":[{},{},{},{},{},{},{},{}]}}",
+                entry("purpose", "\"tabular\",\"text\""),
+                entry("batch", "\"tabular\""),
+                entry("credit", "\"tabular-logreg\""),
+                entry("support", "\"text\""),
+                triage,
+                entry("routed", "\"tabular\""),
+                entry("helpdesk", "\"text\""),
+                screening
+            );
+            std::fs::create_dir_all(&cfg.data_root).unwrap();
+            std::fs::write(&cfg.purposes_file, raw).unwrap();
+            assert_eq!(reload_purposes().unwrap(), 8);
+        });
+    }
+
+    /// Logistic model over `amount`, trained on a fixture unique to `name`.
+    fn test_model(name: &str) -> ModelVersion {
+        register_purposes();
+        let lines = (0..6)
+            .map(|i| {
+                format!(
+                    "{{\"amount\":{i},\"label\":{},\"fixture\":\"{name}\"}}",
+                    i / 3
+                )
+            })
+            .collect::<Vec<_>>();
+        let lines = lines.iter().map(String::as_str).collect::<Vec<_>>();
+        let dataset = crate::data::service::ingest_fixture(name, &lines);
+        let cfg = "{\"dp\":{\"enabled\":false},\"fairness\":{\"delta_tpr\":0.01,\"delta_fpr\":0.01,\"delta_ppv\":0.01},\"features\":[{\"name\":\"amount\",\"type\":\"numeric\"}]}";
+        training_service::train(dataset, cfg).unwrap()
+    }
+
+    fn consent(purpose_id: &str, subjects: &[&str]) {
+        register_purposes();
+        for subject in subjects {
+            grant_consent(purpose_id, subject, None, "test", "req-test").unwrap();
         }
     }
 
     #[test]
     fn router_falls_back_when_text_missing() {
-        reset_state();
-        register_active_model(test_model());
-        let payload = "{\"text\":123}";
+        consent("purpose", &["subject"]);
+        register_active_model(test_model("inference-router")).unwrap();
+        let payload = "{\"text\":123,\"amount\":4}";
         let prediction = infer_with_ctx("purpose", "subject", payload).unwrap();
         assert!(prediction.json.contains("\"route\":\"tabular\""));
     }
 
+    #[test]
+    fn tabular_engine_replays_the_trained_pipeline() {
+        let dataset = crate::data::service::ingest_fixture(
+            "inference-pipeline",
+            &[
+                "{\"amount\":10,\"country\":\"NL\",\"label\":1}",
+                "{\"amount\":90,\"country\":\"BE\",\"label\":0}",
+            ],
+        );
+        let cfg = "{\"dp\":{\"enabled\":false},\"fairness\":{\"delta_tpr\":0.01,\"delta_fpr\":0.01,\"delta_ppv\":0.01},\"features\":[{\"name\":\"amount\",\"type\":\"numeric\"},{\"name\":\"country\",\"type\":\"onehot\",\"impute\":\"other\"}]}";
+        let model = training_service::train(dataset, cfg).unwrap();
+
+        let response = TabularEngine
+            .infer(&model, "{\"amount\":90,\"country\":\"DE\"}")
+            .unwrap();
+        // The `other` bucket never occurred in training, so its zero weight
+        // does not move the score and it is not reported.
+        assert_eq!(response.saliency, vec!["amount"]);
+        assert!(response.payload.contains("\"calibrated\":false"));
+
+        let err = TabularEngine
+            .infer(&model, "{\"country\":\"NL\"}")
+            .unwrap_err();
+        assert_eq!(err.error.msg, "feature_missing");
+
+        let err = TabularEngine
+            .infer(&model, "{\"amount\":\"ten\",\"country\":[1]}")
+            .unwrap_err();
+        assert_eq!(
+            err.error.code as u32,
+            crate::common::error::DeltaCode::InvalidInput as u32
+        );
+        assert_eq!(
+            err.fields,
+            vec![
+                FieldIssue::new("amount", "feature_type_mismatch"),
+                FieldIssue::new("country", "feature_type_mismatch"),
+            ]
+        );
+        assert!(err.to_json().contains("\"fields\":[{\"field\":\"amount\""));
+
+        // Whitespace in the input no longer changes the prediction.
+        let spaced = TabularEngine
+            .infer(&model, "{ \"amount\" : 90 ,\n \"country\": \"DE\" }")
+            .unwrap();
+        assert_eq!(spaced.payload, response.payload);
+    }
+
+    #[test]
+    fn text_engine_requires_a_tokenizer_and_a_classification_head() {
+        use crate::training::domain::{FeatureKind, FeatureSpec, ModelKind, ModelRepo};
+        use crate::training::repo_fs::FsModelRepo;
+
+        // Training refuses text models without an encoder, so store a bare
+        // text artefact over a tabular version.
+        let mut model = test_model("inference-tokenizer");
+        model.kind = ModelKind::TextMiniLm;
+        let mut artefact = ModelArtefact::for_version(&model);
+        artefact.features = vec![FeatureSpec::new("text", FeatureKind::Text)];
+        FsModelRepo::new(&crate::common::config::AppCfg::load())
+            .put_model(&model, &artefact)
+            .unwrap();
+        let input = "{\"text\":\"Banking fees\"}";
+
+        let err = TextEngine.infer(&model, input).unwrap_err();
+        assert_eq!(err.error.msg, "tokenizer_missing");
+
+        let dir = std::path::Path::new(&crate::common::config::AppCfg::load().data_root)
+            .join(&model.artefact_path)
+            .with_file_name("tokenizer.json");
+        std::fs::write(
+            dir,
+            "{\"model\":{\"vocab\":[\"[PAD]\",\"[UNK]\",\"[CLS]\",\"[SEP]\",\"bank\",\"##ing\"]}}",
+        )
+        .unwrap();
+        let err = TextEngine.infer(&model, "{\"body\":\"fees\"}").unwrap_err();
+        assert_eq!(err.fields, vec![FieldIssue::new("text", "feature_missing")]);
+        let err = TextEngine.infer(&model, input).unwrap_err();
+        assert_eq!(err.error.msg, "text_head_missing");
+    }
+
+    /// Imported text model with an int8 encoder and classification head; the
+    /// tokenizer names `name` so every fixture gets its own model id.
+    fn text_model(name: &str) -> ModelVersion {
+        register_purposes();
+        let mut artefact = super::super::encoder::test_artefact(10, 1);
+        artefact.tensors = artefact.tensors.iter().map(|t| t.quantize_int8()).collect();
+        artefact.set_meta("encoder.heads", "2");
+        let tokenizer = format!(
+            "{{\"model\":{{\"vocab\":[\"[PAD]\",\"[UNK]\",\"[CLS]\",\"[SEP]\",\"bank\",\"##ing\",\"fees\"]}},\"truncation\":{{\"max_length\":16}},\"fixture\":\"{name}\"}}"
+        );
+        training_service::import_encoder(artefact, &tokenizer).unwrap()
+    }
+
+    #[test]
+    fn text_engine_scores_with_the_stored_encoder() {
+        let model = text_model("inference-encoder");
+        let input = "{\"text\":\"banking fees\"}";
+        let first = TextEngine.infer(&model, input).unwrap();
+        assert_eq!(first.rationale, "minilm-token-attribution");
+        assert_eq!(first.saliency.len(), 3);
+        assert!(first.saliency.iter().any(|token| token == "##ing"));
+        assert!(first.confidence >= 0.5 && first.confidence <= 1.0);
+        assert_eq!(
+            first.payload,
+            TextEngine.infer(&model, input).unwrap().payload
+        );
+    }
+
+    #[test]
+    fn text_heads_read_the_column_they_were_trained_on() {
+        let encoder = text_model("inference-head-column-encoder");
+        let dataset = crate::data::service::ingest_fixture(
+            "inference-head-column",
+            &[
+                "{\"review\":\"bank fees\",\"label\":1}",
+                "{\"review\":\"banking\",\"label\":0}",
+            ],
+        );
+        let cfg = format!(
+            "{{\"dp\":{{\"enabled\":false}},\"model_kind\":\"text_minilm\",\"fairness\":{{\"delta_tpr\":0.01,\"delta_fpr\":0.01,\"delta_ppv\":0.01}},\"encoder\":{{\"model_id\":\"{}\",\"version\":\"{}\",\"text_column\":\"review\"}}}}",
+            encoder.id.as_str(),
+            encoder.version.as_str()
+        );
+        let head = training_service::train(dataset, &cfg).unwrap();
+        load_pooled_model(head.clone()).unwrap();
+        consent("purpose", &["rea"]);
+
+        let served = infer_with_model(
+            &head.id,
+            Some(&head.version),
+            "purpose",
+            "rea",
+            "{\"review\":\"bank fees\"}",
+        )
+        .unwrap();
+        assert!(served.json.contains("\"mode\":\"text\""));
+        let err = infer_with_model(
+            &head.id,
+            Some(&head.version),
+            "purpose",
+            "rea",
+            "{\"text\":\"bank fees\"}",
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.fields,
+            vec![FieldIssue::new("review", "feature_missing")]
+        );
+    }
+
+    #[test]
+    fn text_purposes_are_served_by_the_text_engine() {
+        let model = text_model("inference-text-purpose");
+        load_pooled_model(model.clone()).unwrap();
+        set_purpose_model("support", &model.id, Some(&model.version)).unwrap();
+        consent("support", &["hana"]);
+
+        // Short texts take the default route, which keeps the model's family.
+        let short = infer_with_ctx("support", "hana", "{\"text\":\"banking fees\"}").unwrap();
+        assert!(short.json.contains("\"mode\":\"text\""));
+        assert!(short.json.contains("\"route\":\"text\""));
+        assert!(short.json.contains("\"route_reason\":\"default\""));
+        let long = format!("{{\"text\":\"{}\"}}", "bank fees ".repeat(30));
+        let long = infer_with_ctx("support", "hana", &long).unwrap();
+        assert!(long.json.contains("\"route_reason\":\"long_text\""));
+
+        // `support` allows no tabular model, so `features_only` has nowhere to go.
+        let err = infer_with_ctx(
+            "support",
+            "hana",
+            "{\"text\":\"fees\",\"context\":{\"features_only\":true}}",
+        )
+        .unwrap_err();
+        assert_eq!(err.error.msg, "route_family_unavailable");
+    }
+
+    #[test]
+    fn one_process_serves_text_and_tabular_purposes_side_by_side() {
+        let tabular = test_model("inference-pool-tabular");
+        let text = text_model("inference-pool-text");
+        load_pooled_model(tabular.clone()).unwrap();
+        load_pooled_model(text.clone()).unwrap();
+        set_purpose_model("credit", &tabular.id, Some(&tabular.version)).unwrap();
+        set_purpose_model("helpdesk", &text.id, Some(&text.version)).unwrap();
+        consent("credit", &["jan"]);
+        consent("helpdesk", &["jan"]);
+
+        for _ in 0..2 {
+            let credit = infer_with_ctx("credit", "jan", "{\"amount\":4}").unwrap();
+            assert!(credit.json.contains("\"mode\":\"tabular\""));
+            assert!(credit
+                .json
+                .contains(&format!("\"model_id\":\"{}\"", tabular.id.as_str())));
+            let helpdesk = infer_with_ctx("helpdesk", "jan", "{\"text\":\"bank fees\"}").unwrap();
+            assert!(helpdesk.json.contains("\"mode\":\"text\""));
+            assert!(helpdesk
+                .json
+                .contains(&format!("\"model_id\":\"{}\"", text.id.as_str())));
+        }
+
+        let items = vec![("jan".to_string(), "{\"text\":\"fees\"}".to_string()); 3];
+        for result in infer_batch("helpdesk", &items).unwrap() {
+            assert!(result.unwrap().json.contains("\"route\":\"text\""));
+        }
+    }
+
+    #[test]
+    fn family_rules_pick_a_loaded_model_of_that_family() {
+        let tabular = test_model("inference-route-family");
+        let text = text_model("inference-route-family-text");
+        load_pooled_model(text.clone()).unwrap();
+        consent("purpose", &["ivo"]);
+        consent("routed", &["ivo"]);
+        let long = format!("{{\"text\":\"{}\",\"amount\":1}}", "bank fees ".repeat(30));
+
+        // `purpose` allows text models, so the long text is scored by one.
+        let routed = infer_on(&tabular, "purpose", "ivo", &long).unwrap();
+        assert!(routed.json.contains("\"mode\":\"text\""));
+        assert!(routed.json.contains("\"route\":\"text\""));
+        assert!(routed.json.contains("\"route_reason\":\"long_text\""));
+        assert!(!routed
+            .json
+            .contains(&format!("\"model_id\":\"{}\"", tabular.id.as_str())));
+
+        // `routed` only allows tabular models.
+        let err = infer_on(&tabular, "routed", "ivo", &long).unwrap_err();
+        assert_eq!(err.error.msg, "route_family_unavailable");
+        // A pinned model cannot be swapped for one of the requested family.
+        load_pooled_model(tabular.clone()).unwrap();
+        let err = infer_with_model(&tabular.id, Some(&tabular.version), "purpose", "ivo", &long)
+            .unwrap_err();
+        assert_eq!(err.error.msg, "route_family_mismatch");
+    }
+
+    #[test]
+    fn archiving_drops_the_cached_artefact() {
+        use crate::training::domain::ModelStage;
+
+        let model = test_model("inference-cache");
+        let input = "{\"amount\":5}";
+        let first = TabularEngine.infer(&model, input).unwrap();
+        let hits = artefact_cache_stats().hits;
+        assert_eq!(
+            TabularEngine.infer(&model, input).unwrap().payload,
+            first.payload
+        );
+        assert!(artefact_cache_stats().hits > hits);
+
+        training_service::promote_model(&model.id, &model.version, ModelStage::Archived, None)
+            .unwrap();
+        assert_eq!(evict_artefacts(&model.id, None), 0);
+        TabularEngine.infer(&model, input).unwrap();
+        assert_eq!(evict_artefacts(&model.id, Some(&model.version)), 1);
+    }
+
+    #[test]
+    fn batches_keep_order_and_fail_per_item() {
+        use crate::inference::domain::{batch_to_json, parse_batch};
+
+        let model = Arc::new(test_model("inference-batch"));
+        let items = parse_batch(
+            "[{\"subject_id\":\"s0\",\"input\":{\"amount\":1}},\
+              {\"subject_id\":\"s1\",\"input\":{\"amount\":\"x\"}},\
+              {\"subject_id\":\"s2\",\"input\":{\"amount\":5}}]",
+        )
+        .unwrap();
+        assert_eq!(
+            parse_batch("[{\"input\":{}}]").unwrap_err().msg,
+            "batch_item_invalid"
+        );
+
+        // `s2` has no consent and only fails its own slot.
+        consent("batch", &["s0", "s1", "a"]);
+        let results = infer_batch_on(&model, "batch", &items).unwrap();
+        assert_eq!(results.len(), 3);
+        let single = infer_on(&model, "batch", "s0", &items[0].1).unwrap();
+        assert_eq!(results[0].as_ref().unwrap().json, single.json);
+        let err = results[1].as_ref().unwrap_err();
+        assert_eq!(
+            err.fields,
+            vec![FieldIssue::new("amount", "feature_type_mismatch")]
+        );
+        let err = results[2].as_ref().unwrap_err();
+        assert_eq!(err.error.msg, "no_consent");
+        assert!(batch_to_json(&results).contains("},{\"ok\":false,\"code\":4"));
+
+        // Under `reject`, batches larger than the queue are submitted in windows.
+        let small = Pool::new(PoolCfg {
+            threads: 1,
+            queue_capacity: 2,
+            when_full: crate::inference::workers::QueueFull::Reject,
+        });
+        // Consent lookups run on the calling thread, never on a worker.
+        let caller = std::thread::current().id();
+        let lookup = CallbackConsentStore::new(
+            Box::new(move |_, _| Ok(std::thread::current().id() == caller)),
+            0,
+            b"key".to_vec(),
+        );
+        let many = (0..7)
+            .map(|i| (format!("w{i}"), items[0].1.clone()))
+            .collect::<Vec<_>>();
+        let windowed = infer_batch_in(&small, &lookup, &model, "batch", &many);
+        assert!(windowed.iter().all(|result| result.is_ok()));
+        assert_eq!(small.metrics().rejected, 0);
+        assert_eq!(lookup.stats().lookups, 7);
+
+        let source = crate::data::service::ingest_fixture(
+            "inference-batch-source",
+            &["{\"subject_id\":\"a\",\"amount\":2}", "{\"amount\":null}"],
+        );
+        let report = score_dataset_with(&model, "batch", &source).unwrap();
+        assert_eq!((report.rows, report.failed), (2, 1));
+        let scored = data_service::load_records(&report.dataset_id).unwrap();
+        assert_eq!(
+            scored[0].get("subject_id").and_then(Value::as_str),
+            Some("a")
+        );
+        assert!(scored[0].get("prediction").is_some());
+        assert!(scored[1].get("error").is_some());
+        let schema = data_service::dataset(&report.dataset_id).unwrap().schema;
+        assert!(schema.definition_json.contains(source.as_str()));
+    }
+
+    #[test]
+    fn inference_requires_recorded_consent() {
+        let model = test_model("inference-consent");
+        let input = "{\"amount\":3}";
+        let denied = infer_on(&model, "credit", "carol", input).unwrap_err();
+        assert_eq!(denied.error.msg, "no_consent");
+
+        grant_consent("credit", "carol", None, "portal", "req-10").unwrap();
+        infer_on(&model, "credit", "carol", input).unwrap();
+        revoke_consent("credit", "carol", "portal", "req-11").unwrap();
+        assert_eq!(
+            infer_on(&model, "credit", "carol", input)
+                .unwrap_err()
+                .error
+                .msg,
+            "no_consent"
+        );
+        let record = query_consent("credit", "carol").unwrap().unwrap();
+        assert_eq!(record.request_id, "req-11");
+    }
+
+    #[test]
+    fn unregistered_purposes_and_model_families_are_rejected() {
+        let model = test_model("inference-purpose");
+        consent("support", &["erin"]);
+        let input = "{\"amount\":2}";
+
+        // Consent can only be granted for registered purposes.
+        let err = grant_consent("marketing", "erin", None, "portal", "req-9").unwrap_err();
+        assert_eq!(err.code, DeltaCode::InvalidPurpose);
+
+        let err = infer_on(&model, "marketing", "erin", input).unwrap_err();
+        assert_eq!(
+            err.error.code as u32,
+            crate::common::error::DeltaCode::InvalidPurpose as u32
+        );
+        assert_eq!(
+            infer_on(&model, "support", "erin", input)
+                .unwrap_err()
+                .error
+                .msg,
+            "purpose_model_not_allowed"
+        );
+        assert_eq!(purpose("credit").unwrap().retention_days, 30);
+
+        load_pooled_model(model.clone()).unwrap();
+        assert_eq!(
+            set_purpose_model("marketing", &model.id, Some(&model.version))
+                .unwrap_err()
+                .msg,
+            "purpose_unknown"
+        );
+    }
+
+    #[test]
+    fn high_risk_decisions_wait_for_a_human_verdict() {
+        let model = test_model("inference-hitl");
+        consent("purpose", &["frank"]);
+        consent("triage", &["frank"]);
+        consent("screening", &["frank"]);
+
+        let plain = infer_on(&model, "screening", "frank", "{\"amount\":4}").unwrap();
+        assert!(plain.review_id.is_none());
+        assert!(plain.json.contains("\"risk\":\"minimal\""));
+
+        // PII only escalates for purposes that opt in.
+        let input = "{\"amount\":4,\"note\":\"reply to frank@example.nl\"}";
+        let listed = infer_on(&model, "purpose", "frank", input).unwrap();
+        assert!(listed.review_id.is_none());
+        assert!(listed.json.contains("\"risk\":\"minimal\""));
+        assert!(listed.json.contains("\"risk_reasons\":[\"pii:email\"]"));
+
+        let held = infer_on(&model, "screening", "frank", input).unwrap();
+        let review_id = held.review_id.clone().unwrap();
+        assert!(held.json.contains("\"status\":\"needs_human_review\""));
+        assert!(held.json.starts_with("{\"ok\":false,\"code\":7,"));
+        assert!(held.json.contains("\"risk_reasons\":[\"pii:email\"]"));
+        assert!(!held.json.contains("\"score\""));
+        assert!(pending_reviews()
+            .unwrap()
+            .iter()
+            .any(|ticket| ticket.review_id == review_id));
+
+        let ticket = decide_review(&review_id, ReviewStatus::Approved, "anna", "ok").unwrap();
+        assert_eq!(ticket.whylog_hash, held.whylog.hash);
+        assert!(ticket.suggestion.contains("\"score\""));
+        assert_eq!(
+            decide_review(&review_id, ReviewStatus::Rejected, "anna", "")
+                .unwrap_err()
+                .msg,
+            "review_already_decided"
+        );
+
+        let banded = infer_on(&model, "triage", "frank", input).unwrap();
+        let ticket = review(banded.review_id.as_deref().unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(ticket.reasons, vec!["confidence_band", "pii:email"]);
+        assert_eq!(ticket.status, ReviewStatus::Pending);
+    }
+
+    /// Puts the built-in routing rules back when a test that rewrote them
+    /// ends, even if it panicked, so no rules file outlives the test.
+    struct BuiltInRules;
+
+    impl Drop for BuiltInRules {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(AppCfg::load().router_rules_file);
+            if let Ok(mut rules) = routes().write() {
+                *rules = Arc::new(RuleRouter::default());
+            }
+        }
+    }
+
+    #[test]
+    fn routing_rules_can_pick_a_specific_model() {
+        crate::common::config::use_test_data_root();
+        let _restore = BuiltInRules;
+        let regular = test_model("inference-route-regular");
+        let vip = test_model("inference-route-vip");
+        load_pooled_model(regular.clone()).unwrap();
+        load_pooled_model(vip.clone()).unwrap();
+        consent("routed", &["gina"]);
+
+        // Keep the built-in rules for the other tests sharing the router.
+        let rules = format!(
+            "{{\"rules\":[\
+              {{\"name\":\"features_only\",\"when\":{{\"features_only\":true}},\"target\":\"tabular\"}},\
+              {{\"name\":\"vip\",\"when\":{{\"purpose\":\"routed\",\"subject\":{{\"tier\":\"vip\"}}}},\"model\":{{\"id\":\"{}\"}}}},\
+              {{\"name\":\"long_text\",\"when\":{{\"text_length_gt\":256}},\"target\":\"text\"}}]}}",
+            vip.id.as_str()
+        );
+        std::fs::write(AppCfg::load().router_rules_file, rules).unwrap();
+        assert_eq!(reload_router_rules().unwrap(), 3);
+
+        let tagged = "{\"amount\":2,\"context\":{\"subject\":{\"tier\":\"vip\"}}}";
+        let routed = infer_on(&regular, "routed", "gina", tagged).unwrap();
+        assert!(routed
+            .json
+            .contains(&format!("\"model_id\":\"{}\"", vip.id.as_str())));
+        assert!(routed.json.contains("\"route_reason\":\"vip\""));
+
+        let plain = infer_on(&regular, "routed", "gina", "{\"amount\":2}").unwrap();
+        assert!(plain
+            .json
+            .contains(&format!("\"model_id\":\"{}\"", regular.id.as_str())));
+        assert!(plain.json.contains("\"route_reason\":\"default\""));
+
+        // An explicitly requested model is kept.
+        let pinned = infer_with_model(
+            &regular.id,
+            Some(&regular.version),
+            "routed",
+            "gina",
+            tagged,
+        )
+        .unwrap();
+        assert!(pinned
+            .json
+            .contains(&format!("\"model_id\":\"{}\"", regular.id.as_str())));
+        assert!(pinned.json.contains("\"route_reason\":\"pinned\""));
+
+        std::fs::write(
+            AppCfg::load().router_rules_file,
+            "{\"rules\":[{\"name\":\"x\"}]}",
+        )
+        .unwrap();
+        assert_eq!(
+            reload_router_rules().unwrap_err().msg,
+            "router_target_invalid"
+        );
+        assert!(infer_on(&regular, "routed", "gina", tagged)
+            .unwrap()
+            .json
+            .contains("\"route_reason\":\"vip\""));
+    }
+
     #[test]
     fn whylog_hash_is_stable() {
-        reset_state();
-        register_active_model(test_model());
+        consent("purpose", &["subject"]);
+        register_active_model(test_model("inference-whylog")).unwrap();
         let payload = "{\"amount\":100,\"features_only\":true}";
         let result = infer_with_ctx("purpose", "subject", payload).unwrap();
         assert_eq!(result.whylog.hash.len(), 64);
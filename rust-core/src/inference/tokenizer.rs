@@ -0,0 +1,326 @@
+//! WordPiece tokenizer for MiniLM-style text models.
+//!
+//! The vocabulary is read from `models/{id}/{version}/tokenizer.json`, using the
+//! subset of the Hugging Face `tokenizers` layout that BERT-family models ship:
+//!
+//! ```text
+//! {
+//!   "model": {"type": "WordPiece", "vocab": {"[PAD]": 0, "[UNK]": 100, ...},
+//!             "unk_token": "[UNK]", "continuing_subword_prefix": "##",
+//!             "max_input_chars_per_word": 100},
+//!   "normalizer": {"lowercase": true},
+//!   "truncation": {"max_length": 128}
+//! }
+//! ```
+//!
+//! `"vocab"` may also be an array whose positions are the token IDs. Text is
+//! split on whitespace and punctuation, optionally lowercased, and each word is
+//! matched greedily longest-prefix-first against the vocabulary. Offsets are
+//! byte ranges into the original text so saliency can point at the input.
+
+use std::collections::HashMap;
+
+use crate::common::error::{DeltaError, DeltaResult};
+use crate::common::json::{self, Value};
+
+const DEFAULT_MAX_LENGTH: usize = 128;
+const DEFAULT_MAX_CHARS_PER_WORD: usize = 100;
+const MAX_SUPPORTED_LENGTH: usize = 4096;
+
+const CLS_TOKEN: &str = "[CLS]";
+const SEP_TOKEN: &str = "[SEP]";
+const PAD_TOKEN: &str = "[PAD]";
+
+/// Output of [`WordPieceTokenizer::encode`], including the `[CLS]` and `[SEP]` markers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Encoding {
+    pub ids: Vec<u32>,
+    pub tokens: Vec<String>,
+    /// Byte range of each token in the input; special tokens use `(0, 0)`.
+    pub offsets: Vec<(usize, usize)>,
+    /// Whether word pieces were dropped to respect the maximum length.
+    pub truncated: bool,
+}
+
+impl Encoding {
+    /// Number of tokens, special markers included.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Indices of the word-piece tokens, skipping `[CLS]` and `[SEP]`.
+    pub fn content_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len()).filter(|i| self.offsets[*i].1 > self.offsets[*i].0)
+    }
+}
+
+/// Greedy longest-match WordPiece tokenizer.
+#[derive(Clone, Debug)]
+pub struct WordPieceTokenizer {
+    vocab: HashMap<String, u32>,
+    tokens: HashMap<u32, String>,
+    unk_id: u32,
+    cls_id: u32,
+    sep_id: u32,
+    pad_id: Option<u32>,
+    prefix: String,
+    lowercase: bool,
+    max_length: usize,
+    max_chars_per_word: usize,
+}
+
+impl WordPieceTokenizer {
+    /// Parse a `tokenizer.json` document.
+    pub fn from_json(raw: &str) -> DeltaResult<Self> {
+        let invalid = || DeltaError::invalid("tokenizer_invalid");
+        let root = json::parse(raw).map_err(|_| invalid())?;
+        let model = root.get("model").ok_or_else(invalid)?;
+        if let Some(kind) = model.get("type").and_then(Value::as_str) {
+            if kind != "WordPiece" {
+                return Err(DeltaError::invalid("tokenizer_unsupported"));
+            }
+        }
+
+        let mut vocab = HashMap::new();
+        match model.get("vocab") {
+            Some(Value::Object(entries)) => {
+                for (token, id) in entries {
+                    let id = id
+                        .as_f64()
+                        .filter(|n| n.fract() == 0.0 && *n >= 0.0 && *n <= u32::MAX as f64)
+                        .ok_or_else(invalid)?;
+                    vocab.insert(token.clone(), id as u32);
+                }
+            }
+            Some(Value::Array(entries)) => {
+                for (id, token) in entries.iter().enumerate() {
+                    vocab.insert(token.as_str().ok_or_else(invalid)?.to_string(), id as u32);
+                }
+            }
+            _ => return Err(invalid()),
+        }
+        let tokens = vocab
+            .iter()
+            .map(|(token, id)| (*id, token.clone()))
+            .collect::<HashMap<_, _>>();
+        if tokens.len() != vocab.len() {
+            return Err(invalid());
+        }
+
+        let unk = model
+            .get("unk_token")
+            .and_then(Value::as_str)
+            .unwrap_or("[UNK]");
+        let special = |token: &str| {
+            vocab
+                .get(token)
+                .copied()
+                .ok_or(DeltaError::invalid("tokenizer_special_token_missing"))
+        };
+        let max_length = match root.get("truncation").and_then(|t| t.get("max_length")) {
+            None => DEFAULT_MAX_LENGTH,
+            Some(value) => value
+                .as_f64()
+                .filter(|n| n.fract() == 0.0 && *n >= 2.0 && *n <= MAX_SUPPORTED_LENGTH as f64)
+                .ok_or_else(invalid)? as usize,
+        };
+        let max_chars_per_word = model
+            .get("max_input_chars_per_word")
+            .and_then(Value::as_f64)
+            .filter(|n| *n >= 1.0)
+            .map(|n| n as usize)
+            .unwrap_or(DEFAULT_MAX_CHARS_PER_WORD);
+
+        Ok(Self {
+            unk_id: special(unk)?,
+            cls_id: special(CLS_TOKEN)?,
+            sep_id: special(SEP_TOKEN)?,
+            pad_id: vocab.get(PAD_TOKEN).copied(),
+            prefix: model
+                .get("continuing_subword_prefix")
+                .and_then(Value::as_str)
+                .unwrap_or("##")
+                .to_string(),
+            lowercase: root
+                .get("normalizer")
+                .and_then(|n| n.get("lowercase"))
+                .and_then(Value::as_bool)
+                .unwrap_or(true),
+            max_length,
+            max_chars_per_word,
+            vocab,
+            tokens,
+        })
+    }
+
+    pub fn vocab_size(&self) -> usize {
+        self.vocab.len()
+    }
+
+    /// Maximum sequence length, `[CLS]` and `[SEP]` included.
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    pub fn pad_id(&self) -> Option<u32> {
+        self.pad_id
+    }
+
+    pub fn token(&self, id: u32) -> Option<&str> {
+        self.tokens.get(&id).map(String::as_str)
+    }
+
+    /// Tokenise `text` into `[CLS] pieces… [SEP]`, truncated to [`Self::max_length`].
+    pub fn encode(&self, text: &str) -> Encoding {
+        let budget = self.max_length - 2;
+        let mut encoding = Encoding {
+            ids: vec![self.cls_id],
+            tokens: vec![CLS_TOKEN.to_string()],
+            offsets: vec![(0, 0)],
+            truncated: false,
+        };
+
+        'words: for (start, word) in pre_tokenize(text) {
+            for (id, range) in self.word_pieces(start, word) {
+                if encoding.ids.len() - 1 == budget {
+                    encoding.truncated = true;
+                    break 'words;
+                }
+                encoding.ids.push(id);
+                encoding
+                    .tokens
+                    .push(self.token(id).unwrap_or_default().to_string());
+                encoding.offsets.push(range);
+            }
+        }
+
+        encoding.ids.push(self.sep_id);
+        encoding.tokens.push(SEP_TOKEN.to_string());
+        encoding.offsets.push((0, 0));
+        encoding
+    }
+
+    /// WordPiece segmentation of one pre-tokenised word; unmatched words map to `[UNK]`.
+    fn word_pieces(&self, start: usize, word: &str) -> Vec<(u32, (usize, usize))> {
+        let unknown = vec![(self.unk_id, (start, start + word.len()))];
+        if word.chars().count() > self.max_chars_per_word {
+            return unknown;
+        }
+        let normalised = if self.lowercase {
+            word.to_lowercase()
+        } else {
+            word.to_string()
+        };
+        // Lowercasing can change byte lengths; offsets then fall back to whole-word spans.
+        let aligned = normalised.len() == word.len();
+
+        let mut pieces = Vec::new();
+        let mut begin = 0;
+        while begin < normalised.len() {
+            let mut end = normalised.len();
+            let mut matched = None;
+            while end > begin {
+                if normalised.is_char_boundary(end) {
+                    let piece = &normalised[begin..end];
+                    let id = if begin == 0 {
+                        self.vocab.get(piece)
+                    } else {
+                        self.vocab.get(&format!("{}{}", self.prefix, piece))
+                    };
+                    if let Some(id) = id {
+                        matched = Some(*id);
+                        break;
+                    }
+                }
+                end -= 1;
+            }
+            let Some(id) = matched else {
+                return unknown;
+            };
+            let range = if aligned {
+                (start + begin, start + end)
+            } else {
+                (start, start + word.len())
+            };
+            pieces.push((id, range));
+            begin = end;
+        }
+        pieces
+    }
+}
+
+/// Split on whitespace and isolate punctuation, keeping byte offsets into `text`.
+fn pre_tokenize(text: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut current: Option<usize> = None;
+    for (index, ch) in text.char_indices() {
+        let punctuation = !ch.is_whitespace() && is_punctuation(ch);
+        if ch.is_whitespace() || ch.is_control() || punctuation {
+            if let Some(start) = current.take() {
+                words.push((start, &text[start..index]));
+            }
+            if punctuation {
+                words.push((index, &text[index..index + ch.len_utf8()]));
+            }
+        } else if current.is_none() {
+            current = Some(index);
+        }
+    }
+    if let Some(start) = current {
+        words.push((start, &text[start..]));
+    }
+    words
+}
+
+/// BERT treats every ASCII symbol as punctuation, plus Unicode punctuation.
+fn is_punctuation(ch: char) -> bool {
+    ch.is_ascii_punctuation()
+        || matches!(ch, '\u{2000}'..='\u{206F}' | '\u{3000}'..='\u{303F}' | '¡' | '¿' | '«' | '»')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokenizer(max_length: usize) -> WordPieceTokenizer {
+        let raw = format!(
+            "{{\"model\":{{\"type\":\"WordPiece\",\"vocab\":[\"[PAD]\",\"[UNK]\",\"[CLS]\",\"[SEP]\",\"the\",\"bank\",\"##ing\",\"##s\",\"un\",\"##afford\",\"##able\",\"!\"]}},\"truncation\":{{\"max_length\":{max_length}}}}}"
+        );
+        WordPieceTokenizer::from_json(&raw).unwrap()
+    }
+
+    #[test]
+    fn splits_words_into_pieces_with_offsets() {
+        let text = "The Bankings unaffordable!";
+        let encoding = tokenizer(32).encode(text);
+        assert_eq!(
+            encoding.tokens,
+            vec!["[CLS]", "the", "bank", "##ing", "##s", "un", "##afford", "##able", "!", "[SEP]"]
+        );
+        assert_eq!(encoding.ids, vec![2, 4, 5, 6, 7, 8, 9, 10, 11, 3]);
+        let pieces = encoding
+            .content_indices()
+            .map(|i| &text[encoding.offsets[i].0..encoding.offsets[i].1])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pieces,
+            vec!["The", "Bank", "ing", "s", "un", "afford", "able", "!"]
+        );
+        assert!(!encoding.truncated);
+    }
+
+    #[test]
+    fn unknown_words_and_truncation() {
+        let encoding = tokenizer(4).encode("zebra the bank");
+        assert_eq!(encoding.tokens, vec!["[CLS]", "[UNK]", "the", "[SEP]"]);
+        assert_eq!(encoding.offsets[1], (0, 5));
+        assert!(encoding.truncated);
+
+        let err = WordPieceTokenizer::from_json("{\"model\":{\"vocab\":[\"a\"]}}").unwrap_err();
+        assert_eq!(err.msg, "tokenizer_special_token_missing");
+    }
+}
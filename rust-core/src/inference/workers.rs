@@ -1,48 +1,404 @@
-//! Lightweight worker pool for CPU bound inference tasks.
+//! Worker pool for CPU bound inference tasks.
 //!
-//! TODO: Add graceful shutdown so dropped pools stop accepting new work.
-//! TODO: Surface metrics on queue depth and worker utilisation.
+//! Jobs wait in a bounded queue; when it is full, `submit` either blocks or
+//! fails with `worker_queue_full`, depending on [`QueueFull`]. Every job runs
+//! under `catch_unwind`, so a panicking job fails its own [`JobHandle`]
+//! instead of taking a worker down. Release builds keep `panic = "unwind"`
+//! for this reason; with `abort` a single bad job would end the host process.
+//!
+//! `shutdown` stops accepting work, lets the workers drain the queue and
+//! waits for them up to a timeout; dropping the pool does the same.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicIsize, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::common::config::AppCfg;
+use crate::common::error::{DeltaCode, DeltaError, DeltaResult};
+use crate::common::log;
+
+/// A queued job; returns whether it ran without panicking.
+type Job = Box<dyn FnOnce() -> bool + Send + 'static>;
+
+/// How long dropping a pool waits for queued jobs to finish.
+const DROP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What `submit` does when the queue is full.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum QueueFull {
+    /// Wait until a worker frees a slot.
+    #[default]
+    Block,
+    /// Fail immediately with `worker_queue_full`.
+    Reject,
+}
+
+impl QueueFull {
+    /// Parse `DELTA1_QUEUE_FULL` case-insensitively. Any value other than
+    /// `block` or `reject` is logged as an error and rejects, so a typo never
+    /// leaves callers waiting on a queue the operator meant to bound.
+    pub fn parse(raw: &str) -> Self {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "block" => Self::Block,
+            "reject" => Self::Reject,
+            _ => {
+                log::log_json_ctx(
+                    "error",
+                    "inference",
+                    "queue_full_unknown",
+                    DeltaCode::InvalidInput as u32,
+                    0,
+                    &[("value", raw)],
+                );
+                Self::Reject
+            }
+        }
+    }
+}
+
+/// Size and queueing behaviour of a [`Pool`].
+#[derive(Clone, Debug)]
+pub struct PoolCfg {
+    pub threads: usize,
+    pub queue_capacity: usize,
+    pub when_full: QueueFull,
+}
+
+impl PoolCfg {
+    /// `DELTA1_THREADS` workers, `DELTA1_QUEUE_CAPACITY` queued jobs and
+    /// `DELTA1_QUEUE_FULL` (`block` or `reject`).
+    pub fn from_app(cfg: &AppCfg) -> Self {
+        Self {
+            threads: cfg.threads.max(1),
+            queue_capacity: cfg.queue_capacity.max(1),
+            when_full: QueueFull::parse(&cfg.queue_full),
+        }
+    }
+}
+
+/// Queue depth and utilisation of a [`Pool`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PoolMetrics {
+    pub threads: usize,
+    pub queue_depth: usize,
+    pub queue_capacity: usize,
+    /// Workers currently running a job.
+    pub busy: usize,
+    pub completed: u64,
+    pub panicked: u64,
+    pub rejected: u64,
+    /// Total time workers spent running jobs.
+    pub busy_ms: u64,
+}
+
+impl PoolMetrics {
+    /// Share of the workers currently running a job, `0.0..=1.0`.
+    pub fn utilisation(&self) -> f64 {
+        if self.threads == 0 {
+            return 0.0;
+        }
+        self.busy as f64 / self.threads as f64
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"threads\":{},\"queue_depth\":{},\"queue_capacity\":{},\"busy\":{},\"utilisation\":{:.4},\"completed\":{},\"panicked\":{},\"rejected\":{},\"busy_ms\":{}}}",
+            self.threads,
+            self.queue_depth,
+            self.queue_capacity,
+            self.busy,
+            self.utilisation(),
+            self.completed,
+            self.panicked,
+            self.rejected,
+            self.busy_ms
+        )
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    /// Counted once a job is in the queue, so a submitter blocked on a full
+    /// queue does not show up in it. A worker may take the job before the
+    /// submitter counts it, which makes the value briefly negative.
+    queued: AtomicIsize,
+    busy: AtomicUsize,
+    completed: AtomicU64,
+    panicked: AtomicU64,
+    rejected: AtomicU64,
+    busy_us: AtomicU64,
+}
 
-use std::sync::{mpsc, Arc, Mutex};
-use std::thread;
+/// Output of a submitted job.
+pub struct JobHandle<T> {
+    rx: Receiver<thread::Result<T>>,
+}
 
-type Job = Box<dyn FnOnce() + Send + 'static>;
+impl<T> JobHandle<T> {
+    /// Wait for the job; fails with `worker_job_panicked` when it panicked and
+    /// `worker_job_dropped` when it never ran.
+    pub fn join(self) -> DeltaResult<T> {
+        match self.rx.recv() {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(_)) => Err(DeltaError::internal("worker_job_panicked")),
+            Err(_) => Err(DeltaError::internal("worker_job_dropped")),
+        }
+    }
+}
 
 pub struct Pool {
-    tx: mpsc::Sender<Job>,
+    tx: Mutex<Option<SyncSender<Job>>>,
+    workers: Mutex<Vec<JoinHandle<()>>>,
+    counters: Arc<Counters>,
+    cfg: PoolCfg,
 }
 
 impl Pool {
-    pub fn new(size: usize) -> Self {
-        let (tx, rx) = mpsc::channel::<Job>();
+    pub fn new(cfg: PoolCfg) -> Self {
+        let (tx, rx) = mpsc::sync_channel::<Job>(cfg.queue_capacity);
         let shared_rx = Arc::new(Mutex::new(rx));
+        let counters = Arc::new(Counters::default());
 
-        for _ in 0..size {
-            let rx = shared_rx.clone();
-            thread::spawn(move || loop {
-                let job = {
-                    let guard = rx.lock().expect("worker mutex poisoned");
-                    guard.recv()
-                };
-
-                match job {
-                    Ok(job) => job(),
-                    Err(_) => break,
-                }
-            });
-        }
+        let workers = (0..cfg.threads)
+            .map(|_| {
+                let (rx, counters) = (shared_rx.clone(), counters.clone());
+                thread::spawn(move || loop {
+                    // A job never runs while the receiver is locked, so the
+                    // lock cannot be poisoned by a panicking job.
+                    let job = {
+                        let guard = rx.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                        guard.recv()
+                    };
+                    let Ok(job) = job else {
+                        break;
+                    };
+
+                    counters.queued.fetch_sub(1, Ordering::Relaxed);
+                    counters.busy.fetch_add(1, Ordering::Relaxed);
+                    let start = Instant::now();
+                    let completed = job();
+                    let elapsed = start.elapsed().as_micros() as u64;
+                    counters.busy_us.fetch_add(elapsed, Ordering::Relaxed);
+                    counters.busy.fetch_sub(1, Ordering::Relaxed);
+                    if completed {
+                        counters.completed.fetch_add(1, Ordering::Relaxed);
+                    } else {
+                        counters.panicked.fetch_add(1, Ordering::Relaxed);
+                    }
+                })
+            })
+            .collect();
 
-        // TODO: Track worker handles to allow explicit joins on shutdown.
-        Self { tx }
+        Self {
+            tx: Mutex::new(Some(tx)),
+            workers: Mutex::new(workers),
+            counters,
+            cfg,
+        }
     }
 
-    pub fn submit<F>(&self, job: F)
+    /// Queue `job`; its output is available through the returned handle.
+    ///
+    /// Fails with `worker_pool_closed` after `shutdown` and, under
+    /// [`QueueFull::Reject`], with `worker_queue_full` when the queue is full.
+    pub fn submit<F, T>(&self, job: F) -> DeltaResult<JobHandle<T>>
     where
-        F: FnOnce() + Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
     {
-        let _ = self.tx.send(Box::new(job));
-        // TODO: Propagate backpressure when the queue is saturated.
+        let tx = lock(&self.tx)
+            .clone()
+            .ok_or(DeltaError::policy_denied("worker_pool_closed"))?;
+        let (out_tx, out_rx) = mpsc::channel();
+        let job: Job = Box::new(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(job));
+            let completed = result.is_ok();
+            let _ = out_tx.send(result);
+            completed
+        });
+
+        let sent = match self.cfg.when_full {
+            QueueFull::Block => tx
+                .send(job)
+                .map_err(|_| DeltaError::policy_denied("worker_pool_closed")),
+            QueueFull::Reject => tx.try_send(job).map_err(|err| match err {
+                TrySendError::Full(_) => {
+                    self.counters.rejected.fetch_add(1, Ordering::Relaxed);
+                    DeltaError::policy_denied("worker_queue_full")
+                }
+                TrySendError::Disconnected(_) => DeltaError::policy_denied("worker_pool_closed"),
+            }),
+        };
+        sent?;
+        self.counters.queued.fetch_add(1, Ordering::Relaxed);
+        Ok(JobHandle { rx: out_rx })
+    }
+
+    /// Stop accepting jobs, let the workers finish the queue and wait for them.
+    ///
+    /// Fails with `worker_shutdown_timeout` when workers are still running
+    /// after `timeout`; they keep draining in the background.
+    pub fn shutdown(&self, timeout: Duration) -> DeltaResult<()> {
+        lock(&self.tx).take();
+        let deadline = Instant::now() + timeout;
+        let mut workers = lock(&self.workers);
+        loop {
+            let (finished, running): (Vec<_>, Vec<_>) =
+                workers.drain(..).partition(|worker| worker.is_finished());
+            for worker in finished {
+                let _ = worker.join();
+            }
+            *workers = running;
+            if workers.is_empty() {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(DeltaError::internal("worker_shutdown_timeout"));
+            }
+            thread::sleep(Duration::from_millis(2));
+        }
     }
+
+    pub fn metrics(&self) -> PoolMetrics {
+        let counters = &self.counters;
+        PoolMetrics {
+            threads: self.cfg.threads,
+            queue_depth: counters.queued.load(Ordering::Relaxed).max(0) as usize,
+            queue_capacity: self.cfg.queue_capacity,
+            busy: counters.busy.load(Ordering::Relaxed),
+            completed: counters.completed.load(Ordering::Relaxed),
+            panicked: counters.panicked.load(Ordering::Relaxed),
+            rejected: counters.rejected.load(Ordering::Relaxed),
+            busy_ms: counters.busy_us.load(Ordering::Relaxed) / 1_000,
+        }
+    }
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        let _ = self.shutdown(DROP_TIMEOUT);
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
 }
 
-// TODO: Implement Drop to close the channel and await worker completion.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg(threads: usize, queue_capacity: usize, when_full: QueueFull) -> PoolCfg {
+        PoolCfg {
+            threads,
+            queue_capacity,
+            when_full,
+        }
+    }
+
+    #[test]
+    fn jobs_return_results_and_panics_stay_isolated() {
+        let pool = Pool::new(cfg(2, 8, QueueFull::Block));
+        let handles = (0..6)
+            .map(|i| pool.submit(move || i * 10).unwrap())
+            .collect::<Vec<_>>();
+        let outputs = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(outputs, vec![0, 10, 20, 30, 40, 50]);
+
+        let failed = pool.submit(|| -> u32 { panic!("job failure") }).unwrap();
+        assert_eq!(failed.join().unwrap_err().msg, "worker_job_panicked");
+        // Both workers survive the panic.
+        let after = (0..4)
+            .map(|i| pool.submit(move || i).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(after.into_iter().map(|h| h.join().unwrap()).sum::<i32>(), 6);
+
+        pool.shutdown(Duration::from_secs(5)).unwrap();
+        let metrics = pool.metrics();
+        assert_eq!((metrics.completed, metrics.panicked), (10, 1));
+        assert_eq!((metrics.queue_depth, metrics.busy), (0, 0));
+        assert_eq!(
+            pool.submit(|| ()).err().map(|err| err.msg),
+            Some("worker_pool_closed")
+        );
+    }
+
+    #[test]
+    fn queue_full_parses_case_insensitively_and_unknown_values_reject() {
+        assert_eq!(QueueFull::parse("Block"), QueueFull::Block);
+        assert_eq!(QueueFull::parse("REJECT"), QueueFull::Reject);
+        assert_eq!(QueueFull::parse("blocking"), QueueFull::Reject);
+    }
+
+    #[test]
+    fn full_queues_reject_and_shutdown_drains_with_a_timeout() {
+        let pool = Pool::new(cfg(1, 1, QueueFull::Reject));
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let (started_tx, started_rx) = mpsc::channel::<()>();
+        let running = pool
+            .submit(move || {
+                started_tx.send(()).unwrap();
+                release_rx.recv().unwrap();
+                1
+            })
+            .unwrap();
+        started_rx.recv().unwrap();
+        let queued = pool.submit(|| 2).unwrap();
+        assert_eq!(pool.metrics().queue_depth, 1);
+        assert_eq!(pool.metrics().utilisation(), 1.0);
+        assert_eq!(
+            pool.submit(|| 3).err().map(|err| err.msg),
+            Some("worker_queue_full")
+        );
+        assert_eq!(pool.metrics().rejected, 1);
+
+        assert_eq!(
+            pool.shutdown(Duration::from_millis(20)).unwrap_err().msg,
+            "worker_shutdown_timeout"
+        );
+        release_tx.send(()).unwrap();
+        pool.shutdown(Duration::from_secs(5)).unwrap();
+        assert_eq!((running.join().unwrap(), queued.join().unwrap()), (1, 2));
+    }
+
+    #[test]
+    fn blocked_submitters_are_not_counted_as_queued() {
+        let pool = Arc::new(Pool::new(cfg(1, 1, QueueFull::Block)));
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let (started_tx, started_rx) = mpsc::channel::<()>();
+        let running = pool
+            .submit(move || {
+                started_tx.send(()).unwrap();
+                release_rx.recv().unwrap();
+                1
+            })
+            .unwrap();
+        started_rx.recv().unwrap();
+        let queued = pool.submit(|| 2).unwrap();
+        let waiting = {
+            let pool = Arc::clone(&pool);
+            thread::spawn(move || pool.submit(|| 3).unwrap().join().unwrap())
+        };
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(pool.metrics().queue_depth, 1);
+
+        release_tx.send(()).unwrap();
+        assert_eq!(
+            (
+                running.join().unwrap(),
+                queued.join().unwrap(),
+                waiting.join().unwrap()
+            ),
+            (1, 2, 3)
+        );
+        assert_eq!(pool.metrics().queue_depth, 0);
+    }
+}
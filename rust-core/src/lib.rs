@@ -11,9 +11,22 @@ pub mod inference;
 pub mod training;
 
 pub use data::service::{export_datasheet, ingest_file as core_data_ingest};
-pub use inference::service::{infer_with_ctx as core_infer_with_ctx, register_active_model};
+pub use inference::service::{
+    artefact_cache_stats, consent_lookup_stats, decide_review, grant_consent,
+    infer_batch as core_infer_batch, infer_with_ctx as core_infer_with_ctx,
+    infer_with_model as core_infer_with_model, load_pooled_model, pending_reviews,
+    purpose as core_purpose, query_consent, register_active_model, reload_purposes,
+    reload_router_rules, review as core_review, revoke_consent,
+    score_dataset as core_score_dataset, set_consent_lookup, set_purpose_model,
+    unload_pooled_model, worker_metrics,
+};
+pub use training::jobs::{
+    cancel as core_train_cancel, start as core_train_start, status as core_train_status,
+};
 pub use training::service::{
-    export_model_card, load_model as core_load_model, train as core_train,
+    export_model_card, load_model as core_load_model, promote_model as core_promote_model,
+    resign_model, train as core_train, validate_config as core_validate_config,
+    verify_reproducible as core_verify_reproducible,
 };
 
 // TODO: Re-export evaluation entry points when the reporting format settles.
@@ -0,0 +1,1080 @@
+//! Versioned binary model artefact format (`model.bin`).
+//!
+//! All integers and floats are little-endian; strings are UTF-8 prefixed with
+//! their byte length.
+//!
+//! ```text
+//! header   magic      6 bytes  "DELTA1"
+//!          version    u16      FORMAT_VERSION
+//!          kind       u8       0 = tabular-logreg, 1 = tabular-gbdt, 2 = text-minilm
+//!          reserved   3 bytes  zero
+//!          sections   u32      number of sections that follow
+//! section  tag        4 bytes  ASCII section tag
+//!          length     u32      payload length in bytes
+//!          payload    length bytes
+//! trailer  checksum   u32      FNV-1a (`SimpleHash`) over every preceding byte
+//! ```
+//!
+//! Sections (each tag at most once):
+//!
+//! * `FEAT` (required) – feature manifest: `u32` count, then per feature a
+//!   `u8` kind (0 numeric, 1 categorical, 2 text) and a `u16`-prefixed name.
+//! * `WGHT` – flat `u32` count followed by `f32` weights; linear models store
+//!   the intercept first, boosted trees only their base margin.
+//! * `TREE` – `u32` tree count, then per tree a `u32` node count followed by
+//!   nodes of `u32 feature, f32 threshold, u32 left, u32 right, f32 value`.
+//! * `META` (required) – `u32` count of `u16`-prefixed key and `u32`-prefixed
+//!   value string pairs.
+//! * `PIPE` (version 2+) – fitted feature pipeline, see
+//!   [`FeaturePipeline::encode`]. When present the `FEAT` manifest must match
+//!   the pipeline's input columns.
+//! * `CALB` (version 3+) – probability calibrator, see [`Calibrator::encode`].
+//! * `TNSR` (version 4+) – `u32` count of named weight tensors (f32, int8 or
+//!   Q4), see [`super::tensor`]; used by the text encoder.
+//!
+//! Readers accept every version up to [`FORMAT_VERSION`] and reject newer
+//! files, unknown or duplicate sections, trailing bytes and checksum mismatches.
+//!
+//! TODO: Compress large weight sections once text models ship.
+
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::common::error::{DeltaError, DeltaResult};
+use crate::common::ids::SimpleHash;
+use crate::common::json;
+use crate::data::domain::DatasetId;
+
+use super::calibration::Calibrator;
+use super::domain::{
+    CalibrationReport, CardInfo, DifferentialPrivacy, EvalMetrics, FairnessReport,
+    FeatureImportance, FeatureKind, FeatureSpec, Hyperparams, Lifecycle, Lineage, ModelId,
+    ModelKind, ModelMetadata, ModelVersion, ReliabilityBin, RiskLevel, SearchSummary, Tree,
+    TreeNode, VersionName,
+};
+use super::features::FeaturePipeline;
+use super::tensor::Tensor;
+
+/// File magic shared with the original header-only artefacts.
+pub const MAGIC: &[u8; 6] = b"DELTA1";
+/// Newest format version this build can read and the one it writes.
+pub const FORMAT_VERSION: u16 = 4;
+
+const HEADER_LEN: usize = 16;
+const CHECKSUM_LEN: usize = 4;
+
+const TAG_FEATURES: [u8; 4] = *b"FEAT";
+const TAG_WEIGHTS: [u8; 4] = *b"WGHT";
+const TAG_TREES: [u8; 4] = *b"TREE";
+const TAG_METADATA: [u8; 4] = *b"META";
+const TAG_PIPELINE: [u8; 4] = *b"PIPE";
+const TAG_CALIBRATION: [u8; 4] = *b"CALB";
+const TAG_TENSORS: [u8; 4] = *b"TNSR";
+
+/// Decoded contents of a model artefact.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelArtefact {
+    pub kind: ModelKind,
+    pub features: Vec<FeatureSpec>,
+    pub weights: Vec<f32>,
+    pub trees: Vec<Tree>,
+    pub metadata: Vec<(String, String)>,
+    pub pipeline: Option<FeaturePipeline>,
+    pub calibration: Option<Calibrator>,
+    pub tensors: Vec<Tensor>,
+}
+
+impl ModelArtefact {
+    /// Create an empty artefact for the given model kind.
+    pub fn new(kind: ModelKind) -> Self {
+        Self {
+            kind,
+            features: Vec::new(),
+            weights: Vec::new(),
+            trees: Vec::new(),
+            metadata: Vec::new(),
+            pipeline: None,
+            calibration: None,
+            tensors: Vec::new(),
+        }
+    }
+
+    /// Build an artefact whose metadata block mirrors the version's governance data.
+    pub fn for_version(model: &ModelVersion) -> Self {
+        let mut artefact = Self::new(model.kind);
+        artefact.metadata = version_metadata(model);
+        artefact
+    }
+
+    /// Rebuild the version record from the metadata block.
+    pub fn to_version(&self) -> DeltaResult<ModelVersion> {
+        let id = ModelId::new(self.required_meta("model_id")?);
+        let version = VersionName::new(self.required_meta("version")?);
+        let kind = ModelKind::from_label(self.required_meta("kind")?)
+            .filter(|kind| *kind == self.kind)
+            .ok_or_else(|| corrupt("artefact_metadata_invalid"))?;
+
+        let dp = DifferentialPrivacy {
+            enabled: self.parsed_meta("dp.enabled")?,
+            epsilon: self.parsed_meta("dp.epsilon")?,
+            delta: self.parsed_meta("dp.delta")?,
+            clip: self.parsed_meta("dp.clip")?,
+            noise_multiplier: self.parsed_meta("dp.noise_multiplier")?,
+        };
+        let fairness = match self.meta("fairness.delta_tpr") {
+            Some(_) => Some(FairnessReport {
+                delta_tpr: self.parsed_meta("fairness.delta_tpr")?,
+                delta_fpr: self.parsed_meta("fairness.delta_fpr")?,
+                delta_ppv: self.parsed_meta("fairness.delta_ppv")?,
+            }),
+            None => None,
+        };
+
+        // Artefacts written before hyperparameters were recorded fall back to the defaults.
+        let mut hyperparams = Hyperparams::default();
+        for name in Hyperparams::NAMES {
+            if self.meta(&format!("hp.{name}")).is_some() {
+                hyperparams.set(name, self.parsed_meta(&format!("hp.{name}"))?);
+            }
+        }
+        let search = match self.meta("search.strategy") {
+            Some(strategy) => Some(SearchSummary {
+                strategy: strategy.to_string(),
+                folds: self.parsed_meta("search.folds")?,
+                trials: self.parsed_meta("search.trials")?,
+                best_trial: self.parsed_meta("search.best_trial")?,
+                best_log_loss: self.parsed_meta("search.best_log_loss")?,
+                best_accuracy: self.parsed_meta("search.best_accuracy")?,
+            }),
+            None => None,
+        };
+        let calibration = match self.meta("calibration.method") {
+            Some(method) => Some(CalibrationReport {
+                method: method.to_string(),
+                holdout_rows: self.parsed_meta("calibration.holdout_rows")?,
+                ece: self.parsed_meta("calibration.ece")?,
+                bins: parse_bins(self.required_meta("calibration.bins")?)?,
+            }),
+            None => None,
+        };
+        let lineage = Lineage {
+            parent: match self.meta("lineage.parent") {
+                Some(parent) => {
+                    let (id, version) = parent
+                        .split_once('/')
+                        .ok_or_else(|| corrupt("artefact_metadata_invalid"))?;
+                    Some((ModelId::new(id), VersionName::new(version)))
+                }
+                None => None,
+            },
+            datasets: self
+                .meta("lineage.datasets")
+                .unwrap_or_default()
+                .split(',')
+                .filter(|id| !id.is_empty())
+                .map(DatasetId::new)
+                .collect(),
+            period: match self.meta("lineage.period_from_ms") {
+                Some(_) => Some((
+                    self.parsed_meta("lineage.period_from_ms")?,
+                    self.parsed_meta("lineage.period_to_ms")?,
+                )),
+                None => None,
+            },
+        };
+        let metrics = match self.meta("metrics.split") {
+            Some(split) => Some(EvalMetrics {
+                split: split.to_string(),
+                rows: self.parsed_meta("metrics.rows")?,
+                accuracy: self.parsed_meta("metrics.accuracy")?,
+                log_loss: self.parsed_meta("metrics.log_loss")?,
+                auc: self.parsed_meta("metrics.auc")?,
+            }),
+            None => None,
+        };
+        let importance = match self.meta("importance.method") {
+            Some(method) => Some(FeatureImportance {
+                method: method.to_string(),
+                features: self
+                    .metadata
+                    .iter()
+                    .filter_map(|(key, value)| {
+                        let name = key.strip_prefix(IMPORTANCE_PREFIX)?;
+                        Some(value.parse().map(|value| (name.to_string(), value)))
+                    })
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| corrupt("artefact_metadata_invalid"))?,
+            }),
+            None => None,
+        };
+        let card = CardInfo {
+            risk: match self.meta("card.risk") {
+                Some(label) => Some(
+                    RiskLevel::from_label(label)
+                        .ok_or_else(|| corrupt("artefact_metadata_invalid"))?,
+                ),
+                None => None,
+            },
+            limitations: match self.meta("card.limitations") {
+                Some(raw) => parse_strings(raw)?,
+                None => Vec::new(),
+            },
+            contact: self.meta("card.contact").map(str::to_string),
+        };
+
+        Ok(ModelVersion {
+            artefact_path: relative_path(&id, &version),
+            created_ms: self.parsed_meta("created_ms")?,
+            lifecycle: Lifecycle::default(),
+            id,
+            version,
+            kind,
+            metadata: ModelMetadata {
+                dp,
+                fairness,
+                hyperparams,
+                search,
+                calibration,
+                lineage,
+                metrics,
+                importance,
+                card,
+            },
+            checksum: None,
+        })
+    }
+
+    /// Look up a metadata value by key.
+    pub fn meta(&self, key: &str) -> Option<&str> {
+        self.metadata
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Look up a weight tensor by name.
+    pub fn tensor(&self, name: &str) -> Option<&Tensor> {
+        self.tensors.iter().find(|t| t.name == name)
+    }
+
+    /// Insert or replace a metadata entry.
+    pub fn set_meta(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        let key = key.into();
+        let value = value.into();
+        match self.metadata.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = value,
+            None => self.metadata.push((key, value)),
+        }
+    }
+
+    fn required_meta(&self, key: &str) -> DeltaResult<&str> {
+        self.meta(key)
+            .ok_or_else(|| corrupt("artefact_metadata_missing"))
+    }
+
+    fn parsed_meta<T: std::str::FromStr>(&self, key: &str) -> DeltaResult<T> {
+        self.required_meta(key)?
+            .parse()
+            .map_err(|_| corrupt("artefact_metadata_invalid"))
+    }
+
+    /// Serialise the artefact into the current format version.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut sections: Vec<([u8; 4], Vec<u8>)> = Vec::new();
+
+        let mut feat = Vec::new();
+        put_u32(&mut feat, self.features.len() as u32);
+        for spec in &self.features {
+            feat.push(feature_kind_code(spec.kind));
+            put_str16(&mut feat, &spec.name);
+        }
+        sections.push((TAG_FEATURES, feat));
+
+        if !self.weights.is_empty() {
+            let mut wght = Vec::with_capacity(4 + self.weights.len() * 4);
+            put_u32(&mut wght, self.weights.len() as u32);
+            for w in &self.weights {
+                put_f32(&mut wght, *w);
+            }
+            sections.push((TAG_WEIGHTS, wght));
+        }
+
+        if !self.trees.is_empty() {
+            let mut tree = Vec::new();
+            put_u32(&mut tree, self.trees.len() as u32);
+            for t in &self.trees {
+                put_u32(&mut tree, t.nodes.len() as u32);
+                for node in &t.nodes {
+                    put_u32(&mut tree, node.feature);
+                    put_f32(&mut tree, node.threshold);
+                    put_u32(&mut tree, node.left);
+                    put_u32(&mut tree, node.right);
+                    put_f32(&mut tree, node.value);
+                }
+            }
+            sections.push((TAG_TREES, tree));
+        }
+
+        let mut meta = Vec::new();
+        put_u32(&mut meta, self.metadata.len() as u32);
+        for (key, value) in &self.metadata {
+            put_str16(&mut meta, key);
+            put_u32(&mut meta, value.len() as u32);
+            meta.extend_from_slice(value.as_bytes());
+        }
+        sections.push((TAG_METADATA, meta));
+
+        if let Some(pipeline) = &self.pipeline {
+            let mut pipe = Vec::new();
+            pipeline.encode(&mut pipe);
+            sections.push((TAG_PIPELINE, pipe));
+        }
+
+        if let Some(calibrator) = &self.calibration {
+            let mut calb = Vec::new();
+            calibrator.encode(&mut calb);
+            sections.push((TAG_CALIBRATION, calb));
+        }
+
+        if !self.tensors.is_empty() {
+            let mut tnsr = Vec::new();
+            put_u32(&mut tnsr, self.tensors.len() as u32);
+            for tensor in &self.tensors {
+                tensor.encode(&mut tnsr);
+            }
+            sections.push((TAG_TENSORS, tnsr));
+        }
+
+        let mut out = Vec::with_capacity(
+            HEADER_LEN + sections.iter().map(|(_, p)| 8 + p.len()).sum::<usize>() + CHECKSUM_LEN,
+        );
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        out.push(model_kind_code(self.kind));
+        out.extend_from_slice(&[0, 0, 0]);
+        put_u32(&mut out, sections.len() as u32);
+        for (tag, payload) in &sections {
+            out.extend_from_slice(tag);
+            put_u32(&mut out, payload.len() as u32);
+            out.extend_from_slice(payload);
+        }
+        let checksum = checksum(&out);
+        put_u32(&mut out, checksum);
+        out
+    }
+
+    /// Parse and validate an artefact produced by [`ModelArtefact::encode`].
+    pub fn decode(bytes: &[u8]) -> DeltaResult<Self> {
+        if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(corrupt("artefact_magic_invalid"));
+        }
+        if bytes.len() < HEADER_LEN + CHECKSUM_LEN {
+            return Err(corrupt("artefact_truncated"));
+        }
+
+        let version = u16::from_le_bytes([bytes[6], bytes[7]]);
+        if version == 0 || version > FORMAT_VERSION {
+            return Err(corrupt("artefact_version_unsupported"));
+        }
+
+        let (body, trailer) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        let expected = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        if checksum(body) != expected {
+            return Err(corrupt("artefact_checksum_mismatch"));
+        }
+
+        let kind = model_kind_from_code(body[8]).ok_or_else(|| corrupt("artefact_kind_unknown"))?;
+        if body[9..12] != [0, 0, 0] {
+            return Err(corrupt("artefact_header_invalid"));
+        }
+
+        let mut reader = Reader::new(&body[12..]);
+        let section_count = reader.u32()?;
+        let mut artefact = Self::new(kind);
+        let mut seen: Vec<[u8; 4]> = Vec::new();
+
+        for _ in 0..section_count {
+            let tag = reader.tag()?;
+            if seen.contains(&tag) {
+                return Err(corrupt("artefact_section_duplicate"));
+            }
+            seen.push(tag);
+
+            let len = reader.u32()? as usize;
+            let mut section = Reader::new(reader.take(len)?);
+            match tag {
+                TAG_FEATURES => artefact.features = read_features(&mut section)?,
+                TAG_WEIGHTS => artefact.weights = read_weights(&mut section)?,
+                TAG_TREES => artefact.trees = read_trees(&mut section)?,
+                TAG_METADATA => artefact.metadata = read_metadata(&mut section)?,
+                TAG_PIPELINE if version >= 2 => {
+                    artefact.pipeline = Some(FeaturePipeline::decode(&mut section)?)
+                }
+                TAG_CALIBRATION if version >= 3 => {
+                    artefact.calibration = Some(Calibrator::decode(&mut section)?)
+                }
+                TAG_TENSORS if version >= 4 => artefact.tensors = read_tensors(&mut section)?,
+                _ => return Err(corrupt("artefact_section_unknown")),
+            }
+            section.finish()?;
+        }
+        reader.finish()?;
+
+        if !seen.contains(&TAG_FEATURES) || !seen.contains(&TAG_METADATA) {
+            return Err(corrupt("artefact_section_missing"));
+        }
+        if let Some(pipeline) = &artefact.pipeline {
+            if pipeline.manifest() != artefact.features {
+                return Err(corrupt("artefact_pipeline_mismatch"));
+            }
+        }
+
+        Ok(artefact)
+    }
+}
+
+/// Rebuild the version record from the header and `META` section only,
+/// seeking past every other payload. Used to list versions without reading
+/// their weights; the trailer checksum is reported but not verified, which
+/// [`ModelArtefact::decode`] still does when the artefact is loaded.
+pub fn read_version<R: Read + Seek>(source: &mut R) -> DeltaResult<ModelVersion> {
+    let mut header = [0u8; HEADER_LEN];
+    read_exact(source, &mut header)?;
+    if &header[..MAGIC.len()] != MAGIC {
+        return Err(corrupt("artefact_magic_invalid"));
+    }
+    let version = u16::from_le_bytes([header[6], header[7]]);
+    if version == 0 || version > FORMAT_VERSION {
+        return Err(corrupt("artefact_version_unsupported"));
+    }
+    let kind = model_kind_from_code(header[8]).ok_or_else(|| corrupt("artefact_kind_unknown"))?;
+    if header[9..12] != [0, 0, 0] {
+        return Err(corrupt("artefact_header_invalid"));
+    }
+
+    let end = source
+        .seek(SeekFrom::End(0))
+        .map_err(|_| DeltaError::io())?;
+    let body_end = end
+        .checked_sub(CHECKSUM_LEN as u64)
+        .filter(|body_end| *body_end >= HEADER_LEN as u64)
+        .ok_or_else(|| corrupt("artefact_truncated"))?;
+    let mut trailer = [0u8; CHECKSUM_LEN];
+    source
+        .seek(SeekFrom::Start(body_end))
+        .map_err(|_| DeltaError::io())?;
+    read_exact(source, &mut trailer)?;
+
+    let section_count = u32::from_le_bytes([header[12], header[13], header[14], header[15]]);
+    let mut pos = source
+        .seek(SeekFrom::Start(HEADER_LEN as u64))
+        .map_err(|_| DeltaError::io())?;
+    let mut artefact = ModelArtefact::new(kind);
+    let mut seen: Vec<[u8; 4]> = Vec::new();
+    for _ in 0..section_count {
+        let mut raw = [0u8; 8];
+        if pos + 8 > body_end {
+            return Err(corrupt("artefact_truncated"));
+        }
+        read_exact(source, &mut raw)?;
+        let tag = [raw[0], raw[1], raw[2], raw[3]];
+        let len = u32::from_le_bytes([raw[4], raw[5], raw[6], raw[7]]) as u64;
+        pos += 8;
+        if pos + len > body_end {
+            return Err(corrupt("artefact_truncated"));
+        }
+        if seen.contains(&tag) {
+            return Err(corrupt("artefact_section_duplicate"));
+        }
+        seen.push(tag);
+        match tag {
+            TAG_METADATA => {
+                let mut payload = vec![0u8; len as usize];
+                read_exact(source, &mut payload)?;
+                let mut section = Reader::new(&payload);
+                artefact.metadata = read_metadata(&mut section)?;
+                section.finish()?;
+            }
+            TAG_FEATURES | TAG_WEIGHTS | TAG_TREES => {}
+            TAG_PIPELINE if version >= 2 => {}
+            TAG_CALIBRATION if version >= 3 => {}
+            TAG_TENSORS if version >= 4 => {}
+            _ => return Err(corrupt("artefact_section_unknown")),
+        }
+        pos += len;
+        source
+            .seek(SeekFrom::Start(pos))
+            .map_err(|_| DeltaError::io())?;
+    }
+    if pos != body_end {
+        return Err(corrupt("artefact_trailing_bytes"));
+    }
+    if !seen.contains(&TAG_FEATURES) || !seen.contains(&TAG_METADATA) {
+        return Err(corrupt("artefact_section_missing"));
+    }
+
+    let mut model = artefact.to_version()?;
+    model.checksum = Some(format!("{:08x}", u32::from_le_bytes(trailer)));
+    Ok(model)
+}
+
+fn read_exact<R: Read>(source: &mut R, buf: &mut [u8]) -> DeltaResult<()> {
+    source.read_exact(buf).map_err(|err| match err.kind() {
+        std::io::ErrorKind::UnexpectedEof => corrupt("artefact_truncated"),
+        _ => DeltaError::io(),
+    })
+}
+
+/// Checksum stored in the artefact trailer.
+pub fn checksum(bytes: &[u8]) -> u32 {
+    let mut hasher = SimpleHash::new();
+    hasher.update(bytes);
+    hasher.finish32()
+}
+
+/// Artefact location relative to `DATA_ROOT`, as recorded on [`ModelVersion`].
+pub fn relative_path(id: &ModelId, version: &VersionName) -> String {
+    format!("models/{}/{}/model.bin", id.as_str(), version.as_str())
+}
+
+/// Hex rendering of the trailer checksum of an encoded artefact.
+pub fn checksum_hex(encoded: &[u8]) -> Option<String> {
+    let tail = encoded.len().checked_sub(CHECKSUM_LEN)?;
+    let raw = &encoded[tail..];
+    Some(format!(
+        "{:08x}",
+        u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]])
+    ))
+}
+
+fn version_metadata(model: &ModelVersion) -> Vec<(String, String)> {
+    let dp = &model.metadata.dp;
+    let mut meta = Vec::new();
+    let mut push = |key: &str, value: String| meta.push((key.to_string(), value));
+
+    push("model_id", model.id.as_str().to_string());
+    push("version", model.version.as_str().to_string());
+    push("kind", model.kind.as_str().to_string());
+    push("created_ms", model.created_ms.to_string());
+    push("dp.enabled", dp.enabled.to_string());
+    push("dp.epsilon", dp.epsilon.to_string());
+    push("dp.delta", dp.delta.to_string());
+    push("dp.clip", dp.clip.to_string());
+    push("dp.noise_multiplier", dp.noise_multiplier.to_string());
+    if let Some(fairness) = &model.metadata.fairness {
+        push("fairness.delta_tpr", fairness.delta_tpr.to_string());
+        push("fairness.delta_fpr", fairness.delta_fpr.to_string());
+        push("fairness.delta_ppv", fairness.delta_ppv.to_string());
+    }
+    for name in Hyperparams::NAMES {
+        let value = model.metadata.hyperparams.get(name).unwrap_or_default();
+        push(&format!("hp.{name}"), value.to_string());
+    }
+    if let Some(search) = &model.metadata.search {
+        push("search.strategy", search.strategy.clone());
+        push("search.folds", search.folds.to_string());
+        push("search.trials", search.trials.to_string());
+        push("search.best_trial", search.best_trial.to_string());
+        push("search.best_log_loss", search.best_log_loss.to_string());
+        push("search.best_accuracy", search.best_accuracy.to_string());
+    }
+    if let Some(calibration) = &model.metadata.calibration {
+        push("calibration.method", calibration.method.clone());
+        push(
+            "calibration.holdout_rows",
+            calibration.holdout_rows.to_string(),
+        );
+        push("calibration.ece", calibration.ece.to_string());
+        push("calibration.bins", format_bins(&calibration.bins));
+    }
+    let lineage = &model.metadata.lineage;
+    if let Some((id, version)) = &lineage.parent {
+        push(
+            "lineage.parent",
+            format!("{}/{}", id.as_str(), version.as_str()),
+        );
+    }
+    if !lineage.datasets.is_empty() {
+        let datasets = lineage.datasets.iter().map(DatasetId::as_str);
+        push("lineage.datasets", datasets.collect::<Vec<_>>().join(","));
+    }
+    if let Some((from, to)) = lineage.period {
+        push("lineage.period_from_ms", from.to_string());
+        push("lineage.period_to_ms", to.to_string());
+    }
+    if let Some(metrics) = &model.metadata.metrics {
+        push("metrics.split", metrics.split.clone());
+        push("metrics.rows", metrics.rows.to_string());
+        push("metrics.accuracy", metrics.accuracy.to_string());
+        push("metrics.log_loss", metrics.log_loss.to_string());
+        push("metrics.auc", metrics.auc.to_string());
+    }
+    if let Some(importance) = &model.metadata.importance {
+        push("importance.method", importance.method.clone());
+        for (name, value) in &importance.features {
+            push(&format!("{IMPORTANCE_PREFIX}{name}"), value.to_string());
+        }
+    }
+    let card = &model.metadata.card;
+    if let Some(risk) = card.risk {
+        push("card.risk", risk.as_str().to_string());
+    }
+    if !card.limitations.is_empty() {
+        push(
+            "card.limitations",
+            json::build_string_array(&card.limitations),
+        );
+    }
+    if let Some(contact) = &card.contact {
+        push("card.contact", contact.clone());
+    }
+    meta
+}
+
+/// Metadata keys `importance.feature.<name>` hold one importance each, in order.
+const IMPORTANCE_PREFIX: &str = "importance.feature.";
+
+fn parse_strings(raw: &str) -> DeltaResult<Vec<String>> {
+    let invalid = || corrupt("artefact_metadata_invalid");
+    json::parse(raw)
+        .map_err(|_| invalid())?
+        .as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|item| item.as_str().map(str::to_string).ok_or_else(invalid))
+        .collect()
+}
+
+/// Reliability bins as `lower:upper:count:mean_predicted:observed_rate` joined by `;`.
+fn format_bins(bins: &[ReliabilityBin]) -> String {
+    bins.iter()
+        .map(|bin| {
+            format!(
+                "{}:{}:{}:{}:{}",
+                bin.lower, bin.upper, bin.count, bin.mean_predicted, bin.observed_rate
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+fn parse_bins(raw: &str) -> DeltaResult<Vec<ReliabilityBin>> {
+    let invalid = || corrupt("artefact_metadata_invalid");
+    raw.split(';')
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let fields = entry.split(':').collect::<Vec<_>>();
+            if fields.len() != 5 {
+                return Err(invalid());
+            }
+            Ok(ReliabilityBin {
+                lower: fields[0].parse().map_err(|_| invalid())?,
+                upper: fields[1].parse().map_err(|_| invalid())?,
+                count: fields[2].parse().map_err(|_| invalid())?,
+                mean_predicted: fields[3].parse().map_err(|_| invalid())?,
+                observed_rate: fields[4].parse().map_err(|_| invalid())?,
+            })
+        })
+        .collect()
+}
+
+/// A stored artefact that exists but cannot be decoded. Reported as
+/// `Internal`, so callers can tell it apart from an absent model.
+pub(crate) fn corrupt(msg: &'static str) -> DeltaError {
+    DeltaError::internal(msg)
+}
+
+fn model_kind_code(kind: ModelKind) -> u8 {
+    match kind {
+        ModelKind::TabularLogistic => 0,
+        ModelKind::TabularGradientBoosting => 1,
+        ModelKind::TextMiniLm => 2,
+    }
+}
+
+fn model_kind_from_code(code: u8) -> Option<ModelKind> {
+    match code {
+        0 => Some(ModelKind::TabularLogistic),
+        1 => Some(ModelKind::TabularGradientBoosting),
+        2 => Some(ModelKind::TextMiniLm),
+        _ => None,
+    }
+}
+
+fn feature_kind_code(kind: FeatureKind) -> u8 {
+    match kind {
+        FeatureKind::Numeric => 0,
+        FeatureKind::Categorical => 1,
+        FeatureKind::Text => 2,
+    }
+}
+
+fn feature_kind_from_code(code: u8) -> Option<FeatureKind> {
+    match code {
+        0 => Some(FeatureKind::Numeric),
+        1 => Some(FeatureKind::Categorical),
+        2 => Some(FeatureKind::Text),
+        _ => None,
+    }
+}
+
+pub(crate) fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+pub(crate) fn put_f32(out: &mut Vec<u8>, value: f32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+pub(crate) fn put_str16(out: &mut Vec<u8>, value: &str) {
+    // Keys and feature names are short identifiers; longer values are truncated at a char boundary.
+    let mut end = value.len().min(u16::MAX as usize);
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    out.extend_from_slice(&(end as u16).to_le_bytes());
+    out.extend_from_slice(&value.as_bytes()[..end]);
+}
+
+fn read_features(section: &mut Reader<'_>) -> DeltaResult<Vec<FeatureSpec>> {
+    let count = section.u32()? as usize;
+    let mut features = Vec::with_capacity(count.min(section.remaining()));
+    for _ in 0..count {
+        let kind = feature_kind_from_code(section.u8()?)
+            .ok_or_else(|| corrupt("artefact_feature_kind_unknown"))?;
+        let name = section.str16()?;
+        features.push(FeatureSpec { name, kind });
+    }
+    Ok(features)
+}
+
+fn read_weights(section: &mut Reader<'_>) -> DeltaResult<Vec<f32>> {
+    let count = section.u32()? as usize;
+    let mut weights = Vec::with_capacity(count.min(section.remaining() / 4));
+    for _ in 0..count {
+        weights.push(section.f32()?);
+    }
+    Ok(weights)
+}
+
+fn read_trees(section: &mut Reader<'_>) -> DeltaResult<Vec<Tree>> {
+    let count = section.u32()? as usize;
+    let mut trees = Vec::with_capacity(count.min(section.remaining() / 4));
+    for _ in 0..count {
+        let node_count = section.u32()? as usize;
+        if node_count == 0 {
+            return Err(corrupt("artefact_tree_empty"));
+        }
+        let mut nodes = Vec::with_capacity(node_count.min(section.remaining() / 20));
+        for _ in 0..node_count {
+            let node = TreeNode {
+                feature: section.u32()?,
+                threshold: section.f32()?,
+                left: section.u32()?,
+                right: section.u32()?,
+                value: section.f32()?,
+            };
+            if !node.is_leaf()
+                && (node.left as usize >= node_count || node.right as usize >= node_count)
+            {
+                return Err(corrupt("artefact_tree_invalid"));
+            }
+            nodes.push(node);
+        }
+        trees.push(Tree { nodes });
+    }
+    Ok(trees)
+}
+
+fn read_tensors(section: &mut Reader<'_>) -> DeltaResult<Vec<Tensor>> {
+    let count = section.u32()? as usize;
+    let mut tensors: Vec<Tensor> = Vec::with_capacity(count.min(section.remaining()));
+    for _ in 0..count {
+        let tensor = Tensor::decode(section)?;
+        if tensors.iter().any(|t| t.name == tensor.name) {
+            return Err(corrupt("artefact_tensor_duplicate"));
+        }
+        tensors.push(tensor);
+    }
+    Ok(tensors)
+}
+
+fn read_metadata(section: &mut Reader<'_>) -> DeltaResult<Vec<(String, String)>> {
+    let count = section.u32()? as usize;
+    let mut metadata = Vec::with_capacity(count.min(section.remaining()));
+    for _ in 0..count {
+        let key = section.str16()?;
+        let len = section.u32()? as usize;
+        let value = section.utf8(len)?;
+        metadata.push((key, value));
+    }
+    Ok(metadata)
+}
+
+/// Bounds-checked little-endian cursor over a byte slice.
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> DeltaResult<&'a [u8]> {
+        if len > self.remaining() {
+            return Err(corrupt("artefact_truncated"));
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    pub(crate) fn u8(&mut self) -> DeltaResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u32(&mut self) -> DeltaResult<u32> {
+        let raw = self.take(4)?;
+        Ok(u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]))
+    }
+
+    pub(crate) fn f32(&mut self) -> DeltaResult<f32> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+
+    pub(crate) fn tag(&mut self) -> DeltaResult<[u8; 4]> {
+        let raw = self.take(4)?;
+        Ok([raw[0], raw[1], raw[2], raw[3]])
+    }
+
+    pub(crate) fn str16(&mut self) -> DeltaResult<String> {
+        let raw = self.take(2)?;
+        let len = u16::from_le_bytes([raw[0], raw[1]]) as usize;
+        self.utf8(len)
+    }
+
+    pub(crate) fn utf8(&mut self, len: usize) -> DeltaResult<String> {
+        let raw = self.take(len)?;
+        String::from_utf8(raw.to_vec()).map_err(|_| corrupt("artefact_utf8_invalid"))
+    }
+
+    pub(crate) fn finish(&self) -> DeltaResult<()> {
+        if self.remaining() == 0 {
+            Ok(())
+        } else {
+            Err(corrupt("artefact_trailing_bytes"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::error::DeltaCode;
+
+    fn sample() -> ModelArtefact {
+        let mut artefact = ModelArtefact::new(ModelKind::TabularGradientBoosting);
+        artefact.features = vec![
+            FeatureSpec::new("amount", FeatureKind::Numeric),
+            FeatureSpec::new("country", FeatureKind::Categorical),
+        ];
+        artefact.weights = vec![0.25, -1.5];
+        artefact.trees = vec![Tree {
+            nodes: vec![
+                TreeNode {
+                    feature: 0,
+                    threshold: 10.0,
+                    left: 1,
+                    right: 2,
+                    value: 0.0,
+                },
+                TreeNode::leaf(-0.5),
+                TreeNode::leaf(0.75),
+            ],
+        }];
+        artefact.set_meta("model_id", "tabular-gbdt-test");
+        artefact
+    }
+
+    #[test]
+    fn roundtrip_preserves_contents() {
+        let artefact = sample();
+        let decoded = ModelArtefact::decode(&artefact.encode()).unwrap();
+        assert_eq!(decoded, artefact);
+        assert_eq!(decoded.meta("model_id"), Some("tabular-gbdt-test"));
+    }
+
+    #[test]
+    fn version_roundtrips_through_metadata() {
+        let model = ModelVersion {
+            id: ModelId::new("tabular-logreg-abc"),
+            version: VersionName::new("v42"),
+            kind: ModelKind::TabularLogistic,
+            artefact_path: relative_path(
+                &ModelId::new("tabular-logreg-abc"),
+                &VersionName::new("v42"),
+            ),
+            created_ms: 42,
+            lifecycle: Lifecycle::default(),
+            metadata: ModelMetadata {
+                dp: DifferentialPrivacy {
+                    enabled: true,
+                    epsilon: 3.0,
+                    delta: 1e-5,
+                    clip: 1.0,
+                    noise_multiplier: 1.1,
+                },
+                fairness: Some(FairnessReport {
+                    delta_tpr: 0.01,
+                    delta_fpr: 0.02,
+                    delta_ppv: 0.03,
+                }),
+                lineage: Lineage {
+                    parent: Some((ModelId::new("tabular-logreg-abc"), VersionName::new("v41"))),
+                    datasets: vec![DatasetId::new("ds-a"), DatasetId::new("ds-b")],
+                    period: Some((1_700_000_000_000, 1_700_000_500_000)),
+                },
+                metrics: Some(EvalMetrics {
+                    split: "train".to_string(),
+                    rows: 20,
+                    accuracy: 0.9,
+                    log_loss: 0.25,
+                    auc: 0.95,
+                }),
+                importance: Some(FeatureImportance {
+                    method: "coefficient".to_string(),
+                    features: vec![("amount".to_string(), 1.5), ("city=a,b".to_string(), -0.25)],
+                }),
+                card: CardInfo {
+                    risk: Some(RiskLevel::Limited),
+                    limitations: vec!["sensitive to missing \"income\"".to_string()],
+                    contact: Some("ml-oversight@example.org".to_string()),
+                },
+                ..ModelMetadata::default()
+            },
+            checksum: None,
+        };
+        let bytes = ModelArtefact::for_version(&model).encode();
+        let restored = ModelArtefact::decode(&bytes).unwrap().to_version().unwrap();
+        assert_eq!(restored.id, model.id);
+        assert_eq!(restored.created_ms, 42);
+        assert_eq!(restored.artefact_path, model.artefact_path);
+        assert_eq!(restored.metadata.dp.noise_multiplier, 1.1);
+        assert_eq!(restored.metadata.fairness.unwrap().delta_ppv, 0.03);
+        assert!(restored.metadata.calibration.is_none());
+        assert_eq!(restored.metadata.lineage, model.metadata.lineage);
+        assert_eq!(restored.metadata.metrics, model.metadata.metrics);
+        assert_eq!(restored.metadata.importance, model.metadata.importance);
+        assert_eq!(restored.metadata.card, model.metadata.card);
+    }
+
+    #[test]
+    fn calibrator_and_report_roundtrip() {
+        let id = ModelId::new("tabular-logreg-cal");
+        let version = VersionName::new("v1");
+        let mut model = ModelVersion {
+            artefact_path: relative_path(&id, &version),
+            id,
+            version,
+            kind: ModelKind::TabularLogistic,
+            created_ms: 7,
+            lifecycle: Lifecycle::default(),
+            metadata: ModelMetadata::default(),
+            checksum: None,
+        };
+        model.metadata.calibration = Some(CalibrationReport {
+            method: "platt".to_string(),
+            holdout_rows: 20,
+            ece: 0.0625,
+            bins: vec![ReliabilityBin {
+                lower: 0.1,
+                upper: 0.2,
+                count: 3,
+                mean_predicted: 0.15,
+                observed_rate: 1.0 / 3.0,
+            }],
+        });
+        let mut artefact = ModelArtefact::for_version(&model);
+        artefact.calibration = Some(Calibrator::Platt { a: 0.5, b: -0.25 });
+
+        let decoded = ModelArtefact::decode(&artefact.encode()).unwrap();
+        assert_eq!(decoded.calibration, artefact.calibration);
+        let restored = decoded.to_version().unwrap();
+        assert_eq!(restored.metadata.calibration, model.metadata.calibration);
+    }
+
+    #[test]
+    fn pipeline_must_match_feature_manifest() {
+        use crate::common::json;
+        use crate::training::features::{ColumnSpec, FeaturePipeline, Impute, Transform};
+
+        let specs = [ColumnSpec {
+            name: "amount".to_string(),
+            transform: Transform::Numeric { standardize: true },
+            impute: Impute::Mean,
+        }];
+        let records = [json::parse("{\"amount\":4}").unwrap()];
+        let mut artefact = sample();
+        artefact.pipeline = Some(FeaturePipeline::fit(&specs, &records).unwrap());
+        let err = ModelArtefact::decode(&artefact.encode()).unwrap_err();
+        assert_eq!(err.msg, "artefact_pipeline_mismatch");
+
+        artefact.features = artefact.pipeline.as_ref().unwrap().manifest();
+        let decoded = ModelArtefact::decode(&artefact.encode()).unwrap();
+        assert_eq!(decoded, artefact);
+    }
+
+    #[test]
+    fn version_is_read_from_the_metadata_section_alone() {
+        let mut artefact = sample();
+        let version = ModelVersion {
+            id: ModelId::new("churn"),
+            version: VersionName::new("v1"),
+            kind: artefact.kind,
+            created_ms: 42,
+            lifecycle: Lifecycle::default(),
+            artefact_path: relative_path(&ModelId::new("churn"), &VersionName::new("v1")),
+            metadata: ModelMetadata::default(),
+            checksum: None,
+        };
+        artefact.metadata = version_metadata(&version);
+        let bytes = artefact.encode();
+
+        let read = read_version(&mut std::io::Cursor::new(&bytes)).unwrap();
+        assert_eq!(read.id, version.id);
+        assert_eq!(read.created_ms, 42);
+        assert_eq!(read.checksum, checksum_hex(&bytes));
+
+        let err = read_version(&mut std::io::Cursor::new(&bytes[..bytes.len() - 9])).unwrap_err();
+        assert_eq!(err.msg, "artefact_truncated");
+    }
+
+    #[test]
+    fn rejects_flipped_byte() {
+        let mut bytes = sample().encode();
+        let mid = bytes.len() / 2;
+        bytes[mid] ^= 0xff;
+        let err = ModelArtefact::decode(&bytes).unwrap_err();
+        assert_eq!(err.msg, "artefact_checksum_mismatch");
+        assert_eq!(err.code, DeltaCode::Internal);
+    }
+
+    #[test]
+    fn rejects_future_version() {
+        let mut bytes = sample().encode();
+        bytes[6..8].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        let err = ModelArtefact::decode(&bytes).unwrap_err();
+        assert_eq!(err.msg, "artefact_version_unsupported");
+    }
+
+    #[test]
+    fn rejects_legacy_header_only_files() {
+        let err = ModelArtefact::decode(b"DELTA1v123").unwrap_err();
+        assert_eq!(err.msg, "artefact_truncated");
+    }
+}
@@ -0,0 +1,358 @@
+//! Probability calibration fitted on a held-out split.
+//!
+//! Declared in `TrainConfig` as
+//! `"calibration": {"method": "platt" | "isotonic", "holdout": 0.2, "seed": 42}`.
+//! The model is fitted on the remaining records, its raw probabilities on the
+//! holdout rows train the calibrator, and the calibrated holdout predictions
+//! produce the reliability bins and expected calibration error (ECE) that the
+//! evaluation card reports. The calibrator is stored in the artefact `CALB`
+//! section and applied by the inference engines.
+
+use crate::common::error::{DeltaError, DeltaResult};
+use crate::common::json::Value;
+
+use super::artefact::{corrupt, put_f32, put_u32, Reader};
+use super::domain::{CalibrationReport, ReliabilityBin};
+
+const DEFAULT_HOLDOUT: f64 = 0.2;
+const DEFAULT_SEED: u64 = 42;
+const RELIABILITY_BINS: usize = 10;
+const EPS: f64 = 1e-7;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CalibrationMethod {
+    Platt,
+    Isotonic,
+}
+
+impl CalibrationMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CalibrationMethod::Platt => "platt",
+            CalibrationMethod::Isotonic => "isotonic",
+        }
+    }
+}
+
+/// Calibration declaration parsed from the `"calibration"` section of `TrainConfig`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CalibrationSpec {
+    pub method: CalibrationMethod,
+    /// Share of records held out for fitting the calibrator.
+    pub holdout: f64,
+    pub seed: u64,
+}
+
+impl CalibrationSpec {
+    /// Parse the section; `"method": "none"` disables calibration.
+    pub fn from_json(section: &Value) -> DeltaResult<Option<Self>> {
+        let invalid = || DeltaError::invalid("train_cfg_calibration_invalid");
+        let method = match section.get("method").and_then(Value::as_str) {
+            Some("platt") => CalibrationMethod::Platt,
+            Some("isotonic") => CalibrationMethod::Isotonic,
+            Some("none") => return Ok(None),
+            _ => return Err(invalid()),
+        };
+        let holdout = match section.get("holdout") {
+            None => DEFAULT_HOLDOUT,
+            Some(value) => value
+                .as_f64()
+                .filter(|h| *h >= 0.05 && *h <= 0.5)
+                .ok_or_else(invalid)?,
+        };
+        let seed = match section.get("seed") {
+            None => DEFAULT_SEED,
+            Some(value) => value
+                .as_f64()
+                .filter(|n| n.fract() == 0.0 && *n >= 0.0 && *n <= u32::MAX as f64)
+                .ok_or_else(invalid)? as u64,
+        };
+        Ok(Some(Self {
+            method,
+            holdout,
+            seed,
+        }))
+    }
+
+    /// Deterministically split record indices into `(train, holdout)`.
+    pub fn split(&self, n: usize) -> DeltaResult<(Vec<usize>, Vec<usize>)> {
+        let held = ((n as f64) * self.holdout).ceil() as usize;
+        if held < 2 || n - held < 2 {
+            return Err(DeltaError::invalid("calibration_holdout_too_small"));
+        }
+        // With one fold per record the assignment is the seeded shuffle position.
+        let order = super::search::fold_assignment(n, n, self.seed);
+        let mut holdout = (0..n).filter(|i| order[*i] < held).collect::<Vec<_>>();
+        let train = (0..n).filter(|i| order[*i] >= held).collect::<Vec<_>>();
+        holdout.sort_unstable();
+        Ok((train, holdout))
+    }
+}
+
+/// Fitted mapping from raw model probabilities to calibrated probabilities.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Calibrator {
+    /// `sigmoid(a * logit(p) + b)`.
+    Platt { a: f32, b: f32 },
+    /// Monotone piecewise-linear map through `(raw, calibrated)` knots sorted by `raw`.
+    Isotonic { knots: Vec<(f32, f32)> },
+}
+
+impl Calibrator {
+    pub fn fit(method: CalibrationMethod, raw: &[f32], y: &[f32]) -> DeltaResult<Self> {
+        if raw.len() != y.len() || raw.len() < 2 {
+            return Err(DeltaError::invalid("calibration_holdout_too_small"));
+        }
+        Ok(match method {
+            CalibrationMethod::Platt => fit_platt(raw, y),
+            CalibrationMethod::Isotonic => fit_isotonic(raw, y),
+        })
+    }
+
+    pub fn method(&self) -> CalibrationMethod {
+        match self {
+            Calibrator::Platt { .. } => CalibrationMethod::Platt,
+            Calibrator::Isotonic { .. } => CalibrationMethod::Isotonic,
+        }
+    }
+
+    pub fn apply(&self, p: f32) -> f32 {
+        match self {
+            Calibrator::Platt { a, b } => sigmoid(a * logit(p) + b),
+            Calibrator::Isotonic { knots } => interpolate(knots, p),
+        }
+    }
+
+    /// Serialise into the payload of the artefact `CALB` section.
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Calibrator::Platt { a, b } => {
+                out.push(0);
+                put_f32(out, *a);
+                put_f32(out, *b);
+            }
+            Calibrator::Isotonic { knots } => {
+                out.push(1);
+                put_u32(out, knots.len() as u32);
+                for (raw, calibrated) in knots {
+                    put_f32(out, *raw);
+                    put_f32(out, *calibrated);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn decode(section: &mut Reader<'_>) -> DeltaResult<Self> {
+        match section.u8()? {
+            0 => Ok(Calibrator::Platt {
+                a: section.f32()?,
+                b: section.f32()?,
+            }),
+            1 => {
+                let count = section.u32()? as usize;
+                let mut knots = Vec::with_capacity(count.min(section.remaining() / 8));
+                for _ in 0..count {
+                    knots.push((section.f32()?, section.f32()?));
+                }
+                let sorted = knots.windows(2).all(|w| w[0].0 <= w[1].0);
+                if knots.is_empty() || !sorted {
+                    return Err(corrupt("artefact_calibration_invalid"));
+                }
+                Ok(Calibrator::Isotonic { knots })
+            }
+            _ => Err(corrupt("artefact_calibration_invalid")),
+        }
+    }
+}
+
+/// Platt scaling with the smoothed targets from the original paper, fitted by Newton's method.
+fn fit_platt(raw: &[f32], y: &[f32]) -> Calibrator {
+    let positives = y.iter().filter(|t| **t >= 0.5).count() as f64;
+    let negatives = y.len() as f64 - positives;
+    let hi = (positives + 1.0) / (positives + 2.0);
+    let lo = 1.0 / (negatives + 2.0);
+    let samples = raw
+        .iter()
+        .zip(y)
+        .map(|(p, t)| (logit(*p) as f64, if *t >= 0.5 { hi } else { lo }))
+        .collect::<Vec<_>>();
+
+    let (mut a, mut b) = (1.0f64, 0.0f64);
+    for _ in 0..100 {
+        let (mut ga, mut gb, mut haa, mut hab, mut hbb) = (0.0, 0.0, 1e-9, 0.0, 1e-9);
+        for (s, t) in &samples {
+            let p = 1.0 / (1.0 + (-(a * s + b)).exp());
+            let w = (p * (1.0 - p)).max(EPS);
+            ga += (p - t) * s;
+            gb += p - t;
+            haa += w * s * s;
+            hab += w * s;
+            hbb += w;
+        }
+        let det = haa * hbb - hab * hab;
+        if det.abs() < 1e-12 {
+            break;
+        }
+        let da = (hbb * ga - hab * gb) / det;
+        let db = (haa * gb - hab * ga) / det;
+        a -= da;
+        b -= db;
+        if da.abs() < 1e-9 && db.abs() < 1e-9 {
+            break;
+        }
+    }
+    Calibrator::Platt {
+        a: a as f32,
+        b: b as f32,
+    }
+}
+
+/// Pool-adjacent-violators; each pooled block becomes a knot at its mean raw score.
+fn fit_isotonic(raw: &[f32], y: &[f32]) -> Calibrator {
+    let mut pairs = raw
+        .iter()
+        .copied()
+        .zip(y.iter().copied())
+        .collect::<Vec<_>>();
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // (sum raw, sum target, count) per block.
+    let mut blocks: Vec<(f64, f64, f64)> = Vec::new();
+    for (p, t) in pairs {
+        blocks.push((p as f64, t as f64, 1.0));
+        while blocks.len() > 1 {
+            let last = blocks[blocks.len() - 1];
+            let prev = blocks[blocks.len() - 2];
+            if prev.1 / prev.2 <= last.1 / last.2 {
+                break;
+            }
+            blocks.pop();
+            let merged = blocks.last_mut().expect("at least one block");
+            merged.0 += last.0;
+            merged.1 += last.1;
+            merged.2 += last.2;
+        }
+    }
+    Calibrator::Isotonic {
+        knots: blocks
+            .into_iter()
+            .map(|(raw, target, n)| ((raw / n) as f32, (target / n) as f32))
+            .collect(),
+    }
+}
+
+fn interpolate(knots: &[(f32, f32)], p: f32) -> f32 {
+    let first = match knots.first() {
+        Some(first) => *first,
+        None => return p,
+    };
+    if p <= first.0 {
+        return first.1;
+    }
+    for pair in knots.windows(2) {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        if p <= x1 {
+            if x1 - x0 <= f32::EPSILON {
+                return y1;
+            }
+            return y0 + (y1 - y0) * (p - x0) / (x1 - x0);
+        }
+    }
+    knots[knots.len() - 1].1
+}
+
+/// Equal-width reliability bins over `[0, 1]` and the resulting expected calibration error.
+pub fn reliability(p: &[f32], y: &[f32]) -> (Vec<ReliabilityBin>, f64) {
+    let mut bins = (0..RELIABILITY_BINS)
+        .map(|i| ReliabilityBin {
+            lower: i as f32 / RELIABILITY_BINS as f32,
+            upper: (i + 1) as f32 / RELIABILITY_BINS as f32,
+            count: 0,
+            mean_predicted: 0.0,
+            observed_rate: 0.0,
+        })
+        .collect::<Vec<_>>();
+    for (prob, target) in p.iter().zip(y) {
+        let index = ((prob * RELIABILITY_BINS as f32) as usize).min(RELIABILITY_BINS - 1);
+        let bin = &mut bins[index];
+        bin.count += 1;
+        bin.mean_predicted += *prob as f64;
+        bin.observed_rate += *target as f64;
+    }
+
+    let total = p.len().max(1) as f64;
+    let mut ece = 0.0;
+    for bin in &mut bins {
+        if bin.count > 0 {
+            bin.mean_predicted /= bin.count as f64;
+            bin.observed_rate /= bin.count as f64;
+            ece += bin.count as f64 / total * (bin.mean_predicted - bin.observed_rate).abs();
+        }
+    }
+    (bins, ece)
+}
+
+/// Summary stored with the model version and shown on the evaluation card.
+pub fn report(calibrator: &Calibrator, calibrated: &[f32], y: &[f32]) -> CalibrationReport {
+    let (bins, ece) = reliability(calibrated, y);
+    CalibrationReport {
+        method: calibrator.method().as_str().to_string(),
+        holdout_rows: y.len() as u32,
+        ece,
+        bins,
+    }
+}
+
+fn logit(p: f32) -> f32 {
+    let p = (p as f64).clamp(EPS, 1.0 - EPS);
+    (p / (1.0 - p)).ln() as f32
+}
+
+fn sigmoid(z: f32) -> f32 {
+    1.0 / (1.0 + (-z).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Overconfident scores: the true positive rate is much flatter than the raw probability.
+    fn overconfident() -> (Vec<f32>, Vec<f32>) {
+        let mut raw = Vec::new();
+        let mut y = Vec::new();
+        for i in 0..100 {
+            let p = if i % 2 == 0 { 0.95 } else { 0.05 };
+            raw.push(p);
+            // 70% of the "0.95" rows and 30% of the "0.05" rows are positive.
+            let positive = if i % 2 == 0 { i % 10 < 7 } else { i % 10 < 3 };
+            y.push(if positive { 1.0 } else { 0.0 });
+        }
+        (raw, y)
+    }
+
+    #[test]
+    fn both_methods_reduce_calibration_error() {
+        let (raw, y) = overconfident();
+        let (_, before) = reliability(&raw, &y);
+        for method in [CalibrationMethod::Platt, CalibrationMethod::Isotonic] {
+            let calibrator = Calibrator::fit(method, &raw, &y).unwrap();
+            let calibrated = raw.iter().map(|p| calibrator.apply(*p)).collect::<Vec<_>>();
+            let (_, after) = reliability(&calibrated, &y);
+            assert!(after < before / 2.0, "{method:?}: {after} vs {before}");
+        }
+    }
+
+    #[test]
+    fn isotonic_is_monotone_and_roundtrips() {
+        let raw = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6];
+        let y = [0.0, 1.0, 0.0, 0.0, 1.0, 1.0];
+        let calibrator = Calibrator::fit(CalibrationMethod::Isotonic, &raw, &y).unwrap();
+        let mapped = raw.iter().map(|p| calibrator.apply(*p)).collect::<Vec<_>>();
+        assert!(mapped.windows(2).all(|w| w[0] <= w[1]));
+
+        let mut bytes = Vec::new();
+        calibrator.encode(&mut bytes);
+        let decoded = Calibrator::decode(&mut Reader::new(&bytes)).unwrap();
+        assert_eq!(decoded, calibrator);
+    }
+}
@@ -0,0 +1,500 @@
+//! Strict schema checks for `TrainConfig`.
+//!
+//! Configs are strict by default: every key must be known, the model kind
+//! must be one of the supported families, the DP section must be present and
+//! the DP and fairness sections must be complete and in range. Problems are reported per field, using
+//! dotted paths such as `dp.epsilon` or `features[1].impute`:
+//!
+//! ```json
+//! [{"field": "model_kind", "error": "train_cfg_model_kind_unknown"}]
+//! ```
+//!
+//! `"strict": false` restores the lenient behaviour of older clients, where
+//! unknown keys are ignored and missing values fall back to their defaults.
+//! Both modes need a valid JSON document; the spec is read from the parsed
+//! document only.
+//! The section parsers (`params`, `search`, `calibration`, ...) still validate
+//! their own values in both modes.
+//!
+//! [`normalized`] echoes a parsed config with every default filled in, so
+//! callers can see exactly what a run will use.
+
+use crate::common::json::{self, Value};
+
+use super::domain::{Hyperparams, ModelKind, TrainSpec, DEFAULT_LABEL};
+use super::features::Transform;
+use super::search::ParamRange;
+
+const TOP_LEVEL_KEYS: &[&str] = &[
+    "strict",
+    "model_kind",
+    "label",
+    "features",
+    "params",
+    "dp",
+    "fairness",
+    "search",
+    "calibration",
+    "encoder",
+    "warm_start",
+    "card",
+];
+const DP_KEYS: &[&str] = &["enabled", "epsilon", "delta", "clip", "noise_multiplier"];
+const FAIRNESS_KEYS: &[&str] = &["delta_tpr", "delta_fpr", "delta_ppv"];
+const FEATURE_KEYS: &[&str] = &[
+    "name",
+    "type",
+    "standardize",
+    "max_categories",
+    "buckets",
+    "impute",
+];
+const SEARCH_KEYS: &[&str] = &["strategy", "folds", "seed", "trials", "space"];
+const RANGE_KEYS: &[&str] = &["min", "max", "log"];
+const CALIBRATION_KEYS: &[&str] = &["method", "holdout", "seed"];
+const ENCODER_KEYS: &[&str] = &["model_id", "version", "text_column"];
+const WARM_START_KEYS: &[&str] = &["model_id", "version", "include_parent_data"];
+const CARD_KEYS: &[&str] = &["risk", "limitations", "contact"];
+
+/// Accepted values of a numeric field.
+type RangeCheck = fn(f64) -> bool;
+
+/// One rejected field of a train config.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigIssue {
+    /// Dotted path of the offending key; empty for the document itself.
+    pub field: String,
+    pub code: &'static str,
+}
+
+impl ConfigIssue {
+    pub fn new(field: impl Into<String>, code: &'static str) -> Self {
+        Self {
+            field: field.into(),
+            code,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"field\":\"{}\",\"error\":\"{}\"}}",
+            json::escape(&self.field),
+            self.code
+        )
+    }
+}
+
+/// Whether the config asks for strict parsing (the default).
+pub fn is_strict(root: &Value) -> bool {
+    root.get("strict").and_then(Value::as_bool).unwrap_or(true)
+}
+
+/// Every schema problem of a parsed config: unknown keys, wrong types, an
+/// unknown model kind and incomplete or out-of-range DP and fairness values.
+pub fn schema_issues(root: &Value) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+    if root.as_object().is_none() {
+        issues.push(ConfigIssue::new("", "train_cfg_invalid"));
+        return issues;
+    }
+    unknown_keys(root, TOP_LEVEL_KEYS, "", &mut issues);
+
+    if let Some(value) = root.get("strict") {
+        if value.as_bool().is_none() {
+            issues.push(ConfigIssue::new("strict", "train_cfg_type_invalid"));
+        }
+    }
+    if let Some(value) = root.get("model_kind") {
+        match value.as_str() {
+            Some(label) if ModelKind::from_config_label(label).is_some() => {}
+            Some(_) => issues.push(ConfigIssue::new(
+                "model_kind",
+                "train_cfg_model_kind_unknown",
+            )),
+            None => issues.push(ConfigIssue::new("model_kind", "train_cfg_type_invalid")),
+        }
+    }
+
+    if root.get("dp").is_none_or(Value::is_null) {
+        // Strict configs state their privacy choice, even when it is "off".
+        issues.push(ConfigIssue::new("dp", "train_cfg_field_missing"));
+    }
+    if let Some(dp) = section(root, "dp", &mut issues) {
+        unknown_keys(dp, DP_KEYS, "dp", &mut issues);
+        let enabled = match dp.get("enabled") {
+            None => false,
+            Some(value) => value.as_bool().unwrap_or_else(|| {
+                issues.push(ConfigIssue::new("dp.enabled", "train_cfg_type_invalid"));
+                false
+            }),
+        };
+        let ranges: [(&str, RangeCheck); 4] = [
+            ("epsilon", |v| v > 0.0),
+            ("delta", |v| (0.0..1.0).contains(&v)),
+            ("clip", |v| v > 0.0),
+            ("noise_multiplier", |v| v > 0.0),
+        ];
+        for (key, in_range) in ranges {
+            // With DP enabled every privacy parameter must be stated explicitly.
+            number(dp, key, "dp", enabled, in_range, &mut issues);
+        }
+    }
+
+    if let Some(fairness) = section(root, "fairness", &mut issues) {
+        unknown_keys(fairness, FAIRNESS_KEYS, "fairness", &mut issues);
+        for key in FAIRNESS_KEYS {
+            number(
+                fairness,
+                key,
+                "fairness",
+                true,
+                |v| (0.0..=1.0).contains(&v),
+                &mut issues,
+            );
+        }
+    }
+
+    if let Some(params) = section(root, "params", &mut issues) {
+        unknown_keys(params, &Hyperparams::NAMES, "params", &mut issues);
+    }
+    if let Some(features) = root.get("features").and_then(Value::as_array) {
+        for (index, feature) in features.iter().enumerate() {
+            unknown_keys(
+                feature,
+                FEATURE_KEYS,
+                &format!("features[{index}]"),
+                &mut issues,
+            );
+        }
+    }
+    if let Some(search) = section(root, "search", &mut issues) {
+        unknown_keys(search, SEARCH_KEYS, "search", &mut issues);
+        if let Some(space) = search.get("space").and_then(Value::as_object) {
+            for (name, range) in space {
+                if range.as_object().is_some() {
+                    unknown_keys(
+                        range,
+                        RANGE_KEYS,
+                        &format!("search.space.{name}"),
+                        &mut issues,
+                    );
+                }
+            }
+        }
+    }
+    for (key, allowed) in [
+        ("calibration", CALIBRATION_KEYS),
+        ("encoder", ENCODER_KEYS),
+        ("warm_start", WARM_START_KEYS),
+        ("card", CARD_KEYS),
+    ] {
+        if let Some(value) = section(root, key, &mut issues) {
+            unknown_keys(value, allowed, key, &mut issues);
+        }
+    }
+    issues
+}
+
+/// JSON array of issues, in the order they were found.
+pub fn issues_json(issues: &[ConfigIssue]) -> String {
+    let items = issues.iter().map(ConfigIssue::to_json).collect::<Vec<_>>();
+    format!("[{}]", items.join(","))
+}
+
+/// The parsed spec as a config document with every default spelled out.
+pub fn normalized(spec: &TrainSpec) -> Value {
+    let dp = &spec.dp;
+    let mut root = vec![
+        ("strict".to_string(), Value::Bool(spec.strict)),
+        (
+            "model_kind".to_string(),
+            text(spec.model_kind.config_label()),
+        ),
+        (
+            "label".to_string(),
+            text(spec.label.as_deref().unwrap_or(DEFAULT_LABEL)),
+        ),
+        (
+            "dp".to_string(),
+            object(vec![
+                ("enabled", Value::Bool(dp.enabled)),
+                ("epsilon", float(dp.epsilon)),
+                ("delta", float(dp.delta)),
+                ("clip", float(dp.clip)),
+                ("noise_multiplier", float(dp.noise_multiplier)),
+            ]),
+        ),
+        (
+            "params".to_string(),
+            Value::Object(
+                Hyperparams::NAMES
+                    .iter()
+                    .map(|name| {
+                        let value = spec.params.get(name).unwrap_or_default() as f32;
+                        (name.to_string(), float(value))
+                    })
+                    .collect(),
+            ),
+        ),
+    ];
+    let fairness = spec.fairness.as_ref().map(|report| {
+        object(vec![
+            ("delta_tpr", float(report.delta_tpr)),
+            ("delta_fpr", float(report.delta_fpr)),
+            ("delta_ppv", float(report.delta_ppv)),
+        ])
+    });
+    let features = spec.features.as_ref().map(|specs| {
+        Value::Array(
+            specs
+                .iter()
+                .map(|column| {
+                    let mut members = vec![
+                        ("name", text(&column.name)),
+                        ("type", text(column.transform.as_str())),
+                        ("impute", text(column.impute.as_str())),
+                    ];
+                    members.push(match column.transform {
+                        Transform::Numeric { standardize } => {
+                            ("standardize", Value::Bool(standardize))
+                        }
+                        Transform::OneHot { max_categories } => {
+                            ("max_categories", Value::Number(max_categories as f64))
+                        }
+                        Transform::Hashed { buckets } => ("buckets", Value::Number(buckets as f64)),
+                    });
+                    object(members)
+                })
+                .collect(),
+        )
+    });
+    let search = spec.search.as_ref().map(|search| {
+        let space = search
+            .space
+            .iter()
+            .map(|(name, range)| {
+                let range = match range {
+                    ParamRange::Values(values) => {
+                        Value::Array(values.iter().map(|v| Value::Number(*v)).collect())
+                    }
+                    ParamRange::Range { min, max, log } => object(vec![
+                        ("min", Value::Number(*min)),
+                        ("max", Value::Number(*max)),
+                        ("log", Value::Bool(*log)),
+                    ]),
+                };
+                (name.clone(), range)
+            })
+            .collect();
+        object(vec![
+            ("strategy", text(search.strategy.as_str())),
+            ("folds", Value::Number(search.folds as f64)),
+            ("seed", Value::Number(search.seed as f64)),
+            ("trials", Value::Number(search.trials as f64)),
+            ("space", Value::Object(space)),
+        ])
+    });
+    let calibration = spec.calibration.as_ref().map(|calibration| {
+        object(vec![
+            ("method", text(calibration.method.as_str())),
+            ("holdout", Value::Number(calibration.holdout)),
+            ("seed", Value::Number(calibration.seed as f64)),
+        ])
+    });
+    let encoder = spec.encoder.as_ref().map(|encoder| {
+        object(vec![
+            ("model_id", text(encoder.model_id.as_str())),
+            ("version", text(encoder.version.as_str())),
+            ("text_column", text(&encoder.column)),
+        ])
+    });
+    let warm_start = spec.warm_start.as_ref().map(|warm| {
+        object(vec![
+            ("model_id", text(warm.model_id.as_str())),
+            ("version", text(warm.version.as_str())),
+            ("include_parent_data", Value::Bool(warm.include_parent_data)),
+        ])
+    });
+    let card = &spec.card;
+    let optional = |value: Option<&str>| value.map(text).unwrap_or(Value::Null);
+    root.push((
+        "card".to_string(),
+        object(vec![
+            ("risk", optional(card.risk.map(|risk| risk.as_str()))),
+            (
+                "limitations",
+                Value::Array(card.limitations.iter().map(|item| text(item)).collect()),
+            ),
+            ("contact", optional(card.contact.as_deref())),
+        ]),
+    ));
+    for (key, value) in [
+        ("fairness", fairness),
+        ("features", features),
+        ("search", search),
+        ("calibration", calibration),
+        ("encoder", encoder),
+        ("warm_start", warm_start),
+    ] {
+        root.push((key.to_string(), value.unwrap_or(Value::Null)));
+    }
+    Value::Object(root)
+}
+
+fn object(members: Vec<(&str, Value)>) -> Value {
+    Value::Object(
+        members
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+    )
+}
+
+fn text(value: &str) -> Value {
+    Value::String(value.to_string())
+}
+
+/// Shortest decimal form of an `f32`, so `0.01` is echoed as `0.01`.
+fn float(value: f32) -> Value {
+    Value::Number(value.to_string().parse().unwrap_or_default())
+}
+
+/// The object under `key`, or `None` when absent or null; other types are an issue.
+fn section<'a>(root: &'a Value, key: &str, issues: &mut Vec<ConfigIssue>) -> Option<&'a Value> {
+    match root.get(key) {
+        None | Some(Value::Null) => None,
+        Some(value) if value.as_object().is_some() => Some(value),
+        Some(_) => {
+            issues.push(ConfigIssue::new(key, "train_cfg_type_invalid"));
+            None
+        }
+    }
+}
+
+fn unknown_keys(object: &Value, allowed: &[&str], prefix: &str, issues: &mut Vec<ConfigIssue>) {
+    for (key, _) in object.as_object().unwrap_or_default() {
+        if !allowed.contains(&key.as_str()) {
+            issues.push(ConfigIssue::new(path(prefix, key), "train_cfg_unknown_key"));
+        }
+    }
+}
+
+fn number(
+    object: &Value,
+    key: &str,
+    prefix: &str,
+    required: bool,
+    in_range: RangeCheck,
+    issues: &mut Vec<ConfigIssue>,
+) {
+    let code = match object.get(key) {
+        None if required => "train_cfg_field_missing",
+        None => return,
+        Some(value) => match value.as_f64() {
+            None => "train_cfg_type_invalid",
+            Some(n) if !in_range(n) => "train_cfg_out_of_range",
+            Some(_) => return,
+        },
+    };
+    issues.push(ConfigIssue::new(path(prefix, key), code));
+}
+
+fn path(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issues(raw: &str) -> Vec<(String, &'static str)> {
+        schema_issues(&json::parse(raw).unwrap())
+            .into_iter()
+            .map(|issue| (issue.field, issue.code))
+            .collect()
+    }
+
+    #[test]
+    fn reports_each_bad_field_by_path() {
+        let found = issues(
+            "{\"model_kind\":\"tabular_gbt\",\"dp\":{\"enabled\":true,\"epsilon\":-1,\"delta\":0.00001,\"clip\":1},\"fairness\":{\"delta_tpr\":0.01,\"delta_fpr\":0.01,\"delta_ppv\":0.01},\"features\":[{\"name\":\"a\",\"type\":\"numeric\",\"scale\":2}],\"epochs\":5}",
+        );
+        assert_eq!(
+            found,
+            vec![
+                ("epochs".to_string(), "train_cfg_unknown_key"),
+                ("model_kind".to_string(), "train_cfg_model_kind_unknown"),
+                ("dp.epsilon".to_string(), "train_cfg_out_of_range"),
+                ("dp.noise_multiplier".to_string(), "train_cfg_field_missing"),
+                ("features[0].scale".to_string(), "train_cfg_unknown_key"),
+            ]
+        );
+        assert!(issues("{\"model_kind\":\"tabular_gbdt\",\"dp\":{\"enabled\":false}}").is_empty());
+        assert!(!is_strict(
+            &json::parse("{\"strict\":false,\"typo\":1}").unwrap()
+        ));
+    }
+
+    #[test]
+    fn normalized_configs_are_complete_and_stable() {
+        use crate::training::domain::TrainConfig;
+
+        let cfg = TrainConfig::parse(
+            "{\"dp\":{\"enabled\":false},\"model_kind\":\"tabular_gbdt\",\"fairness\":{\"delta_tpr\":0.01,\"delta_fpr\":0.02,\"delta_ppv\":0.03},\"params\":{\"n_trees\":7},\"calibration\":{\"method\":\"platt\"}}"
+                .to_string(),
+        )
+        .unwrap();
+        let normalized = cfg.normalized();
+        assert!(normalized.contains("\"dp\":{\"clip\":1,\"delta\":0.00001,\"enabled\":false,\"epsilon\":3,\"noise_multiplier\":1}"));
+        assert!(normalized
+            .contains("\"calibration\":{\"holdout\":0.2,\"method\":\"platt\",\"seed\":42}"));
+        assert!(normalized.contains("\"n_trees\":7"));
+        let reparsed = TrainConfig::parse(normalized.clone()).unwrap();
+        assert_eq!(reparsed.normalized(), normalized);
+
+        let lenient = TrainConfig::parse(
+            "{\"strict\":false,\"model_kind\":\"tabular_gbt\",\"typo\":1}".to_string(),
+        )
+        .unwrap();
+        assert_eq!(lenient.model_kind(), ModelKind::TabularLogistic);
+    }
+
+    #[test]
+    fn sections_are_read_from_the_document_not_the_text() {
+        use crate::common::error::DeltaCode;
+        use crate::training::domain::TrainConfig;
+        use crate::training::service::{check_dp, check_fairness};
+
+        // Features named after top-level keys come first in the text.
+        let cfg = TrainConfig::parse(
+            "{\"features\":[{\"name\":\"fairness\",\"type\":\"numeric\"},{\"name\":\"dp\",\"type\":\"numeric\"}],\"dp\":{\"enabled\":true,\"epsilon\":8,\"delta\":0.00001,\"clip\":1,\"noise_multiplier\":1.1},\"fairness\":{\"delta_tpr\":0.9,\"delta_fpr\":0.01,\"delta_ppv\":0.01}}"
+                .to_string(),
+        )
+        .unwrap();
+        assert!(cfg.dp().enabled);
+        assert_eq!(cfg.fairness().map(|report| report.delta_tpr), Some(0.9));
+        assert_eq!(check_dp(cfg.dp()).unwrap_err().msg, "dp_epsilon_exceeded");
+        assert_eq!(
+            check_fairness(cfg.fairness()).unwrap_err().msg,
+            "delta_tpr_exceeded"
+        );
+        let normalized = cfg.normalized();
+        assert!(normalized.contains("\"enabled\":true,\"epsilon\":8"));
+        assert!(normalized.contains("\"delta_tpr\":0.9"));
+
+        // Strict configs must state the DP section; lenient ones default it.
+        assert_eq!(
+            issues("{}"),
+            vec![("dp".to_string(), "train_cfg_field_missing")]
+        );
+        let missing = TrainConfig::parse("{\"model_kind\":\"tabular_gbdt\"}".to_string());
+        assert_eq!(missing.unwrap_err().code, DeltaCode::InvalidInput);
+        let lenient = TrainConfig::parse("{\"strict\":false}".to_string()).unwrap();
+        assert!(!lenient.dp().enabled);
+        assert!(TrainConfig::parse("{\"strict\":false,\"dp\":".to_string()).is_err());
+    }
+}
@@ -1,12 +1,21 @@
 //! Domain types for model training and versioning.
 //!
 //! TODO: Encode semantic version identifiers with stronger typing.
-//! TODO: Track parent dataset identifiers for lineage and reproducibility.
 
-use crate::common::error::DeltaResult;
-use crate::common::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::common::error::{DeltaError, DeltaResult};
+use crate::common::json::{self, Value};
+use crate::common::time;
 use crate::data::domain::DatasetId;
 
+use super::artefact::ModelArtefact;
+use super::calibration::CalibrationSpec;
+use super::config::{self, ConfigIssue};
+use super::features::ColumnSpec;
+use super::search::SearchSpec;
+
 /// Identifier for a logical model family.
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct ModelId(String);
@@ -43,17 +52,108 @@ impl VersionName {
 }
 
 /// Supported model kinds defined by the product roadmap.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 pub enum ModelKind {
+    #[default]
     TabularLogistic,
     TabularGradientBoosting,
     TextMiniLm,
 }
 
-impl Default for ModelKind {
-    fn default() -> Self {
-        ModelKind::TabularLogistic
+impl ModelKind {
+    /// Stable label used in model identifiers, cards and artefact metadata.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModelKind::TabularLogistic => "tabular-logreg",
+            ModelKind::TabularGradientBoosting => "tabular-gbdt",
+            ModelKind::TextMiniLm => "text-minilm",
+        }
     }
+
+    /// Inverse of [`ModelKind::as_str`].
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "tabular-logreg" => Some(ModelKind::TabularLogistic),
+            "tabular-gbdt" => Some(ModelKind::TabularGradientBoosting),
+            "text-minilm" => Some(ModelKind::TextMiniLm),
+            _ => None,
+        }
+    }
+
+    /// Value of `"model_kind"` in a train config.
+    pub fn config_label(&self) -> &'static str {
+        match self {
+            ModelKind::TabularLogistic => "tabular_logreg",
+            ModelKind::TabularGradientBoosting => "tabular_gbdt",
+            ModelKind::TextMiniLm => "text_minilm",
+        }
+    }
+
+    /// Inverse of [`ModelKind::config_label`].
+    pub fn from_config_label(label: &str) -> Option<Self> {
+        match label {
+            "tabular_logreg" => Some(ModelKind::TabularLogistic),
+            "tabular_gbdt" => Some(ModelKind::TabularGradientBoosting),
+            "text_minilm" => Some(ModelKind::TextMiniLm),
+            _ => None,
+        }
+    }
+}
+
+/// Lifecycle stage of a model version; only `Production` is served by default.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ModelStage {
+    #[default]
+    Candidate,
+    Approved,
+    Production,
+    Archived,
+}
+
+impl ModelStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModelStage::Candidate => "candidate",
+            ModelStage::Approved => "approved",
+            ModelStage::Production => "production",
+            ModelStage::Archived => "archived",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "candidate" => Some(ModelStage::Candidate),
+            "approved" => Some(ModelStage::Approved),
+            "production" => Some(ModelStage::Production),
+            "archived" => Some(ModelStage::Archived),
+            _ => None,
+        }
+    }
+
+    /// Whether the approval workflow allows moving from `self` to `next`.
+    pub fn can_transition_to(&self, next: ModelStage) -> bool {
+        matches!(
+            (self, next),
+            (ModelStage::Candidate, ModelStage::Approved)
+                | (ModelStage::Approved, ModelStage::Production)
+                | (ModelStage::Candidate, ModelStage::Archived)
+                | (ModelStage::Approved, ModelStage::Archived)
+                | (ModelStage::Production, ModelStage::Archived)
+        )
+    }
+
+    /// Stages that may only be entered with a recorded approver.
+    pub fn requires_approver(&self) -> bool {
+        matches!(self, ModelStage::Approved | ModelStage::Production)
+    }
+}
+
+/// Current lifecycle state of a version, including who moved it there.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Lifecycle {
+    pub stage: ModelStage,
+    pub approver: Option<String>,
+    pub changed_ms: u128,
 }
 
 /// Metadata associated with a model version that affects routing and governance.
@@ -61,6 +161,81 @@ impl Default for ModelKind {
 pub struct ModelMetadata {
     pub dp: DifferentialPrivacy,
     pub fairness: Option<FairnessReport>,
+    /// Hyperparameters of the final fit (the search winner when a search ran).
+    pub hyperparams: Hyperparams,
+    pub search: Option<SearchSummary>,
+    pub calibration: Option<CalibrationReport>,
+    pub lineage: Lineage,
+    pub metrics: Option<EvalMetrics>,
+    pub importance: Option<FeatureImportance>,
+    pub card: CardInfo,
+}
+
+/// Where a version came from: the version it was warm-started from and the
+/// datasets its final fit saw.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Lineage {
+    pub parent: Option<(ModelId, VersionName)>,
+    pub datasets: Vec<DatasetId>,
+    /// Earliest and latest ingest time (ms) of `datasets`.
+    pub period: Option<(u128, u128)>,
+}
+
+/// Metrics of the final fit, on the calibration holdout when there is one and
+/// on the training rows otherwise.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalMetrics {
+    /// `"holdout"` or `"train"`.
+    pub split: String,
+    pub rows: u32,
+    pub accuracy: f64,
+    pub log_loss: f64,
+    pub auc: f64,
+}
+
+/// Global weight of each model input: logistic coefficients or the total
+/// split gain of the boosted trees, in pipeline output order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeatureImportance {
+    /// `"coefficient"` or `"gain"`.
+    pub method: String,
+    pub features: Vec<(String, f64)>,
+}
+
+/// Risk class of the model's intended use (EU AI Act), ordered from
+/// minimal to high.
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum RiskLevel {
+    Minimal,
+    Limited,
+    High,
+}
+
+impl RiskLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RiskLevel::Minimal => "minimal",
+            RiskLevel::Limited => "limited",
+            RiskLevel::High => "high",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "minimal" => Some(RiskLevel::Minimal),
+            "limited" => Some(RiskLevel::Limited),
+            "high" => Some(RiskLevel::High),
+            _ => None,
+        }
+    }
+}
+
+/// Governance details the train config supplies for the model card.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CardInfo {
+    pub risk: Option<RiskLevel>,
+    pub limitations: Vec<String>,
+    pub contact: Option<String>,
 }
 
 /// Differential privacy configuration snapshot.
@@ -73,6 +248,137 @@ pub struct DifferentialPrivacy {
     pub noise_multiplier: f32,
 }
 
+/// Hyperparameters shared by the tabular trainers.
+///
+/// `l2` regularises the logistic weights and the GBDT leaf values; the tree
+/// settings are ignored by the logistic trainer and vice versa for `epochs`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hyperparams {
+    pub l2: f32,
+    pub learning_rate: f32,
+    pub epochs: u32,
+    pub max_depth: u32,
+    pub n_trees: u32,
+}
+
+impl Default for Hyperparams {
+    fn default() -> Self {
+        Self {
+            l2: 0.01,
+            learning_rate: 0.1,
+            epochs: 200,
+            max_depth: 3,
+            n_trees: 50,
+        }
+    }
+}
+
+impl Hyperparams {
+    /// Parameter names accepted in `"params"` and search spaces.
+    pub const NAMES: [&'static str; 5] = ["l2", "learning_rate", "epochs", "max_depth", "n_trees"];
+
+    pub fn get(&self, name: &str) -> Option<f64> {
+        match name {
+            "l2" => Some(self.l2 as f64),
+            "learning_rate" => Some(self.learning_rate as f64),
+            "epochs" => Some(self.epochs as f64),
+            "max_depth" => Some(self.max_depth as f64),
+            "n_trees" => Some(self.n_trees as f64),
+            _ => None,
+        }
+    }
+
+    /// Set a parameter by name; integer parameters are rounded. Returns `false` for unknown names.
+    pub fn set(&mut self, name: &str, value: f64) -> bool {
+        let count = || value.round().clamp(0.0, u32::MAX as f64) as u32;
+        match name {
+            "l2" => self.l2 = value as f32,
+            "learning_rate" => self.learning_rate = value as f32,
+            "epochs" => self.epochs = count(),
+            "max_depth" => self.max_depth = count(),
+            "n_trees" => self.n_trees = count(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Reject values outside the ranges the trainers are designed for.
+    pub fn validate(&self) -> DeltaResult<()> {
+        let valid = self.l2.is_finite()
+            && self.l2 >= 0.0
+            && self.learning_rate > 0.0
+            && self.learning_rate <= 10.0
+            && (1..=10_000).contains(&self.epochs)
+            && (1..=12).contains(&self.max_depth)
+            && (1..=1_000).contains(&self.n_trees);
+        if valid {
+            Ok(())
+        } else {
+            Err(DeltaError::invalid("train_cfg_params_invalid"))
+        }
+    }
+
+    /// JSON object with every parameter, in [`Hyperparams::NAMES`] order.
+    pub fn to_json(&self) -> String {
+        let fields = Self::NAMES
+            .iter()
+            .map(|name| format!("\"{}\":{}", name, self.get(name).unwrap_or_default()))
+            .collect::<Vec<_>>();
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+/// Outcome of a hyperparameter search, summarised on the model card.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchSummary {
+    pub strategy: String,
+    pub folds: u32,
+    pub trials: u32,
+    pub best_trial: u32,
+    pub best_log_loss: f64,
+    pub best_accuracy: f64,
+}
+
+/// Cross-validated metrics of one hyperparameter candidate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trial {
+    pub index: u32,
+    pub params: Hyperparams,
+    pub fold_log_loss: Vec<f64>,
+    /// Mean out-of-fold log loss; the search minimises this.
+    pub log_loss: f64,
+    pub accuracy: f64,
+}
+
+/// Calibration quality measured on the held-out split after applying the calibrator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CalibrationReport {
+    pub method: String,
+    pub holdout_rows: u32,
+    /// Expected calibration error: count-weighted gap between predicted and observed rates.
+    pub ece: f64,
+    pub bins: Vec<ReliabilityBin>,
+}
+
+/// One bucket of a reliability diagram over `[lower, upper)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReliabilityBin {
+    pub lower: f32,
+    pub upper: f32,
+    pub count: u32,
+    pub mean_predicted: f64,
+    pub observed_rate: f64,
+}
+
+impl ReliabilityBin {
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"lower\":{},\"upper\":{},\"count\":{},\"mean_predicted\":{:.6},\"observed_rate\":{:.6}}}",
+            self.lower, self.upper, self.count, self.mean_predicted, self.observed_rate
+        )
+    }
+}
+
 /// Simplified fairness metrics captured during evaluation.
 #[derive(Clone, Debug, Default)]
 pub struct FairnessReport {
@@ -88,8 +394,70 @@ pub struct ModelVersion {
     pub version: VersionName,
     pub kind: ModelKind,
     pub artefact_path: String,
+    pub created_ms: u128,
+    pub lifecycle: Lifecycle,
     pub metadata: ModelMetadata,
-    // TODO: Add checksum/hash fields to detect corruption early.
+    /// Trailing artefact checksum (hex), known once the artefact has been written or read.
+    pub checksum: Option<String>,
+}
+
+/// Value type of an input feature listed in the artefact manifest.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FeatureKind {
+    Numeric,
+    Categorical,
+    Text,
+}
+
+/// Single entry of the feature manifest stored with every artefact.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeatureSpec {
+    pub name: String,
+    pub kind: FeatureKind,
+}
+
+impl FeatureSpec {
+    pub fn new(name: impl Into<String>, kind: FeatureKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+        }
+    }
+}
+
+/// Node of a regression tree; leaves carry `feature == TreeNode::LEAF`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TreeNode {
+    pub feature: u32,
+    pub threshold: f32,
+    pub left: u32,
+    pub right: u32,
+    pub value: f32,
+}
+
+impl TreeNode {
+    /// Sentinel feature index marking a leaf.
+    pub const LEAF: u32 = u32::MAX;
+
+    pub fn leaf(value: f32) -> Self {
+        Self {
+            feature: Self::LEAF,
+            threshold: 0.0,
+            left: 0,
+            right: 0,
+            value,
+        }
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        self.feature == Self::LEAF
+    }
+}
+
+/// Regression tree stored as a flat node list; node 0 is the root.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Tree {
+    pub nodes: Vec<TreeNode>,
 }
 
 /// Training configuration blob (mini JSON string parsed into a structured spec).
@@ -100,13 +468,29 @@ pub struct TrainConfig {
 }
 
 impl TrainConfig {
+    /// Parse a config, failing with the code of the first rejected field.
     pub fn parse(raw: String) -> DeltaResult<Self> {
+        Self::check(raw).map_err(|issues| DeltaError::invalid(issues[0].code))
+    }
+
+    /// Parse a config, reporting every rejected field (see [`super::config`]).
+    pub fn check(raw: String) -> Result<Self, Vec<ConfigIssue>> {
         Ok(Self {
-            spec: TrainSpec::from_raw(&raw),
+            spec: TrainSpec::from_raw(&raw)?,
             raw,
         })
     }
 
+    /// The fully defaulted config as canonical JSON; parsing it yields the same spec.
+    pub fn normalized(&self) -> String {
+        config::normalized(&self.spec).canonical()
+    }
+
+    /// Whether unknown keys and incomplete sections are rejected.
+    pub fn strict(&self) -> bool {
+        self.spec.strict
+    }
+
     pub fn model_kind(&self) -> ModelKind {
         self.spec.model_kind
     }
@@ -118,56 +502,448 @@ impl TrainConfig {
     pub fn dp(&self) -> &DifferentialPrivacy {
         &self.spec.dp
     }
+
+    /// Target column of the training records.
+    pub fn label(&self) -> &str {
+        self.spec.label.as_deref().unwrap_or(DEFAULT_LABEL)
+    }
+
+    /// Base hyperparameters, used directly or as the starting point of a search.
+    pub fn params(&self) -> &Hyperparams {
+        &self.spec.params
+    }
+
+    pub fn search(&self) -> Option<&SearchSpec> {
+        self.spec.search.as_ref()
+    }
+
+    /// Frozen encoder whose sentence embeddings feed a text classification head.
+    pub fn encoder(&self) -> Option<&EncoderRef> {
+        self.spec.encoder.as_ref()
+    }
+
+    /// Seed of the run's randomised steps: the search seed, else the
+    /// calibration seed. The trainers themselves draw no random numbers.
+    pub fn seed(&self) -> u64 {
+        self.search()
+            .map(|spec| spec.seed)
+            .or_else(|| self.calibration().map(|spec| spec.seed))
+            .unwrap_or(super::search::DEFAULT_SEED)
+    }
+
+    /// Parent version to continue training from; `None` trains from scratch.
+    pub fn warm_start(&self) -> Option<&WarmStart> {
+        self.spec.warm_start.as_ref()
+    }
+
+    /// Holdout calibration; `None` keeps the raw model probabilities.
+    pub fn calibration(&self) -> Option<&CalibrationSpec> {
+        self.spec.calibration.as_ref()
+    }
+
+    /// Declared feature columns; `None` means infer them from the data.
+    pub fn features(&self) -> Option<&[ColumnSpec]> {
+        self.spec.features.as_deref()
+    }
+
+    /// Risk level, limitations and contact for the model card.
+    pub fn card(&self) -> &CardInfo {
+        &self.spec.card
+    }
 }
 
+/// Label column used when the config does not name one.
+pub const DEFAULT_LABEL: &str = "label";
+
 /// Internal training specification derived from JSON.
 #[derive(Clone, Debug, Default)]
 pub struct TrainSpec {
+    pub strict: bool,
     pub model_kind: ModelKind,
     pub dp: DifferentialPrivacy,
     pub fairness: Option<FairnessReport>,
+    pub label: Option<String>,
+    pub features: Option<Vec<ColumnSpec>>,
+    pub params: Hyperparams,
+    pub search: Option<SearchSpec>,
+    pub calibration: Option<CalibrationSpec>,
+    pub encoder: Option<EncoderRef>,
+    pub warm_start: Option<WarmStart>,
+    pub card: CardInfo,
+}
+
+/// Stored text model used as a frozen encoder, and the record column it embeds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EncoderRef {
+    pub model_id: ModelId,
+    pub version: VersionName,
+    pub column: String,
+}
+
+/// Parent version for warm-start training.
+///
+/// With `include_parent_data` the new fit sees the parent's datasets as well
+/// as the new one; otherwise only the new dataset.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WarmStart {
+    pub model_id: ModelId,
+    pub version: VersionName,
+    pub include_parent_data: bool,
 }
 
+/// Record column embedded when `"encoder"` does not name one.
+pub const DEFAULT_TEXT_COLUMN: &str = "text";
+
 impl TrainSpec {
-    fn from_raw(raw: &str) -> Self {
-        let model_kind = match json::extract_string(raw, "model_kind").as_deref() {
-            Some("tabular_gbdt") => ModelKind::TabularGradientBoosting,
-            Some("text_minilm") => ModelKind::TextMiniLm,
-            _ => ModelKind::TabularLogistic,
-        };
+    fn from_raw(raw: &str) -> Result<Self, Vec<ConfigIssue>> {
+        let root = json::parse(raw)
+            .ok()
+            .filter(|root| root.as_object().is_some())
+            .ok_or_else(|| vec![ConfigIssue::new("", "train_cfg_invalid")])?;
+        let strict = config::is_strict(&root);
+        if strict {
+            let issues = config::schema_issues(&root);
+            if !issues.is_empty() {
+                return Err(issues);
+            }
+        }
+        // Lenient configs fall back to logistic regression for unknown kinds.
+        let model_kind = root
+            .get("model_kind")
+            .and_then(Value::as_str)
+            .and_then(ModelKind::from_config_label)
+            .unwrap_or_default();
 
-        let dp_section = json::extract_object(raw, "dp").unwrap_or("{}");
+        let section = |key: &str| root.get(key).filter(|value| value.as_object().is_some());
+        let number =
+            |section: &Value, key: &str| section.get(key).and_then(Value::as_f64).map(|n| n as f32);
+        let no_dp = Value::Object(Vec::new());
+        let dp = section("dp").unwrap_or(&no_dp);
         let dp = DifferentialPrivacy {
-            enabled: json::extract_bool(dp_section, "enabled").unwrap_or(false),
-            epsilon: json::extract_number(dp_section, "epsilon").unwrap_or(3.0),
-            delta: json::extract_number(dp_section, "delta").unwrap_or(1e-5),
-            clip: json::extract_number(dp_section, "clip").unwrap_or(1.0),
-            noise_multiplier: json::extract_number(dp_section, "noise_multiplier").unwrap_or(1.0),
+            enabled: dp.get("enabled").and_then(Value::as_bool).unwrap_or(false),
+            epsilon: number(dp, "epsilon").unwrap_or(3.0),
+            delta: number(dp, "delta").unwrap_or(1e-5),
+            clip: number(dp, "clip").unwrap_or(1.0),
+            noise_multiplier: number(dp, "noise_multiplier").unwrap_or(1.0),
         };
-
-        let fairness = json::extract_object(raw, "fairness").map(|section| FairnessReport {
-            delta_tpr: json::extract_number(section, "delta_tpr").unwrap_or_default(),
-            delta_fpr: json::extract_number(section, "delta_fpr").unwrap_or_default(),
-            delta_ppv: json::extract_number(section, "delta_ppv").unwrap_or_default(),
+        let fairness = section("fairness").map(|fairness| FairnessReport {
+            delta_tpr: number(fairness, "delta_tpr").unwrap_or_default(),
+            delta_fpr: number(fairness, "delta_fpr").unwrap_or_default(),
+            delta_ppv: number(fairness, "delta_ppv").unwrap_or_default(),
         });
 
-        Self {
+        let mut spec = Self {
+            strict,
             model_kind,
             dp,
             fairness,
+            ..Self::default()
+        };
+        let at =
+            |field: &'static str| move |err: DeltaError| vec![ConfigIssue::new(field, err.msg)];
+        let (label, features) = Self::columns(&root).map_err(at("features"))?;
+        spec.label = label;
+        spec.features = features;
+        spec.params = Self::params(&root).map_err(at("params"))?;
+        spec.search = match root.get("search") {
+            None | Some(Value::Null) => None,
+            Some(section) => Some(SearchSpec::from_json(section).map_err(at("search"))?),
+        };
+        spec.calibration = match root.get("calibration") {
+            None | Some(Value::Null) => None,
+            Some(section) => CalibrationSpec::from_json(section).map_err(at("calibration"))?,
+        };
+        spec.encoder = Self::encoder(&root).map_err(at("encoder"))?;
+        if spec.encoder.is_some() && spec.model_kind != ModelKind::TextMiniLm {
+            return Err(vec![ConfigIssue::new(
+                "encoder",
+                "train_cfg_encoder_invalid",
+            )]);
+        }
+        // A text head is fitted on encoder embeddings; without one it could not be served.
+        if spec.encoder.is_none() && spec.model_kind == ModelKind::TextMiniLm {
+            return Err(vec![ConfigIssue::new("encoder", "encoder_missing")]);
+        }
+        spec.card = Self::card(&root).map_err(at("card"))?;
+        spec.warm_start = Self::warm_start(&root).map_err(at("warm_start"))?;
+        if spec.warm_start.is_some() {
+            if spec.model_kind == ModelKind::TextMiniLm {
+                return Err(vec![ConfigIssue::new(
+                    "warm_start",
+                    "warm_start_unsupported_model_kind",
+                )]);
+            }
+            if spec.search.is_some() {
+                return Err(vec![ConfigIssue::new(
+                    "warm_start",
+                    "train_cfg_warm_start_invalid",
+                )]);
+            }
         }
+        Ok(spec)
+    }
+
+    /// Read `"card": {"risk"?, "limitations"?, "contact"?}`.
+    fn card(root: &Value) -> DeltaResult<CardInfo> {
+        let invalid = || DeltaError::invalid("train_cfg_card_invalid");
+        let section = match root.get("card") {
+            None | Some(Value::Null) => return Ok(CardInfo::default()),
+            Some(section) => section,
+        };
+        let text = |key: &str| match section.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => value
+                .as_str()
+                .filter(|value| !value.is_empty())
+                .map(|value| Some(value.to_string()))
+                .ok_or_else(invalid),
+        };
+        let risk = match text("risk")? {
+            Some(label) => Some(RiskLevel::from_label(&label).ok_or_else(invalid)?),
+            None => None,
+        };
+        let limitations = match section.get("limitations") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(value) => value
+                .as_array()
+                .ok_or_else(invalid)?
+                .iter()
+                .map(|item| item.as_str().map(str::to_string).ok_or_else(invalid))
+                .collect::<DeltaResult<Vec<_>>>()?,
+        };
+        Ok(CardInfo {
+            risk,
+            limitations,
+            contact: text("contact")?,
+        })
+    }
+
+    /// Read `"warm_start": {"model_id", "version", "include_parent_data"?}`.
+    fn warm_start(root: &Value) -> DeltaResult<Option<WarmStart>> {
+        let invalid = || DeltaError::invalid("train_cfg_warm_start_invalid");
+        let section = match root.get("warm_start") {
+            None | Some(Value::Null) => return Ok(None),
+            Some(section) => section,
+        };
+        let text = |key: &str| {
+            section
+                .get(key)
+                .and_then(Value::as_str)
+                .filter(|value| !value.is_empty())
+                .ok_or_else(invalid)
+        };
+        let include_parent_data = match section.get("include_parent_data") {
+            None | Some(Value::Null) => false,
+            Some(value) => value.as_bool().ok_or_else(invalid)?,
+        };
+        Ok(Some(WarmStart {
+            model_id: ModelId::new(text("model_id")?),
+            version: VersionName::new(text("version")?),
+            include_parent_data,
+        }))
+    }
+
+    /// Read `"encoder": {"model_id", "version", "text_column"?}`.
+    fn encoder(root: &Value) -> DeltaResult<Option<EncoderRef>> {
+        let invalid = || DeltaError::invalid("train_cfg_encoder_invalid");
+        let section = match root.get("encoder") {
+            None | Some(Value::Null) => return Ok(None),
+            Some(section) => section,
+        };
+        let text = |key: &str| {
+            section
+                .get(key)
+                .and_then(Value::as_str)
+                .filter(|value| !value.is_empty())
+        };
+        let column = match section.get("text_column") {
+            None => DEFAULT_TEXT_COLUMN,
+            Some(_) => text("text_column").ok_or_else(invalid)?,
+        };
+        Ok(Some(EncoderRef {
+            model_id: ModelId::new(text("model_id").ok_or_else(invalid)?),
+            version: VersionName::new(text("version").ok_or_else(invalid)?),
+            column: column.to_string(),
+        }))
+    }
+
+    /// Base hyperparameters from the `"params"` object.
+    fn params(root: &Value) -> DeltaResult<Hyperparams> {
+        let invalid = || DeltaError::invalid("train_cfg_params_invalid");
+        let mut params = Hyperparams::default();
+        if let Some(section) = root.get("params").filter(|v| !v.is_null()) {
+            for (name, value) in section.as_object().ok_or_else(invalid)? {
+                let value = value.as_f64().ok_or_else(invalid)?;
+                if !params.set(name, value) {
+                    return Err(invalid());
+                }
+            }
+        }
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Read the `label` and `features` entries.
+    fn columns(root: &Value) -> DeltaResult<(Option<String>, Option<Vec<ColumnSpec>>)> {
+        let invalid = || DeltaError::invalid("train_cfg_features_invalid");
+
+        let label = match root.get("label") {
+            None | Some(Value::Null) => None,
+            Some(value) => Some(
+                value
+                    .as_str()
+                    .filter(|name| !name.is_empty())
+                    .ok_or_else(invalid)?
+                    .to_string(),
+            ),
+        };
+        let features = match root.get("features") {
+            None | Some(Value::Null) => None,
+            Some(value) => {
+                let specs = value
+                    .as_array()
+                    .ok_or_else(invalid)?
+                    .iter()
+                    .map(ColumnSpec::from_json)
+                    .collect::<DeltaResult<Vec<_>>>()?;
+                let target = label.as_deref().unwrap_or(DEFAULT_LABEL);
+                let duplicate = specs
+                    .iter()
+                    .enumerate()
+                    .any(|(i, spec)| specs[..i].iter().any(|other| other.name == spec.name));
+                if specs.is_empty() || duplicate || specs.iter().any(|spec| spec.name == target) {
+                    return Err(invalid());
+                }
+                Some(specs)
+            }
+        };
+        Ok((label, features))
     }
 }
 
 /// Repository contract for model artefacts.
 pub trait ModelRepo {
-    fn put_model(&self, model: &ModelVersion) -> DeltaResult<()>;
+    fn put_model(&self, model: &ModelVersion, artefact: &ModelArtefact) -> DeltaResult<()>;
     fn get_model(&self, id: &ModelId, version: &VersionName) -> DeltaResult<ModelVersion>;
-    // TODO: Introduce iterators over historical versions for rollback strategies.
+    fn get_artefact(&self, id: &ModelId, version: &VersionName) -> DeltaResult<ModelArtefact>;
+    /// Stored artefact exactly as written, e.g. to check its signature.
+    fn get_artefact_bytes(&self, id: &ModelId, version: &VersionName) -> DeltaResult<Vec<u8>>;
+    /// Identifiers of every model family with at least one stored version.
+    fn list_models(&self) -> DeltaResult<Vec<ModelId>>;
+    /// Stored versions of a model, oldest first (sorted by creation time).
+    fn list_versions(&self, id: &ModelId) -> DeltaResult<Vec<ModelVersion>>;
+    /// Append a lifecycle transition to the version's audit history.
+    fn put_lifecycle(
+        &self,
+        id: &ModelId,
+        version: &VersionName,
+        lifecycle: &Lifecycle,
+    ) -> DeltaResult<()>;
+    /// Store every hyperparameter search trial next to the artefact.
+    fn put_trials(&self, id: &ModelId, version: &VersionName, trials: &[Trial]) -> DeltaResult<()>;
+    /// Trials recorded for a version; empty when no search ran.
+    fn get_trials(&self, id: &ModelId, version: &VersionName) -> DeltaResult<Vec<Trial>>;
+    /// Read an auxiliary file stored next to the artefact, such as `tokenizer.json`.
+    fn get_asset(&self, id: &ModelId, version: &VersionName, name: &str) -> DeltaResult<Vec<u8>>;
+    /// Store an auxiliary file next to an existing artefact.
+    fn put_asset(
+        &self,
+        id: &ModelId,
+        version: &VersionName,
+        name: &str,
+        bytes: &[u8],
+    ) -> DeltaResult<()>;
 }
 
 /// Interface for components that can perform training.
 pub trait Trainer {
-    fn train(&self, dataset: DatasetId, cfg: &TrainConfig) -> DeltaResult<ModelVersion>;
-    // TODO: Provide hooks for progress reporting and cancellation.
+    fn train(
+        &self,
+        dataset: DatasetId,
+        cfg: &TrainConfig,
+        control: &TrainControl,
+    ) -> DeltaResult<ModelVersion>;
+}
+
+/// Progress snapshot emitted by the trainers after every epoch (or boosting round).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProgressEvent {
+    /// `"embed"` while computing text embeddings (epochs count records),
+    /// `"cv"` while cross-validating search candidates, `"fit"` for the final model.
+    pub phase: &'static str,
+    pub epoch: u32,
+    pub total_epochs: u32,
+    /// Training log loss after the epoch; zero while embedding.
+    pub loss: f64,
+    pub elapsed_ms: u128,
+}
+
+type ProgressSink = Box<dyn Fn(&ProgressEvent) + Send + Sync>;
+
+/// Error message of a run stopped through [`TrainControl::cancel`].
+pub const TRAINING_CANCELLED: &str = "training_cancelled";
+
+/// Progress reporting and cooperative cancellation for a single training run.
+///
+/// Trainers call [`TrainControl::check`] between epochs; once cancelled the
+/// run stops with `training_cancelled` before anything is persisted.
+pub struct TrainControl {
+    cancelled: Arc<AtomicBool>,
+    sink: Option<ProgressSink>,
+    started_ms: u128,
+}
+
+impl Default for TrainControl {
+    fn default() -> Self {
+        Self {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            sink: None,
+            started_ms: time::now_ms(),
+        }
+    }
+}
+
+impl TrainControl {
+    /// Control that forwards progress events to `sink`.
+    pub fn with_sink(sink: impl Fn(&ProgressEvent) + Send + Sync + 'static) -> Self {
+        Self {
+            sink: Some(Box::new(sink)),
+            ..Self::default()
+        }
+    }
+
+    /// Shared flag that cancels the run when set, e.g. from another thread.
+    pub fn cancel_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.cancelled)
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Fail with `training_cancelled` once cancellation was requested. The
+    /// caller asked for this, so it is `InvalidInput` rather than a server fault.
+    pub fn check(&self) -> DeltaResult<()> {
+        if self.is_cancelled() {
+            Err(DeltaError::invalid(TRAINING_CANCELLED))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn report(&self, phase: &'static str, epoch: u32, total_epochs: u32, loss: f64) {
+        if let Some(sink) = &self.sink {
+            sink(&ProgressEvent {
+                phase,
+                epoch,
+                total_epochs,
+                loss,
+                elapsed_ms: time::now_ms().saturating_sub(self.started_ms),
+            });
+        }
+    }
 }
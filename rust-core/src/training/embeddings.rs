@@ -0,0 +1,161 @@
+//! Sentence embeddings of a dataset's text column, cached on disk.
+//!
+//! Text heads are trained on embeddings from a frozen encoder. Computing them
+//! is by far the most expensive step, so every `(dataset, encoder version,
+//! column)` combination is embedded once and stored under
+//! `${DATA_ROOT}/embeddings/{dataset}/{key}.bin`:
+//!
+//! ```text
+//! magic "DEMB", u32 rows, u32 dim, rows × dim f32, u32 FNV-1a checksum
+//! ```
+//!
+//! Unreadable cache files are ignored and rewritten.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::common::config::AppCfg;
+use crate::common::error::{DeltaError, DeltaResult};
+use crate::common::ids::SimpleHash;
+use crate::common::json::Value;
+use crate::common::log;
+use crate::data::domain::DatasetId;
+use crate::inference::encoder::Encoder;
+use crate::inference::tokenizer::WordPieceTokenizer;
+
+use super::artefact::{checksum, put_f32, put_u32, Reader};
+use super::domain::{EncoderRef, TrainControl};
+
+const MAGIC: &[u8; 4] = b"DEMB";
+/// Rows between cancellation checks and progress events.
+const REPORT_EVERY: usize = 32;
+
+/// Filesystem cache of per-dataset embedding matrices.
+pub struct EmbeddingCache {
+    root: PathBuf,
+}
+
+impl EmbeddingCache {
+    pub fn new(cfg: &AppCfg) -> Self {
+        Self {
+            root: PathBuf::from(&cfg.data_root).join("embeddings"),
+        }
+    }
+
+    fn path(&self, dataset: &DatasetId, encoder: &EncoderRef) -> PathBuf {
+        let mut hasher = SimpleHash::new();
+        hasher.update(encoder.model_id.as_str().as_bytes());
+        hasher.update(&[0]);
+        hasher.update(encoder.version.as_str().as_bytes());
+        hasher.update(&[0]);
+        hasher.update(encoder.column.as_bytes());
+        self.root
+            .join(dataset.as_str())
+            .join(format!("{}.bin", hasher.finish_hex()))
+    }
+
+    /// Cached embeddings, or `None` when absent or unreadable.
+    pub fn get(&self, dataset: &DatasetId, encoder: &EncoderRef) -> Option<Vec<Vec<f32>>> {
+        let bytes = fs::read(self.path(dataset, encoder)).ok()?;
+        let decoded = decode(&bytes);
+        if decoded.is_none() {
+            log::log_json("warn", "training", "embedding_cache_corrupt", 0, 0);
+        }
+        decoded
+    }
+
+    pub fn put(
+        &self,
+        dataset: &DatasetId,
+        encoder: &EncoderRef,
+        rows: &[Vec<f32>],
+    ) -> DeltaResult<()> {
+        let path = self.path(dataset, encoder);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|_| DeltaError::io())?;
+        }
+        let mut file = File::create(&path).map_err(|_| DeltaError::io())?;
+        file.write_all(&encode(rows))
+            .and_then(|_| file.sync_all())
+            .map_err(|_| DeltaError::io())
+    }
+}
+
+/// Embed the text column of every record, reporting progress as phase `"embed"`.
+pub fn embed_records(
+    encoder: &Encoder,
+    tokenizer: &WordPieceTokenizer,
+    records: &[Value],
+    column: &str,
+    control: &TrainControl,
+) -> DeltaResult<Vec<Vec<f32>>> {
+    let mut rows = Vec::with_capacity(records.len());
+    for (index, record) in records.iter().enumerate() {
+        let text = record
+            .get(column)
+            .and_then(Value::as_str)
+            .ok_or_else(|| DeltaError::invalid("text_column_missing"))?;
+        rows.push(encoder.embed(&tokenizer.encode(text).ids)?);
+        if (index + 1) % REPORT_EVERY == 0 || index + 1 == records.len() {
+            control.check()?;
+            control.report("embed", (index + 1) as u32, records.len() as u32, 0.0);
+        }
+    }
+    Ok(rows)
+}
+
+fn encode(rows: &[Vec<f32>]) -> Vec<u8> {
+    let dim = rows.first().map_or(0, Vec::len);
+    let mut out = Vec::with_capacity(16 + rows.len() * dim * 4);
+    out.extend_from_slice(MAGIC);
+    put_u32(&mut out, rows.len() as u32);
+    put_u32(&mut out, dim as u32);
+    for row in rows {
+        row.iter().for_each(|v| put_f32(&mut out, *v));
+    }
+    let sum = checksum(&out);
+    put_u32(&mut out, sum);
+    out
+}
+
+fn decode(bytes: &[u8]) -> Option<Vec<Vec<f32>>> {
+    let body = bytes.get(..bytes.len().checked_sub(4)?)?;
+    let mut trailer = Reader::new(&bytes[body.len()..]);
+    if trailer.u32().ok()? != checksum(body) {
+        return None;
+    }
+    let mut reader = Reader::new(body);
+    if reader.take(4).ok()? != MAGIC {
+        return None;
+    }
+    let count = reader.u32().ok()? as usize;
+    let dim = reader.u32().ok()? as usize;
+    if count.checked_mul(dim)?.checked_mul(4)? != reader.remaining() {
+        return None;
+    }
+    let mut rows = Vec::with_capacity(count);
+    for _ in 0..count {
+        let mut row = Vec::with_capacity(dim);
+        for _ in 0..dim {
+            row.push(reader.f32().ok()?);
+        }
+        rows.push(row);
+    }
+    Some(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matrices_roundtrip_and_reject_damage() {
+        let rows = vec![vec![0.5, -1.0, 2.0], vec![0.0, 0.25, -0.75]];
+        let mut bytes = encode(&rows);
+        assert_eq!(decode(&bytes), Some(rows));
+        let mid = bytes.len() / 2;
+        bytes[mid] ^= 0x01;
+        assert_eq!(decode(&bytes), None);
+    }
+}
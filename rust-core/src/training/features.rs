@@ -0,0 +1,667 @@
+//! Feature preprocessing pipeline shared by training and inference.
+//!
+//! Columns are declared in `TrainConfig` (`"features": [...]`), fitted on the
+//! training records and stored in the artefact `PIPE` section. Inference
+//! engines replay the fitted pipeline so both sides build identical vectors.
+//!
+//! ```json
+//! "features": [
+//!   {"name": "amount", "type": "numeric", "impute": "median", "standardize": true},
+//!   {"name": "country", "type": "onehot", "max_categories": 20, "impute": "other"},
+//!   {"name": "merchant", "type": "hashed", "buckets": 32}
+//! ]
+//! ```
+
+use crate::common::error::{DeltaError, DeltaResult};
+use crate::common::ids::SimpleHash;
+use crate::common::json::{self, Value};
+
+use super::artefact::{corrupt, put_f32, put_str16, put_u32, Reader};
+use super::domain::{FeatureKind, FeatureSpec};
+
+const DEFAULT_MAX_CATEGORIES: u32 = 32;
+const DEFAULT_BUCKETS: u32 = 16;
+const OTHER_CATEGORY: &str = "__other__";
+
+/// A record field the pipeline cannot use, e.g. `feature_missing` or
+/// `feature_type_mismatch`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldIssue {
+    pub field: String,
+    pub code: &'static str,
+}
+
+impl FieldIssue {
+    pub fn new(field: impl Into<String>, code: &'static str) -> Self {
+        Self {
+            field: field.into(),
+            code,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"field\":\"{}\",\"error\":\"{}\"}}",
+            json::escape(&self.field),
+            self.code
+        )
+    }
+}
+
+/// How missing values are handled; `None` rejects records without the field.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Impute {
+    #[default]
+    None,
+    Mean,
+    Median,
+    Zero,
+    /// Categorical columns: map missing values to the "other" slot (one-hot) or all zeros (hashed).
+    Other,
+}
+
+impl Impute {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Impute::None => "none",
+            Impute::Mean => "mean",
+            Impute::Median => "median",
+            Impute::Zero => "zero",
+            Impute::Other => "other",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "none" => Some(Impute::None),
+            "mean" => Some(Impute::Mean),
+            "median" => Some(Impute::Median),
+            "zero" => Some(Impute::Zero),
+            "other" => Some(Impute::Other),
+            _ => None,
+        }
+    }
+
+    fn code(&self) -> u8 {
+        match self {
+            Impute::None => 0,
+            Impute::Mean => 1,
+            Impute::Median => 2,
+            Impute::Zero => 3,
+            Impute::Other => 4,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Impute::None),
+            1 => Some(Impute::Mean),
+            2 => Some(Impute::Median),
+            3 => Some(Impute::Zero),
+            4 => Some(Impute::Other),
+            _ => None,
+        }
+    }
+}
+
+/// Transformation requested for a column before fitting.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Transform {
+    Numeric { standardize: bool },
+    OneHot { max_categories: u32 },
+    Hashed { buckets: u32 },
+}
+
+impl Transform {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Transform::Numeric { .. } => "numeric",
+            Transform::OneHot { .. } => "onehot",
+            Transform::Hashed { .. } => "hashed",
+        }
+    }
+
+    fn accepts(&self, impute: Impute) -> bool {
+        match self {
+            Transform::Numeric { .. } => impute != Impute::Other,
+            Transform::OneHot { .. } | Transform::Hashed { .. } => {
+                matches!(impute, Impute::None | Impute::Other)
+            }
+        }
+    }
+}
+
+/// Column declaration from `TrainConfig`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnSpec {
+    pub name: String,
+    pub transform: Transform,
+    pub impute: Impute,
+}
+
+impl ColumnSpec {
+    /// Parse one entry of the `"features"` array.
+    pub fn from_json(value: &Value) -> DeltaResult<Self> {
+        let invalid = || DeltaError::invalid("train_cfg_features_invalid");
+        let name = value
+            .get("name")
+            .and_then(Value::as_str)
+            .filter(|name| !name.is_empty())
+            .ok_or_else(invalid)?
+            .to_string();
+        let count = |key: &str, default: u32| -> DeltaResult<u32> {
+            match value.get(key) {
+                None => Ok(default),
+                Some(raw) => raw
+                    .as_f64()
+                    .filter(|n| n.fract() == 0.0 && *n >= 1.0 && *n <= 65_536.0)
+                    .map(|n| n as u32)
+                    .ok_or_else(invalid),
+            }
+        };
+
+        let transform = match value.get("type").and_then(Value::as_str) {
+            Some("numeric") => Transform::Numeric {
+                standardize: match value.get("standardize") {
+                    None => true,
+                    Some(flag) => flag.as_bool().ok_or_else(invalid)?,
+                },
+            },
+            Some("onehot") => Transform::OneHot {
+                max_categories: count("max_categories", DEFAULT_MAX_CATEGORIES)?,
+            },
+            Some("hashed") => Transform::Hashed {
+                buckets: count("buckets", DEFAULT_BUCKETS)?,
+            },
+            _ => return Err(invalid()),
+        };
+        let impute = match value.get("impute") {
+            None => Impute::None,
+            Some(raw) => raw
+                .as_str()
+                .and_then(Impute::from_label)
+                .ok_or_else(invalid)?,
+        };
+        if !transform.accepts(impute) {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            name,
+            transform,
+            impute,
+        })
+    }
+}
+
+/// Derive column specs from the first record when the config declares none:
+/// numbers and booleans become standardised numerics, strings one-hot columns.
+pub fn infer_specs(records: &[Value], exclude: &[&str]) -> Vec<ColumnSpec> {
+    let members = match records.first().and_then(Value::as_object) {
+        Some(members) => members,
+        None => return Vec::new(),
+    };
+    members
+        .iter()
+        .filter(|(name, _)| !exclude.contains(&name.as_str()))
+        .filter_map(|(name, value)| {
+            let (transform, impute) = match value {
+                Value::Number(_) | Value::Bool(_) => {
+                    (Transform::Numeric { standardize: true }, Impute::Mean)
+                }
+                Value::String(_) => (
+                    Transform::OneHot {
+                        max_categories: DEFAULT_MAX_CATEGORIES,
+                    },
+                    Impute::Other,
+                ),
+                _ => return None,
+            };
+            Some(ColumnSpec {
+                name: name.clone(),
+                transform,
+                impute,
+            })
+        })
+        .collect()
+}
+
+/// Fitted state of a single column.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FittedTransform {
+    /// Output `(value - mean) / scale`; `fill` replaces missing values when imputing.
+    Numeric {
+        fill: f32,
+        mean: f32,
+        scale: f32,
+    },
+    /// One slot per category followed by a trailing "other" slot.
+    OneHot {
+        categories: Vec<String>,
+    },
+    Hashed {
+        buckets: u32,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FittedColumn {
+    pub name: String,
+    pub impute: Impute,
+    pub transform: FittedTransform,
+}
+
+/// Ordered list of fitted columns; the output vector concatenates their slots.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FeaturePipeline {
+    pub columns: Vec<FittedColumn>,
+}
+
+impl FeaturePipeline {
+    /// Fit every column on the training records.
+    pub fn fit(specs: &[ColumnSpec], records: &[Value]) -> DeltaResult<Self> {
+        if records.is_empty() {
+            return Err(DeltaError::invalid("dataset_empty"));
+        }
+        if specs.is_empty() {
+            return Err(DeltaError::invalid("features_empty"));
+        }
+
+        let mut columns = Vec::with_capacity(specs.len());
+        for spec in specs {
+            let transform = match spec.transform {
+                Transform::Numeric { standardize } => fit_numeric(spec, records, standardize)?,
+                Transform::OneHot { max_categories } => fit_onehot(spec, records, max_categories)?,
+                Transform::Hashed { buckets } => {
+                    for record in records {
+                        categorical_value(spec.name.as_str(), spec.impute, record)?;
+                    }
+                    FittedTransform::Hashed { buckets }
+                }
+            };
+            columns.push(FittedColumn {
+                name: spec.name.clone(),
+                impute: spec.impute,
+                transform,
+            });
+        }
+        Ok(Self { columns })
+    }
+
+    /// Number of values produced by [`FeaturePipeline::transform`].
+    pub fn width(&self) -> usize {
+        self.columns.iter().map(column_width).sum()
+    }
+
+    /// Human readable name of every output slot, used for saliency and model cards.
+    pub fn output_names(&self) -> Vec<String> {
+        let mut names = Vec::with_capacity(self.width());
+        for column in &self.columns {
+            match &column.transform {
+                FittedTransform::Numeric { .. } => names.push(column.name.clone()),
+                FittedTransform::OneHot { categories } => {
+                    for category in categories {
+                        names.push(format!("{}={}", column.name, category));
+                    }
+                    names.push(format!("{}={}", column.name, OTHER_CATEGORY));
+                }
+                FittedTransform::Hashed { buckets } => {
+                    for bucket in 0..*buckets {
+                        names.push(format!("{}#{}", column.name, bucket));
+                    }
+                }
+            }
+        }
+        names
+    }
+
+    /// Input feature manifest stored in the artefact `FEAT` section.
+    pub fn manifest(&self) -> Vec<FeatureSpec> {
+        self.columns
+            .iter()
+            .map(|column| {
+                let kind = match column.transform {
+                    FittedTransform::Numeric { .. } => FeatureKind::Numeric,
+                    _ => FeatureKind::Categorical,
+                };
+                FeatureSpec::new(column.name.clone(), kind)
+            })
+            .collect()
+    }
+
+    /// Raw pass-through pipeline for artefacts that only carry a `FEAT`
+    /// manifest: every feature must be numeric and is used as given.
+    pub fn from_manifest(manifest: &[FeatureSpec]) -> DeltaResult<Self> {
+        if manifest.is_empty() {
+            return Err(corrupt("artefact_manifest_unsupported"));
+        }
+        let columns = manifest
+            .iter()
+            .map(|spec| match spec.kind {
+                FeatureKind::Numeric => Ok(FittedColumn {
+                    name: spec.name.clone(),
+                    impute: Impute::None,
+                    transform: FittedTransform::Numeric {
+                        fill: 0.0,
+                        mean: 0.0,
+                        scale: 1.0,
+                    },
+                }),
+                _ => Err(corrupt("artefact_manifest_unsupported")),
+            })
+            .collect::<DeltaResult<Vec<_>>>()?;
+        Ok(Self { columns })
+    }
+
+    /// Turn a JSON record into the model input vector.
+    pub fn transform(&self, record: &Value) -> DeltaResult<Vec<f32>> {
+        self.transform_checked(record)
+            .map_err(|issues| DeltaError::invalid(issues[0].code))
+    }
+
+    /// Like [`FeaturePipeline::transform`], but reports every unusable field
+    /// instead of stopping at the first.
+    pub fn transform_checked(&self, record: &Value) -> Result<Vec<f32>, Vec<FieldIssue>> {
+        let mut out = Vec::with_capacity(self.width());
+        let mut issues = Vec::new();
+        for column in &self.columns {
+            let issue = |err: DeltaError| FieldIssue::new(column.name.as_str(), err.msg);
+            match &column.transform {
+                FittedTransform::Numeric { fill, mean, scale } => {
+                    let value = match numeric_value(&column.name, column.impute, record) {
+                        Ok(Some(value)) => value as f32,
+                        Ok(None) => *fill,
+                        Err(err) => {
+                            issues.push(issue(err));
+                            *fill
+                        }
+                    };
+                    out.push((value - mean) / scale);
+                }
+                FittedTransform::OneHot { categories } => {
+                    let start = out.len();
+                    out.resize(start + categories.len() + 1, 0.0);
+                    let value = categorical_value(&column.name, column.impute, record)
+                        .unwrap_or_else(|err| {
+                            issues.push(issue(err));
+                            None
+                        });
+                    let slot = value
+                        .and_then(|value| categories.iter().position(|c| *c == value))
+                        .unwrap_or(categories.len());
+                    out[start + slot] = 1.0;
+                }
+                FittedTransform::Hashed { buckets } => {
+                    let start = out.len();
+                    out.resize(start + *buckets as usize, 0.0);
+                    match categorical_value(&column.name, column.impute, record) {
+                        Ok(Some(value)) => out[start + hash_bucket(&value, *buckets)] = 1.0,
+                        Ok(None) => {}
+                        Err(err) => issues.push(issue(err)),
+                    }
+                }
+            }
+        }
+        if issues.is_empty() {
+            Ok(out)
+        } else {
+            Err(issues)
+        }
+    }
+
+    /// Serialise into the payload of the artefact `PIPE` section.
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        put_u32(out, self.columns.len() as u32);
+        for column in &self.columns {
+            put_str16(out, &column.name);
+            out.push(column.impute.code());
+            match &column.transform {
+                FittedTransform::Numeric { fill, mean, scale } => {
+                    out.push(0);
+                    put_f32(out, *fill);
+                    put_f32(out, *mean);
+                    put_f32(out, *scale);
+                }
+                FittedTransform::OneHot { categories } => {
+                    out.push(1);
+                    put_u32(out, categories.len() as u32);
+                    for category in categories {
+                        put_str16(out, category);
+                    }
+                }
+                FittedTransform::Hashed { buckets } => {
+                    out.push(2);
+                    put_u32(out, *buckets);
+                }
+            }
+        }
+    }
+
+    /// Parse and validate a `PIPE` section payload.
+    pub(crate) fn decode(section: &mut Reader<'_>) -> DeltaResult<Self> {
+        let count = section.u32()? as usize;
+        let mut columns = Vec::with_capacity(count.min(section.remaining()));
+        for _ in 0..count {
+            let name = section.str16()?;
+            let impute = Impute::from_code(section.u8()?)
+                .ok_or_else(|| corrupt("artefact_pipeline_invalid"))?;
+            let transform = match section.u8()? {
+                0 => {
+                    let fill = section.f32()?;
+                    let mean = section.f32()?;
+                    let scale = section.f32()?;
+                    if !(scale.is_finite() && scale > 0.0) {
+                        return Err(corrupt("artefact_pipeline_invalid"));
+                    }
+                    FittedTransform::Numeric { fill, mean, scale }
+                }
+                1 => {
+                    let n = section.u32()? as usize;
+                    let mut categories = Vec::with_capacity(n.min(section.remaining()));
+                    for _ in 0..n {
+                        categories.push(section.str16()?);
+                    }
+                    FittedTransform::OneHot { categories }
+                }
+                2 => {
+                    let buckets = section.u32()?;
+                    if buckets == 0 {
+                        return Err(corrupt("artefact_pipeline_invalid"));
+                    }
+                    FittedTransform::Hashed { buckets }
+                }
+                _ => return Err(corrupt("artefact_pipeline_invalid")),
+            };
+            columns.push(FittedColumn {
+                name,
+                impute,
+                transform,
+            });
+        }
+        Ok(Self { columns })
+    }
+}
+
+fn column_width(column: &FittedColumn) -> usize {
+    match &column.transform {
+        FittedTransform::Numeric { .. } => 1,
+        FittedTransform::OneHot { categories } => categories.len() + 1,
+        FittedTransform::Hashed { buckets } => *buckets as usize,
+    }
+}
+
+fn fit_numeric(
+    spec: &ColumnSpec,
+    records: &[Value],
+    standardize: bool,
+) -> DeltaResult<FittedTransform> {
+    let mut values = Vec::with_capacity(records.len());
+    for record in records {
+        if let Some(value) = numeric_value(&spec.name, spec.impute, record)? {
+            values.push(value);
+        }
+    }
+
+    let mean = if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    };
+    let fill = match spec.impute {
+        Impute::Mean => mean,
+        Impute::Median => median(&mut values),
+        _ => 0.0,
+    };
+
+    let (mean, scale) = if standardize && !values.is_empty() {
+        let var = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / values.len() as f64;
+        let std = var.sqrt();
+        (mean, if std > 1e-12 { std } else { 1.0 })
+    } else {
+        (0.0, 1.0)
+    };
+
+    Ok(FittedTransform::Numeric {
+        fill: fill as f32,
+        mean: mean as f32,
+        scale: scale as f32,
+    })
+}
+
+fn fit_onehot(
+    spec: &ColumnSpec,
+    records: &[Value],
+    max_categories: u32,
+) -> DeltaResult<FittedTransform> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for record in records {
+        if let Some(value) = categorical_value(&spec.name, spec.impute, record)? {
+            match counts.iter_mut().find(|(c, _)| *c == value) {
+                Some(entry) => entry.1 += 1,
+                None => counts.push((value, 1)),
+            }
+        }
+    }
+    // Most frequent first; ties broken by name so the slot order is reproducible.
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts.truncate(max_categories as usize);
+    Ok(FittedTransform::OneHot {
+        categories: counts.into_iter().map(|(c, _)| c).collect(),
+    })
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+/// Numeric field value; `Ok(None)` means missing and allowed by the impute rule.
+fn numeric_value(name: &str, impute: Impute, record: &Value) -> DeltaResult<Option<f64>> {
+    match record.get(name) {
+        Some(Value::Number(n)) => Ok(Some(*n)),
+        Some(Value::Bool(b)) => Ok(Some(if *b { 1.0 } else { 0.0 })),
+        None | Some(Value::Null) if impute != Impute::None => Ok(None),
+        None | Some(Value::Null) => Err(DeltaError::invalid("feature_missing")),
+        Some(_) => Err(DeltaError::invalid("feature_type_mismatch")),
+    }
+}
+
+/// Categorical field value rendered as a string; `Ok(None)` means missing and allowed.
+fn categorical_value(name: &str, impute: Impute, record: &Value) -> DeltaResult<Option<String>> {
+    match record.get(name) {
+        Some(Value::String(s)) => Ok(Some(s.clone())),
+        Some(Value::Number(n)) if n.fract() == 0.0 && n.abs() < 1e15 => {
+            Ok(Some(format!("{}", *n as i64)))
+        }
+        Some(Value::Number(n)) => Ok(Some(n.to_string())),
+        Some(Value::Bool(b)) => Ok(Some(b.to_string())),
+        None | Some(Value::Null) if impute != Impute::None => Ok(None),
+        None | Some(Value::Null) => Err(DeltaError::invalid("feature_missing")),
+        Some(_) => Err(DeltaError::invalid("feature_type_mismatch")),
+    }
+}
+
+fn hash_bucket(value: &str, buckets: u32) -> usize {
+    let mut hasher = SimpleHash::new();
+    hasher.update(value.as_bytes());
+    (hasher.finish32() % buckets) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::json;
+
+    fn records() -> Vec<Value> {
+        [
+            r#"{"amount":10,"country":"NL","merchant":"a"}"#,
+            r#"{"amount":30,"country":"NL","merchant":"b"}"#,
+            r#"{"amount":null,"country":"BE","merchant":"c"}"#,
+        ]
+        .iter()
+        .map(|line| json::parse(line).unwrap())
+        .collect()
+    }
+
+    fn specs() -> Vec<ColumnSpec> {
+        let cfg = json::parse(
+            r#"[{"name":"amount","type":"numeric","impute":"median"},
+                {"name":"country","type":"onehot","impute":"other"},
+                {"name":"merchant","type":"hashed","buckets":4}]"#,
+        )
+        .unwrap();
+        cfg.as_array()
+            .unwrap()
+            .iter()
+            .map(|v| ColumnSpec::from_json(v).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn fit_and_transform_are_consistent() {
+        let pipeline = FeaturePipeline::fit(&specs(), &records()).unwrap();
+        assert_eq!(pipeline.width(), 1 + 3 + 4);
+        assert_eq!(pipeline.output_names()[1], "country=NL");
+
+        let row = json::parse(r#"{"country":"DE","merchant":"a"}"#).unwrap();
+        let vector = pipeline.transform(&row).unwrap();
+        // Median imputation of 10/30 is the mean, so the standardised value is 0.
+        assert_eq!(vector[0], 0.0);
+        assert_eq!(&vector[1..4], &[0.0, 0.0, 1.0]);
+        assert_eq!(vector[4..].iter().sum::<f32>(), 1.0);
+    }
+
+    #[test]
+    fn encoding_roundtrips() {
+        let pipeline = FeaturePipeline::fit(&specs(), &records()).unwrap();
+        let mut bytes = Vec::new();
+        pipeline.encode(&mut bytes);
+        let mut reader = Reader::new(&bytes);
+        assert_eq!(FeaturePipeline::decode(&mut reader).unwrap(), pipeline);
+    }
+
+    #[test]
+    fn strict_columns_reject_missing_and_mistyped_values() {
+        let spec = ColumnSpec {
+            name: "amount".to_string(),
+            transform: Transform::Numeric { standardize: false },
+            impute: Impute::None,
+        };
+        let err = FeaturePipeline::fit(std::slice::from_ref(&spec), &records()).unwrap_err();
+        assert_eq!(err.msg, "feature_missing");
+
+        let pipeline = FeaturePipeline::fit(&[spec], &records()[..2]).unwrap();
+        let err = pipeline
+            .transform(&json::parse(r#"{"amount":"ten"}"#).unwrap())
+            .unwrap_err();
+        assert_eq!(err.msg, "feature_type_mismatch");
+    }
+}
@@ -0,0 +1,533 @@
+//! Background training jobs with progress polling and cancellation.
+//!
+//! `start` runs [`super::service::train_with_control`] on its own thread and
+//! returns a job ID immediately, so the PHP request does not block for the
+//! whole run. The UI polls [`status`] and may [`cancel`] at any time; a
+//! cancelled job stops at the next epoch boundary without persisting anything.
+//!
+//! Job state lives under `${DATA_ROOT}/jobs/{job_id}/`, not in the process:
+//! `status.json` is rewritten as the run progresses and `cancel` is a marker
+//! any process may create. Under PHP-FPM the poll or cancel request can land
+//! on another worker than the one training; the running job checks the
+//! marker after every progress event. A running job whose process is gone is
+//! reported as failed with `train_job_lost`.
+
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+
+use crate::common::config::AppCfg;
+use crate::common::error::{DeltaCode, DeltaError, DeltaResult};
+use crate::common::ids::SimpleHash;
+use crate::common::json::{self, Value};
+use crate::common::log;
+use crate::common::time;
+use crate::data::domain::DatasetId;
+
+use super::domain::{
+    ModelId, ProgressEvent, TrainConfig, TrainControl, VersionName, TRAINING_CANCELLED,
+};
+use super::repo_fs::write_atomic;
+use super::service;
+
+/// Finished jobs kept for polling before the oldest are forgotten.
+const MAX_FINISHED_JOBS: usize = 64;
+const STATUS_FILE: &str = "status.json";
+const CANCEL_MARKER: &str = "cancel";
+/// Progress is written to disk at most this often; state changes always are.
+const SYNC_INTERVAL_MS: u128 = 200;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum JobState {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobState::Running => "running",
+            JobState::Succeeded => "succeeded",
+            JobState::Failed => "failed",
+            JobState::Cancelled => "cancelled",
+        }
+    }
+
+    fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "running" => Some(JobState::Running),
+            "succeeded" => Some(JobState::Succeeded),
+            "failed" => Some(JobState::Failed),
+            "cancelled" => Some(JobState::Cancelled),
+            _ => None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        *self != JobState::Running
+    }
+}
+
+/// Error of a failed job, as read back from its status file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobError {
+    pub code: u32,
+    pub msg: String,
+}
+
+impl From<DeltaError> for JobError {
+    fn from(err: DeltaError) -> Self {
+        Self {
+            code: err.code as u32,
+            msg: err.msg.to_string(),
+        }
+    }
+}
+
+/// Snapshot of a job as returned to pollers.
+#[derive(Clone, Debug)]
+pub struct JobStatus {
+    pub id: String,
+    pub state: JobState,
+    pub started_ms: u128,
+    pub progress: Option<ProgressEvent>,
+    pub model: Option<(ModelId, VersionName)>,
+    pub error: Option<JobError>,
+}
+
+impl JobStatus {
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"ok\":true,\"job_id\":\"{}\",\"state\":\"{}\",\"progress\":{},\"model_id\":{},\"version\":{},\"error\":{}}}",
+            json::escape(&self.id),
+            self.state.as_str(),
+            self.progress_json(),
+            self.model_json(|(id, _)| id.as_str()),
+            self.model_json(|(_, version)| version.as_str()),
+            self.error_json()
+        )
+    }
+
+    /// Status file contents; `pid` is the process running the job.
+    fn to_record(&self, pid: u32) -> String {
+        format!(
+            "{{\"job_id\":\"{}\",\"state\":\"{}\",\"started_ms\":{},\"pid\":{},\"progress\":{},\"model_id\":{},\"version\":{},\"error\":{}}}",
+            json::escape(&self.id),
+            self.state.as_str(),
+            self.started_ms,
+            pid,
+            self.progress_json(),
+            self.model_json(|(id, _)| id.as_str()),
+            self.model_json(|(_, version)| version.as_str()),
+            self.error_json()
+        )
+    }
+
+    /// Parse a status file into the status and the pid that wrote it.
+    fn from_record(root: &Value) -> DeltaResult<(Self, u32)> {
+        let invalid = || DeltaError::internal("train_job_corrupt");
+        let text = |key: &str| root.get(key).and_then(Value::as_str);
+        let number = |value: Option<&Value>, key: &str| {
+            value
+                .and_then(|value| value.get(key))
+                .and_then(Value::as_f64)
+        };
+        let progress = match root.get("progress") {
+            None | Some(Value::Null) => None,
+            Some(progress) => Some(ProgressEvent {
+                phase: match progress.get("phase").and_then(Value::as_str) {
+                    Some("embed") => "embed",
+                    Some("cv") => "cv",
+                    _ => "fit",
+                },
+                epoch: number(Some(progress), "epoch").unwrap_or_default() as u32,
+                total_epochs: number(Some(progress), "total_epochs").unwrap_or_default() as u32,
+                loss: number(Some(progress), "loss").unwrap_or_default(),
+                elapsed_ms: number(Some(progress), "elapsed_ms").unwrap_or_default() as u128,
+            }),
+        };
+        let model = match (text("model_id"), text("version")) {
+            (Some(id), Some(version)) => Some((ModelId::new(id), VersionName::new(version))),
+            _ => None,
+        };
+        let error = root.get("error").filter(|error| !error.is_null());
+        let status = Self {
+            id: text("job_id").ok_or_else(invalid)?.to_string(),
+            state: text("state")
+                .and_then(JobState::from_label)
+                .ok_or_else(invalid)?,
+            started_ms: number(Some(root), "started_ms").unwrap_or_default() as u128,
+            progress,
+            model,
+            error: error.map(|error| JobError {
+                code: number(Some(error), "code").unwrap_or(DeltaCode::Internal as u32 as f64)
+                    as u32,
+                msg: error
+                    .get("msg")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+            }),
+        };
+        let pid = number(Some(root), "pid").unwrap_or_default() as u32;
+        Ok((status, pid))
+    }
+
+    fn progress_json(&self) -> String {
+        self.progress
+            .as_ref()
+            .map(|p| {
+                format!(
+                    "{{\"phase\":\"{}\",\"epoch\":{},\"total_epochs\":{},\"loss\":{:.6},\"elapsed_ms\":{}}}",
+                    p.phase, p.epoch, p.total_epochs, p.loss, p.elapsed_ms
+                )
+            })
+            .unwrap_or_else(|| "null".to_string())
+    }
+
+    fn model_json(&self, pick: impl Fn(&(ModelId, VersionName)) -> &str) -> String {
+        self.model
+            .as_ref()
+            .map(|model| format!("\"{}\"", json::escape(pick(model))))
+            .unwrap_or_else(|| "null".to_string())
+    }
+
+    fn error_json(&self) -> String {
+        self.error
+            .as_ref()
+            .map(|err| {
+                format!(
+                    "{{\"code\":{},\"msg\":\"{}\"}}",
+                    err.code,
+                    json::escape(&err.msg)
+                )
+            })
+            .unwrap_or_else(|| "null".to_string())
+    }
+}
+
+/// The running side of a job: its status, mirrored to disk.
+struct Job {
+    dir: PathBuf,
+    status: Mutex<JobStatus>,
+    cancel: Arc<AtomicBool>,
+    synced_ms: Mutex<u128>,
+}
+
+impl Job {
+    fn progress(&self, event: &ProgressEvent) {
+        if let Ok(mut status) = self.status.lock() {
+            status.progress = Some(event.clone());
+        }
+        if self.dir.join(CANCEL_MARKER).exists() {
+            self.cancel.store(true, Ordering::SeqCst);
+        }
+        let now = time::now_ms();
+        let due = self
+            .synced_ms
+            .lock()
+            .map(|mut synced| {
+                let due = now.saturating_sub(*synced) >= SYNC_INTERVAL_MS;
+                if due {
+                    *synced = now;
+                }
+                due
+            })
+            .unwrap_or(false);
+        if due {
+            let _ = self.persist();
+        }
+    }
+
+    fn finish(&self, result: DeltaResult<super::domain::ModelVersion>) -> JobState {
+        let Ok(mut status) = self.status.lock() else {
+            return JobState::Failed;
+        };
+        match result {
+            Ok(model) => {
+                status.state = JobState::Succeeded;
+                status.model = Some((model.id, model.version));
+            }
+            // Only the trainer's own cancellation counts; any other failure
+            // is reported as such even when a cancel was requested.
+            Err(err) if err.msg == TRAINING_CANCELLED => status.state = JobState::Cancelled,
+            Err(err) => {
+                status.state = JobState::Failed;
+                status.error = Some(err.into());
+            }
+        }
+        let state = status.state;
+        drop(status);
+        if self.persist().is_err() {
+            log::log_json("error", "training", "train_job_persist", 0, 0);
+        }
+        state
+    }
+
+    fn persist(&self) -> DeltaResult<()> {
+        let record = self
+            .status
+            .lock()
+            .map_err(|_| DeltaError::internal("train_jobs_poisoned"))?
+            .to_record(std::process::id());
+        write_atomic(&self.dir.join(STATUS_FILE), record.as_bytes())
+    }
+}
+
+fn jobs_root() -> PathBuf {
+    Path::new(&AppCfg::load().data_root).join("jobs")
+}
+
+/// Directory of `job_id`; IDs that could not have come from `start` are unknown.
+fn job_dir(job_id: &str) -> DeltaResult<PathBuf> {
+    let valid = job_id
+        .strip_prefix("job-")
+        .is_some_and(|hex| !hex.is_empty() && hex.chars().all(|c| c.is_ascii_alphanumeric()));
+    if !valid {
+        return Err(DeltaError::model_missing("train_job"));
+    }
+    Ok(jobs_root().join(job_id))
+}
+
+/// Validate the config and start training in the background.
+pub fn start(dataset: DatasetId, cfg_json: &str) -> DeltaResult<String> {
+    // Fail fast on malformed configs instead of surfacing them through polling.
+    TrainConfig::parse(cfg_json.to_string())?;
+
+    let started_ms = time::now_ms();
+    let id = make_job_id(&dataset, cfg_json, started_ms);
+    let dir = job_dir(&id)?;
+    prune_finished();
+    fs::create_dir_all(&dir).map_err(|_| DeltaError::io())?;
+
+    let job_slot = Arc::new(OnceLock::<Arc<Job>>::new());
+    let sink_job = Arc::clone(&job_slot);
+    let control = TrainControl::with_sink(move |event| {
+        if let Some(job) = sink_job.get() {
+            job.progress(event);
+        }
+    });
+    let job = Arc::new(Job {
+        dir: dir.clone(),
+        status: Mutex::new(JobStatus {
+            id: id.clone(),
+            state: JobState::Running,
+            started_ms,
+            progress: None,
+            model: None,
+            error: None,
+        }),
+        cancel: control.cancel_flag(),
+        synced_ms: Mutex::new(started_ms),
+    });
+    let _ = job_slot.set(Arc::clone(&job));
+    job.persist()?;
+
+    let cfg = cfg_json.to_string();
+    let spawned = thread::Builder::new()
+        .name(format!("delta1-{id}"))
+        .spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                service::train_with_control(dataset, &cfg, &control)
+            }))
+            .unwrap_or_else(|_| Err(DeltaError::internal("train_job_panicked")));
+            let state = job.finish(result);
+            log::log_json(
+                "info",
+                "training",
+                state.as_str(),
+                0,
+                time::now_ms().saturating_sub(started_ms),
+            );
+        });
+
+    if spawned.is_err() {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(DeltaError::internal("train_job_spawn"));
+    }
+    Ok(id)
+}
+
+/// Current state and latest progress event of a job, from any process.
+pub fn status(job_id: &str) -> DeltaResult<JobStatus> {
+    let raw = match fs::read_to_string(job_dir(job_id)?.join(STATUS_FILE)) {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Err(DeltaError::model_missing("train_job"))
+        }
+        Err(_) => return Err(DeltaError::io()),
+    };
+    let root = json::parse(&raw).map_err(|_| DeltaError::internal("train_job_corrupt"))?;
+    let (mut status, pid) = JobStatus::from_record(&root)?;
+    if status.state == JobState::Running && !process_alive(pid) {
+        status.state = JobState::Failed;
+        status.error = Some(DeltaError::internal("train_job_lost").into());
+    }
+    Ok(status)
+}
+
+/// Request cancellation; a no-op for jobs that already finished.
+pub fn cancel(job_id: &str) -> DeltaResult<()> {
+    if status(job_id)?.state.is_finished() {
+        return Ok(());
+    }
+    fs::write(job_dir(job_id)?.join(CANCEL_MARKER), b"").map_err(|_| DeltaError::io())
+}
+
+/// Whether `pid` still runs; assumed alive where `/proc` is unavailable.
+fn process_alive(pid: u32) -> bool {
+    let proc = Path::new("/proc");
+    pid == std::process::id() || !proc.is_dir() || proc.join(pid.to_string()).exists()
+}
+
+fn prune_finished() {
+    let Ok(entries) = fs::read_dir(jobs_root()) else {
+        return;
+    };
+    let mut finished = entries
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().into_string().ok()?;
+            let status = status(&name).ok()?;
+            status
+                .state
+                .is_finished()
+                .then_some((status.started_ms, name))
+        })
+        .collect::<Vec<_>>();
+    if finished.len() < MAX_FINISHED_JOBS {
+        return;
+    }
+    finished.sort();
+    for (_, id) in finished.iter().take(finished.len() + 1 - MAX_FINISHED_JOBS) {
+        if let Ok(dir) = job_dir(id) {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
+fn make_job_id(dataset: &DatasetId, cfg_json: &str, started_ms: u128) -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let seq = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    let mut hasher = SimpleHash::new();
+    hasher.update(dataset.as_str().as_bytes());
+    hasher.update(cfg_json.as_bytes());
+    hasher.update(&started_ms.to_le_bytes());
+    hasher.update(&std::process::id().to_le_bytes());
+    hasher.update(&seq.to_le_bytes());
+    format!("job-{}", hasher.finish_hex())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn wait_until_finished(id: &str) -> JobStatus {
+        for _ in 0..500 {
+            let status = status(id).unwrap();
+            if status.state.is_finished() {
+                return status;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("job {id} did not finish");
+    }
+
+    #[test]
+    fn job_reports_progress_and_result() {
+        super::service::reset_registry();
+        let dataset = crate::data::service::ingest_fixture(
+            "jobs-ok",
+            &["{\"x\":0,\"label\":0}", "{\"x\":1,\"label\":1}"],
+        );
+        let cfg = "{\"dp\":{\"enabled\":false},\"fairness\":{\"delta_tpr\":0.01,\"delta_fpr\":0.01,\"delta_ppv\":0.01},\"params\":{\"epochs\":20}}";
+        let id = start(dataset, cfg).unwrap();
+
+        let done = wait_until_finished(&id);
+        assert_eq!(done.state, JobState::Succeeded);
+        let progress = done.progress.clone().unwrap();
+        assert_eq!((progress.epoch, progress.total_epochs), (20, 20));
+        assert!(done.to_json().contains("\"state\":\"succeeded\""));
+        assert!(done.model.is_some());
+    }
+
+    #[test]
+    fn cancelled_jobs_stop_without_a_model() {
+        super::service::reset_registry();
+        // Enough work that the run is still going when the cancel request lands.
+        let lines = (0..256)
+            .map(|i| format!("{{\"x\":{i},\"label\":{}}}", i % 2))
+            .collect::<Vec<_>>();
+        let lines = lines.iter().map(String::as_str).collect::<Vec<_>>();
+        let dataset = crate::data::service::ingest_fixture("jobs-cancel", &lines);
+        let cfg = "{\"dp\":{\"enabled\":false},\"fairness\":{\"delta_tpr\":0.01,\"delta_fpr\":0.01,\"delta_ppv\":0.01},\"params\":{\"epochs\":10000}}";
+        let id = start(dataset, cfg).unwrap();
+        cancel(&id).unwrap();
+
+        let done = wait_until_finished(&id);
+        assert_eq!(done.state, JobState::Cancelled);
+        assert!(done.model.is_none());
+        assert!(done.error.is_none());
+        assert_eq!(status("job-unknown").unwrap_err().msg, "train_job");
+    }
+
+    #[test]
+    fn status_and_cancel_work_from_the_files_alone() {
+        crate::common::config::use_test_data_root();
+        // A job written by another worker that has since been recycled.
+        let id = "job-0000000000lost";
+        let dir = job_dir(id).unwrap();
+        fs::create_dir_all(&dir).unwrap();
+        let running = JobStatus {
+            id: id.to_string(),
+            state: JobState::Running,
+            started_ms: 1,
+            progress: None,
+            model: None,
+            error: None,
+        };
+        fs::write(dir.join(STATUS_FILE), running.to_record(u32::MAX)).unwrap();
+        let lost = status(id).unwrap();
+        assert_eq!(lost.state, JobState::Failed);
+        assert_eq!(lost.error.unwrap().msg, "train_job_lost");
+
+        // A cancel marker from another process stops a running job.
+        let job = Job {
+            dir: dir.clone(),
+            status: Mutex::new(running),
+            cancel: Arc::new(AtomicBool::new(false)),
+            synced_ms: Mutex::new(0),
+        };
+        job.persist().unwrap();
+        cancel(id).unwrap();
+        assert!(dir.join(CANCEL_MARKER).exists());
+        job.progress(&ProgressEvent {
+            phase: "fit",
+            epoch: 3,
+            total_epochs: 10,
+            loss: 0.5,
+            elapsed_ms: 7,
+        });
+        assert!(job.cancel.load(Ordering::SeqCst));
+        assert_eq!(status(id).unwrap().progress.unwrap().epoch, 3);
+
+        // Only the trainer's own cancellation error reads as cancelled.
+        assert_eq!(job.finish(Err(DeltaError::io())), JobState::Failed);
+        assert_eq!(
+            status(id).unwrap().error.unwrap().code,
+            DeltaError::io().code as u32
+        );
+        assert_eq!(
+            job.finish(Err(DeltaError::invalid(TRAINING_CANCELLED))),
+            JobState::Cancelled
+        );
+        assert_eq!(status("../etc").unwrap_err().msg, "train_job");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
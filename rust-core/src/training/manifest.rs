@@ -0,0 +1,194 @@
+//! Run manifests: the inputs that determine a trained artefact.
+//!
+//! Every training run stores `run.json` next to its artefact:
+//!
+//! ```text
+//! {"model_id": "...", "version": "v…", "created_ms": 1700000000000,
+//!  "dataset": "ds-…", "datasets": ["ds-…"], "dataset_hash": "…",
+//!  "config": "<raw train config>", "config_hash": "…",
+//!  "code_version": "0.1.0", "artefact_format": 4, "seed": 42,
+//!  "artefact_hash": "…"}
+//! ```
+//!
+//! `dataset` is the dataset passed to `train`; `datasets` lists every dataset
+//! the final fit read (more than one for warm starts over parent data) and
+//! `dataset_hash` covers their records in that order. `config_hash` is taken
+//! over the canonical form of the config, so whitespace and key order do not
+//! matter. `artefact_hash` is the SHA-256 of the exact `model.bin` bytes;
+//! retraining with the same inputs must reproduce them, see
+//! [`super::service::verify_reproducible`].
+
+use crate::common::error::{DeltaError, DeltaResult};
+use crate::common::ids::SimpleHash;
+use crate::common::json::{self, Value};
+use crate::data::domain::DatasetId;
+
+use super::domain::{ModelId, VersionName};
+
+/// Name of the manifest file stored next to each artefact.
+pub const MANIFEST_ASSET: &str = "run.json";
+
+/// Version of the crate that produced a run.
+pub const CODE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunManifest {
+    pub model_id: ModelId,
+    pub version: VersionName,
+    pub created_ms: u128,
+    pub dataset: DatasetId,
+    pub datasets: Vec<DatasetId>,
+    pub dataset_hash: String,
+    pub config: String,
+    pub config_hash: String,
+    pub code_version: String,
+    pub artefact_format: u16,
+    pub seed: u64,
+    pub artefact_hash: String,
+}
+
+impl RunManifest {
+    pub fn to_json(&self) -> String {
+        let datasets = self
+            .datasets
+            .iter()
+            .map(|id| id.as_str().to_string())
+            .collect::<Vec<_>>();
+        format!(
+            "{{\"model_id\":\"{}\",\"version\":\"{}\",\"created_ms\":{},\"dataset\":\"{}\",\"datasets\":{},\"dataset_hash\":\"{}\",\"config\":\"{}\",\"config_hash\":\"{}\",\"code_version\":\"{}\",\"artefact_format\":{},\"seed\":{},\"artefact_hash\":\"{}\"}}",
+            json::escape(self.model_id.as_str()),
+            json::escape(self.version.as_str()),
+            self.created_ms,
+            json::escape(self.dataset.as_str()),
+            json::build_string_array(&datasets),
+            json::escape(&self.dataset_hash),
+            json::escape(&self.config),
+            json::escape(&self.config_hash),
+            json::escape(&self.code_version),
+            self.artefact_format,
+            self.seed,
+            json::escape(&self.artefact_hash)
+        )
+    }
+
+    pub fn from_json(raw: &str) -> DeltaResult<Self> {
+        let invalid = || DeltaError::invalid("run_manifest_invalid");
+        let root = json::parse(raw).map_err(|_| invalid())?;
+        let text = |key: &str| {
+            root.get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(invalid)
+        };
+        let number = |key: &str| {
+            root.get(key)
+                .and_then(Value::as_f64)
+                .filter(|n| n.fract() == 0.0 && *n >= 0.0)
+                .ok_or_else(invalid)
+        };
+        let datasets = root
+            .get("datasets")
+            .and_then(Value::as_array)
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|id| id.as_str().map(DatasetId::new).ok_or_else(invalid))
+            .collect::<DeltaResult<Vec<_>>>()?;
+        Ok(Self {
+            model_id: ModelId::new(text("model_id")?),
+            version: VersionName::new(text("version")?),
+            created_ms: number("created_ms")? as u128,
+            dataset: DatasetId::new(text("dataset")?),
+            datasets,
+            dataset_hash: text("dataset_hash")?,
+            config: text("config")?,
+            config_hash: text("config_hash")?,
+            code_version: text("code_version")?,
+            artefact_format: number("artefact_format")? as u16,
+            seed: number("seed")? as u64,
+            artefact_hash: text("artefact_hash")?,
+        })
+    }
+}
+
+/// Hash of a train config in canonical form; configs that are not strict
+/// JSON are hashed verbatim.
+pub fn config_hash(raw: &str) -> String {
+    let mut hasher = SimpleHash::new();
+    match json::parse(raw) {
+        Ok(value) => hasher.update(value.canonical().as_bytes()),
+        Err(_) => hasher.update(raw.as_bytes()),
+    }
+    hasher.finish_hex()
+}
+
+/// Combined hash of the per-dataset record hashes, in order.
+pub fn combine_hashes(hashes: &[String]) -> String {
+    let mut hasher = SimpleHash::new();
+    for hash in hashes {
+        hasher.update(hash.as_bytes());
+        hasher.update(&[0]);
+    }
+    hasher.finish_hex()
+}
+
+/// Outcome of [`super::service::verify_reproducible`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReproducibilityReport {
+    pub model_id: ModelId,
+    pub version: VersionName,
+    pub expected_hash: String,
+    pub actual_hash: String,
+    pub dataset_hash_matches: bool,
+    pub config_hash_matches: bool,
+    pub code_version_matches: bool,
+    /// Whether the retrained artefact equals the stored one byte for byte.
+    pub identical: bool,
+}
+
+impl ReproducibilityReport {
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"model_id\":\"{}\",\"version\":\"{}\",\"expected_hash\":\"{}\",\"actual_hash\":\"{}\",\"dataset_hash_matches\":{},\"config_hash_matches\":{},\"code_version_matches\":{},\"identical\":{}}}",
+            json::escape(self.model_id.as_str()),
+            json::escape(self.version.as_str()),
+            json::escape(&self.expected_hash),
+            json::escape(&self.actual_hash),
+            self.dataset_hash_matches,
+            self.config_hash_matches,
+            self.code_version_matches,
+            self.identical
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifests_roundtrip_and_hash_configs_canonically() {
+        let manifest = RunManifest {
+            model_id: ModelId::new("tabular-logreg-abc"),
+            version: VersionName::new("v7"),
+            created_ms: 1_700_000_000_123,
+            dataset: DatasetId::new("ds-b"),
+            datasets: vec![DatasetId::new("ds-a"), DatasetId::new("ds-b")],
+            dataset_hash: "0011aabb".to_string(),
+            config: "{\"params\": {\"epochs\": 5}}".to_string(),
+            config_hash: config_hash("{\"params\": {\"epochs\": 5}}"),
+            code_version: CODE_VERSION.to_string(),
+            artefact_format: crate::training::artefact::FORMAT_VERSION,
+            seed: 42,
+            artefact_hash: "deadbeef".to_string(),
+        };
+        assert_eq!(
+            RunManifest::from_json(&manifest.to_json()).unwrap(),
+            manifest
+        );
+        assert_eq!(
+            config_hash("{\"a\":1, \"b\":{\"c\":true}}"),
+            config_hash("{\"b\":{\"c\":true},\"a\":1.0}")
+        );
+        assert_ne!(config_hash("{\"a\":1}"), config_hash("{\"a\":2}"));
+    }
+}
@@ -3,9 +3,20 @@
 //! TODO: Separate deterministic mock training from pluggable engines.
 //! TODO: Add audit logging for every artefact write once requirements are clear.
 
+pub mod artefact;
+pub mod calibration;
+pub mod config;
 pub mod domain;
+pub mod embeddings;
+pub mod features;
+pub mod jobs;
+pub mod manifest;
 pub mod repo_fs;
+pub mod search;
 pub mod service;
+pub mod signing;
+pub mod tensor;
+pub mod trainers;
 
 pub use domain::{ModelId, ModelVersion, TrainConfig};
 
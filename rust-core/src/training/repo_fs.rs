@@ -1,16 +1,26 @@
 //! Filesystem repository for trained model artefacts.
 //!
-//! TODO: Validate artefact headers and enforce integrity checksums.
+//! Artefacts are written in the versioned format described in
+//! [`super::artefact`] and validated on every read. Files are replaced
+//! atomically (temporary file, sync, rename), so a crash or a concurrent
+//! reader never sees a half-written artefact.
+//!
 //! TODO: Implement retention policies for outdated versions.
 
-use std::fs::{self, OpenOptions};
+use std::fs::{self, File, OpenOptions};
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use crate::common::config::AppCfg;
 use crate::common::error::{DeltaError, DeltaResult};
+use crate::common::json::{self, Value};
+use crate::common::log;
 
-use super::domain::{ModelId, ModelRepo, ModelVersion, VersionName};
+use super::artefact::{self, ModelArtefact};
+use super::domain::{
+    Hyperparams, Lifecycle, ModelId, ModelRepo, ModelStage, ModelVersion, Trial, VersionName,
+};
 
 /// Persist model metadata and artefacts on the local filesystem.
 pub struct FsModelRepo {
@@ -24,43 +34,352 @@ impl FsModelRepo {
         }
     }
 
-    fn ensure_dirs(&self, model: &ModelVersion) -> io::Result<()> {
-        let dir = self
-            .root
-            .join(model.id.as_str())
-            .join(model.version.as_str());
-        fs::create_dir_all(dir)
+    /// Directory of one version. IDs and versions can come from FFI callers,
+    /// so each must be a single path segment that stays under the root.
+    fn version_dir(&self, id: &ModelId, version: &VersionName) -> DeltaResult<PathBuf> {
+        if !is_segment(id.as_str()) {
+            return Err(DeltaError::invalid("model_id_invalid"));
+        }
+        if !is_segment(version.as_str()) {
+            return Err(DeltaError::invalid("model_version_invalid"));
+        }
+        Ok(self.root.join(id.as_str()).join(version.as_str()))
+    }
+
+    fn artefact_path(&self, id: &ModelId, version: &VersionName) -> DeltaResult<PathBuf> {
+        Ok(self.version_dir(id, version)?.join("model.bin"))
+    }
+
+    fn lifecycle_path(&self, id: &ModelId, version: &VersionName) -> DeltaResult<PathBuf> {
+        Ok(self.version_dir(id, version)?.join("lifecycle.log"))
+    }
+
+    fn trials_path(&self, id: &ModelId, version: &VersionName) -> DeltaResult<PathBuf> {
+        Ok(self.version_dir(id, version)?.join("trials.jsonl"))
+    }
+
+    /// Location of an auxiliary file; names are plain file names so they cannot
+    /// escape the version directory or replace the artefact.
+    fn asset_path(&self, id: &ModelId, version: &VersionName, name: &str) -> DeltaResult<PathBuf> {
+        let plain = !name.is_empty()
+            && !name.starts_with('.')
+            && name != "model.bin"
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+        if !plain {
+            return Err(DeltaError::invalid("model_asset_name_invalid"));
+        }
+        Ok(self.version_dir(id, version)?.join(name))
+    }
+
+    fn require_artefact(&self, id: &ModelId, version: &VersionName) -> DeltaResult<()> {
+        if self.artefact_path(id, version)?.exists() {
+            Ok(())
+        } else {
+            Err(DeltaError::model_missing("model_artefact"))
+        }
+    }
+
+    /// Current lifecycle state: the last transition recorded in `lifecycle.log`.
+    pub fn read_lifecycle(&self, id: &ModelId, version: &VersionName) -> DeltaResult<Lifecycle> {
+        let raw = match fs::read_to_string(self.lifecycle_path(id, version)?) {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Lifecycle::default()),
+            Err(_) => return Err(DeltaError::io()),
+        };
+        match raw.lines().rev().find(|line| !line.trim().is_empty()) {
+            Some(line) => parse_lifecycle_line(line),
+            None => Ok(Lifecycle::default()),
+        }
     }
 
-    fn artefact_path(&self, model: &ModelVersion) -> PathBuf {
-        self.root
-            .join(model.id.as_str())
-            .join(model.version.as_str())
-            .join("model.bin")
+    /// Versions of `id` that have an artefact, without reading the artefacts.
+    pub fn version_names(&self, id: &ModelId) -> DeltaResult<Vec<VersionName>> {
+        if !is_segment(id.as_str()) {
+            return Err(DeltaError::invalid("model_id_invalid"));
+        }
+        Ok(Self::subdirectories(&self.root.join(id.as_str()))?
+            .into_iter()
+            .map(VersionName::new)
+            .filter(|version| {
+                self.artefact_path(id, version)
+                    .is_ok_and(|path| path.exists())
+            })
+            .collect())
+    }
+
+    /// Version record of one artefact, read from its header and `META` section
+    /// without decoding the weights. Listing and registry refreshes use this;
+    /// [`ModelRepo::get_model`] still verifies the whole file.
+    pub fn read_version(&self, id: &ModelId, version: &VersionName) -> DeltaResult<ModelVersion> {
+        let mut file = match File::open(self.artefact_path(id, version)?) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(DeltaError::model_missing("model_artefact"))
+            }
+            Err(_) => return Err(DeltaError::io()),
+        };
+        let mut model = artefact::read_version(&mut file)?;
+        if model.id != *id || model.version != *version {
+            return Err(DeltaError::model_missing("artefact_identity_mismatch"));
+        }
+        model.lifecycle = self.read_lifecycle(id, version)?;
+        Ok(model)
+    }
+
+    fn read_artefact_bytes(&self, id: &ModelId, version: &VersionName) -> DeltaResult<Vec<u8>> {
+        match fs::read(self.artefact_path(id, version)?) {
+            Ok(bytes) => Ok(bytes),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                Err(DeltaError::model_missing("model_artefact"))
+            }
+            Err(_) => Err(DeltaError::io()),
+        }
+    }
+
+    fn subdirectories(path: &Path) -> DeltaResult<Vec<String>> {
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(_) => return Err(DeltaError::io()),
+        };
+
+        let mut names = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|_| DeltaError::io())?;
+            if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+                if let Some(name) = entry.file_name().to_str() {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
     }
 }
 
 impl ModelRepo for FsModelRepo {
-    fn put_model(&self, model: &ModelVersion) -> DeltaResult<()> {
-        self.ensure_dirs(model).map_err(|_| DeltaError::io())?;
-        let path = self.artefact_path(model);
+    fn put_model(&self, model: &ModelVersion, artefact: &ModelArtefact) -> DeltaResult<()> {
+        let dir = self.version_dir(&model.id, &model.version)?;
+        fs::create_dir_all(&dir).map_err(|_| DeltaError::io())?;
+        write_atomic(&dir.join("model.bin"), &artefact.encode())
+    }
+
+    fn get_model(&self, id: &ModelId, version: &VersionName) -> DeltaResult<ModelVersion> {
+        let bytes = self.read_artefact_bytes(id, version)?;
+        let mut model = ModelArtefact::decode(&bytes)?.to_version()?;
+        if model.id != *id || model.version != *version {
+            return Err(DeltaError::model_missing("artefact_identity_mismatch"));
+        }
+        model.checksum = artefact::checksum_hex(&bytes);
+        model.lifecycle = self.read_lifecycle(id, version)?;
+        Ok(model)
+    }
+
+    fn get_artefact(&self, id: &ModelId, version: &VersionName) -> DeltaResult<ModelArtefact> {
+        let bytes = self.read_artefact_bytes(id, version)?;
+        ModelArtefact::decode(&bytes)
+    }
+
+    fn get_artefact_bytes(&self, id: &ModelId, version: &VersionName) -> DeltaResult<Vec<u8>> {
+        self.read_artefact_bytes(id, version)
+    }
+
+    fn list_models(&self) -> DeltaResult<Vec<ModelId>> {
+        Ok(Self::subdirectories(&self.root)?
+            .into_iter()
+            .map(ModelId::new)
+            .collect())
+    }
+
+    fn list_versions(&self, id: &ModelId) -> DeltaResult<Vec<ModelVersion>> {
+        let mut versions = Vec::new();
+        for version in self.version_names(id)? {
+            // A single unreadable version must not hide the rest of the history.
+            match self.read_version(id, &version) {
+                Ok(model) => versions.push(model),
+                Err(err) => {
+                    log::log_json("warn", "training", "artefact_skipped", err.code as u32, 0)
+                }
+            }
+        }
+        versions.sort_by(|a, b| {
+            a.created_ms
+                .cmp(&b.created_ms)
+                .then_with(|| a.version.as_str().cmp(b.version.as_str()))
+        });
+        Ok(versions)
+    }
+
+    fn put_lifecycle(
+        &self,
+        id: &ModelId,
+        version: &VersionName,
+        lifecycle: &Lifecycle,
+    ) -> DeltaResult<()> {
+        self.require_artefact(id, version)?;
         let mut file = OpenOptions::new()
             .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&path)
+            .append(true)
+            .open(self.lifecycle_path(id, version)?)
             .map_err(|_| DeltaError::io())?;
 
-        file.write_all(b"DELTA1")
-            .and_then(|_| file.write_all(model.version.as_str().as_bytes()))
-            .map_err(|_| DeltaError::io())?;
-        // TODO: Write deterministic payload bytes once the training engine is ready.
+        writeln!(
+            file,
+            "changed_ms={};stage={};approver={}",
+            lifecycle.changed_ms,
+            lifecycle.stage.as_str(),
+            lifecycle.approver.as_deref().unwrap_or("")
+        )
+        .and_then(|_| file.sync_all())
+        .map_err(|_| DeltaError::io())?;
         Ok(())
     }
 
-    fn get_model(&self, _id: &ModelId, _version: &VersionName) -> DeltaResult<ModelVersion> {
-        Err(DeltaError::not_implemented("FsModelRepo::get_model"))
+    fn put_trials(&self, id: &ModelId, version: &VersionName, trials: &[Trial]) -> DeltaResult<()> {
+        self.require_artefact(id, version)?;
+        let mut body = String::new();
+        for trial in trials {
+            body.push_str(&trial_line(trial));
+            body.push('\n');
+        }
+        write_atomic(&self.trials_path(id, version)?, body.as_bytes())
+    }
+
+    fn get_trials(&self, id: &ModelId, version: &VersionName) -> DeltaResult<Vec<Trial>> {
+        let raw = match fs::read_to_string(self.trials_path(id, version)?) {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(_) => return Err(DeltaError::io()),
+        };
+        raw.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(parse_trial_line)
+            .collect()
+    }
+
+    fn get_asset(&self, id: &ModelId, version: &VersionName, name: &str) -> DeltaResult<Vec<u8>> {
+        match fs::read(self.asset_path(id, version, name)?) {
+            Ok(bytes) => Ok(bytes),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                Err(DeltaError::model_missing("model_asset"))
+            }
+            Err(_) => Err(DeltaError::io()),
+        }
+    }
+
+    fn put_asset(
+        &self,
+        id: &ModelId,
+        version: &VersionName,
+        name: &str,
+        bytes: &[u8],
+    ) -> DeltaResult<()> {
+        let path = self.asset_path(id, version, name)?;
+        self.require_artefact(id, version)?;
+        write_atomic(&path, bytes)
     }
 }
 
-// TODO: Provide utilities to list available model versions in sorted order.
+fn is_segment(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', '\0'])
+}
+
+/// Replace `path` so readers see either the old or the new contents: write a
+/// temporary file in the same directory, sync it and rename it into place.
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> DeltaResult<()> {
+    static SEQ: AtomicU64 = AtomicU64::new(0);
+    let dir = path.parent().ok_or(DeltaError::io())?;
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or(DeltaError::io())?;
+    let tmp = dir.join(format!(
+        ".{name}.{}.{}.tmp",
+        std::process::id(),
+        SEQ.fetch_add(1, Ordering::Relaxed)
+    ));
+    let written = File::create(&tmp)
+        .and_then(|mut file| file.write_all(bytes).and_then(|_| file.sync_all()))
+        .and_then(|_| fs::rename(&tmp, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp);
+        return Err(DeltaError::io());
+    }
+    // Persist the rename itself; not every platform can sync a directory.
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+fn trial_line(trial: &Trial) -> String {
+    let folds = trial
+        .fold_log_loss
+        .iter()
+        .map(|loss| loss.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "{{\"trial\":{},\"params\":{},\"fold_log_loss\":[{}],\"log_loss\":{},\"accuracy\":{}}}",
+        trial.index,
+        trial.params.to_json(),
+        folds,
+        trial.log_loss,
+        trial.accuracy
+    )
+}
+
+fn parse_trial_line(line: &str) -> DeltaResult<Trial> {
+    let corrupt = || DeltaError::internal("trials_corrupt");
+    let value = json::parse(line).map_err(|_| corrupt())?;
+    let number = |key: &str| value.get(key).and_then(Value::as_f64).ok_or_else(corrupt);
+
+    let mut params = Hyperparams::default();
+    for (name, raw) in value
+        .get("params")
+        .and_then(Value::as_object)
+        .ok_or_else(corrupt)?
+    {
+        params.set(name, raw.as_f64().ok_or_else(corrupt)?);
+    }
+    let fold_log_loss = value
+        .get("fold_log_loss")
+        .and_then(Value::as_array)
+        .ok_or_else(corrupt)?
+        .iter()
+        .map(|loss| loss.as_f64().ok_or_else(corrupt))
+        .collect::<DeltaResult<Vec<_>>>()?;
+
+    Ok(Trial {
+        index: number("trial")? as u32,
+        params,
+        fold_log_loss,
+        log_loss: number("log_loss")?,
+        accuracy: number("accuracy")?,
+    })
+}
+
+fn parse_lifecycle_line(line: &str) -> DeltaResult<Lifecycle> {
+    let mut lifecycle = Lifecycle::default();
+    for field in line.trim().split(';') {
+        let (key, value) = field
+            .split_once('=')
+            .ok_or_else(|| DeltaError::internal("lifecycle_corrupt"))?;
+        match key {
+            "changed_ms" => {
+                lifecycle.changed_ms = value
+                    .parse()
+                    .map_err(|_| DeltaError::internal("lifecycle_corrupt"))?
+            }
+            "stage" => {
+                lifecycle.stage = ModelStage::from_label(value)
+                    .ok_or_else(|| DeltaError::internal("lifecycle_corrupt"))?
+            }
+            "approver" if !value.is_empty() => lifecycle.approver = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    Ok(lifecycle)
+}
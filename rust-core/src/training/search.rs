@@ -0,0 +1,417 @@
+//! Deterministic k-fold cross-validation and hyperparameter search.
+//!
+//! The search space is declared in `TrainConfig`:
+//!
+//! ```json
+//! "search": {
+//!   "strategy": "grid",
+//!   "folds": 5,
+//!   "seed": 42,
+//!   "space": {"l2": [0.001, 0.01, 0.1], "max_depth": [2, 3, 4]}
+//! }
+//! ```
+//!
+//! `grid` evaluates every combination of the listed values. `random` draws
+//! `trials` candidates; besides value lists it accepts ranges such as
+//! `{"min": 0.001, "max": 1.0, "log": true}`. Fold assignment and sampling are
+//! driven by `seed`, so the same config and data always yield the same trials.
+
+use crate::common::error::{DeltaError, DeltaResult};
+use crate::common::json::Value;
+
+use super::domain::{
+    DifferentialPrivacy, Hyperparams, ModelKind, SearchSummary, TrainControl, Trial,
+};
+use super::trainers;
+
+const DEFAULT_FOLDS: u32 = 5;
+/// Seed used when a config does not set one.
+pub const DEFAULT_SEED: u64 = 42;
+const DEFAULT_RANDOM_TRIALS: u32 = 10;
+const MAX_TRIALS: usize = 256;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SearchStrategy {
+    Grid,
+    Random,
+}
+
+impl SearchStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchStrategy::Grid => "grid",
+            SearchStrategy::Random => "random",
+        }
+    }
+}
+
+/// Candidate values of one hyperparameter.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamRange {
+    Values(Vec<f64>),
+    /// Uniform (or log-uniform) interval; only valid for random search.
+    Range {
+        min: f64,
+        max: f64,
+        log: bool,
+    },
+}
+
+/// Search declaration parsed from the `"search"` section of `TrainConfig`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchSpec {
+    pub strategy: SearchStrategy,
+    pub folds: u32,
+    pub seed: u64,
+    pub trials: u32,
+    pub space: Vec<(String, ParamRange)>,
+}
+
+impl SearchSpec {
+    pub fn from_json(section: &Value) -> DeltaResult<Self> {
+        let invalid = || DeltaError::invalid("train_cfg_search_invalid");
+        let integer = |key: &str, default: u64, max: u64| -> DeltaResult<u64> {
+            match section.get(key) {
+                None => Ok(default),
+                Some(value) => value
+                    .as_f64()
+                    .filter(|n| n.fract() == 0.0 && *n >= 0.0 && *n <= max as f64)
+                    .map(|n| n as u64)
+                    .ok_or_else(invalid),
+            }
+        };
+
+        let strategy = match section.get("strategy").and_then(Value::as_str) {
+            Some("grid") | None => SearchStrategy::Grid,
+            Some("random") => SearchStrategy::Random,
+            Some(_) => return Err(invalid()),
+        };
+        let folds = integer("folds", DEFAULT_FOLDS as u64, 20)? as u32;
+        let seed = integer("seed", DEFAULT_SEED, u32::MAX as u64)?;
+        let trials = integer("trials", DEFAULT_RANDOM_TRIALS as u64, MAX_TRIALS as u64)? as u32;
+        if folds < 2 || trials == 0 {
+            return Err(invalid());
+        }
+
+        let mut space = Vec::new();
+        for (name, value) in section
+            .get("space")
+            .and_then(Value::as_object)
+            .ok_or_else(invalid)?
+        {
+            if !Hyperparams::NAMES.contains(&name.as_str()) {
+                return Err(invalid());
+            }
+            let range = match value {
+                Value::Array(items) if !items.is_empty() => ParamRange::Values(
+                    items
+                        .iter()
+                        .map(|item| item.as_f64().ok_or_else(invalid))
+                        .collect::<DeltaResult<_>>()?,
+                ),
+                Value::Object(_) if strategy == SearchStrategy::Random => {
+                    let min = value
+                        .get("min")
+                        .and_then(Value::as_f64)
+                        .ok_or_else(invalid)?;
+                    let max = value
+                        .get("max")
+                        .and_then(Value::as_f64)
+                        .ok_or_else(invalid)?;
+                    let log = match value.get("log") {
+                        None => false,
+                        Some(flag) => flag.as_bool().ok_or_else(invalid)?,
+                    };
+                    if min > max || (log && min <= 0.0) {
+                        return Err(invalid());
+                    }
+                    ParamRange::Range { min, max, log }
+                }
+                _ => return Err(invalid()),
+            };
+            space.push((name.clone(), range));
+        }
+        if space.is_empty() {
+            return Err(invalid());
+        }
+
+        let spec = Self {
+            strategy,
+            folds,
+            seed,
+            trials,
+            space,
+        };
+        if spec.strategy == SearchStrategy::Grid && spec.grid_size() > MAX_TRIALS {
+            return Err(invalid());
+        }
+        Ok(spec)
+    }
+
+    fn grid_size(&self) -> usize {
+        self.space
+            .iter()
+            .map(|(_, range)| match range {
+                ParamRange::Values(values) => values.len(),
+                ParamRange::Range { .. } => 1,
+            })
+            .fold(1usize, |acc, n| acc.saturating_mul(n))
+    }
+
+    /// Expand the space into concrete candidates on top of `base`.
+    pub fn candidates(&self, base: &Hyperparams) -> DeltaResult<Vec<Hyperparams>> {
+        let candidates = match self.strategy {
+            SearchStrategy::Grid => {
+                let mut out = vec![base.clone()];
+                for (name, range) in &self.space {
+                    let values = match range {
+                        ParamRange::Values(values) => values,
+                        ParamRange::Range { .. } => {
+                            return Err(DeltaError::invalid("train_cfg_search_invalid"))
+                        }
+                    };
+                    out = out
+                        .into_iter()
+                        .flat_map(|params| {
+                            values.iter().map(move |value| {
+                                let mut next = params.clone();
+                                next.set(name, *value);
+                                next
+                            })
+                        })
+                        .collect();
+                }
+                out
+            }
+            SearchStrategy::Random => {
+                let mut rng = SplitMix64::new(self.seed ^ 0x5eed);
+                (0..self.trials)
+                    .map(|_| {
+                        let mut params = base.clone();
+                        for (name, range) in &self.space {
+                            params.set(name, sample(range, &mut rng));
+                        }
+                        params
+                    })
+                    .collect()
+            }
+        };
+        for params in &candidates {
+            params
+                .validate()
+                .map_err(|_| DeltaError::invalid("train_cfg_search_invalid"))?;
+        }
+        Ok(candidates)
+    }
+}
+
+fn sample(range: &ParamRange, rng: &mut SplitMix64) -> f64 {
+    match range {
+        ParamRange::Values(values) => values[rng.below(values.len())],
+        ParamRange::Range {
+            min,
+            max,
+            log: false,
+        } => min + (max - min) * rng.unit(),
+        ParamRange::Range {
+            min,
+            max,
+            log: true,
+        } => (min.ln() + (max.ln() - min.ln()) * rng.unit()).exp(),
+    }
+}
+
+/// Trials of a finished search together with the winning candidate.
+#[derive(Clone, Debug)]
+pub struct SearchOutcome {
+    pub trials: Vec<Trial>,
+    pub best: usize,
+}
+
+impl SearchOutcome {
+    pub fn best_params(&self) -> &Hyperparams {
+        &self.trials[self.best].params
+    }
+
+    pub fn summary(&self, spec: &SearchSpec) -> SearchSummary {
+        let best = &self.trials[self.best];
+        SearchSummary {
+            strategy: spec.strategy.as_str().to_string(),
+            folds: spec.folds,
+            trials: self.trials.len() as u32,
+            best_trial: best.index,
+            best_log_loss: best.log_loss,
+            best_accuracy: best.accuracy,
+        }
+    }
+}
+
+/// Evaluate every candidate with k-fold cross-validation and pick the lowest mean log loss.
+///
+/// The folds are fitted under the same `dp` settings as the final model.
+pub fn run(
+    kind: ModelKind,
+    base: &Hyperparams,
+    spec: &SearchSpec,
+    x: &[Vec<f32>],
+    y: &[f32],
+    dp: &DifferentialPrivacy,
+    control: &TrainControl,
+) -> DeltaResult<SearchOutcome> {
+    let folds = spec.folds as usize;
+    if x.len() < folds {
+        return Err(DeltaError::invalid("search_folds_exceed_records"));
+    }
+    let assignment = fold_assignment(x.len(), folds, spec.seed);
+
+    let mut trials = Vec::new();
+    for (index, params) in spec.candidates(base)?.into_iter().enumerate() {
+        let mut fold_log_loss = Vec::with_capacity(folds);
+        let mut predictions = vec![0.0f32; x.len()];
+        for fold in 0..folds {
+            let (train_x, train_y): (Vec<Vec<f32>>, Vec<f32>) = assignment
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| **f != fold)
+                .map(|(i, _)| (x[i].clone(), y[i]))
+                .unzip();
+            let fitted =
+                trainers::fit_warm(kind, None, &train_x, &train_y, &params, dp, control, "cv")?;
+
+            let (mut held_y, mut held_p) = (Vec::new(), Vec::new());
+            for (i, _) in assignment.iter().enumerate().filter(|(_, f)| **f == fold) {
+                predictions[i] = fitted.predict(&x[i]);
+                held_y.push(y[i]);
+                held_p.push(predictions[i]);
+            }
+            fold_log_loss.push(trainers::log_loss(&held_y, &held_p));
+        }
+        trials.push(Trial {
+            index: index as u32,
+            log_loss: fold_log_loss.iter().sum::<f64>() / folds as f64,
+            accuracy: trainers::accuracy(y, &predictions),
+            fold_log_loss,
+            params,
+        });
+    }
+
+    // Strictly-better comparison keeps the earliest candidate on ties.
+    let best = trials.iter().enumerate().fold(0, |best, (i, trial)| {
+        if trial.log_loss < trials[best].log_loss {
+            i
+        } else {
+            best
+        }
+    });
+    Ok(SearchOutcome { trials, best })
+}
+
+/// Fold index of every record: a seeded shuffle dealt round-robin over `k` folds.
+pub fn fold_assignment(n: usize, k: usize, seed: u64) -> Vec<usize> {
+    let mut order = (0..n).collect::<Vec<_>>();
+    let mut rng = SplitMix64::new(seed);
+    for i in (1..n).rev() {
+        order.swap(i, rng.below(i + 1));
+    }
+    let mut folds = vec![0; n];
+    for (position, record) in order.into_iter().enumerate() {
+        folds[record] = position % k;
+    }
+    folds
+}
+
+/// Small seeded generator; quality is ample for shuffling, sampling and DP noise.
+pub(crate) struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    pub(crate) fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normal sample (Box-Muller).
+    pub(crate) fn gaussian(&mut self) -> f64 {
+        let u1 = self.unit().max(f64::MIN_POSITIVE);
+        let u2 = self.unit();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::json;
+
+    #[test]
+    fn folds_are_balanced_and_seeded() {
+        let a = fold_assignment(10, 3, 7);
+        assert_eq!(a, fold_assignment(10, 3, 7));
+        assert_ne!(a, fold_assignment(10, 3, 8));
+        for fold in 0..3 {
+            let size = a.iter().filter(|f| **f == fold).count();
+            assert!((3..=4).contains(&size));
+        }
+    }
+
+    #[test]
+    fn grid_search_picks_the_lowest_log_loss() {
+        let spec = SearchSpec::from_json(
+            &json::parse(
+                r#"{"strategy":"grid","folds":4,"space":{"l2":[10.0,0.0],"epochs":[50]}}"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let x = (0..16)
+            .map(|i| vec![(i as f32 - 7.5) / 4.0])
+            .collect::<Vec<_>>();
+        let y = (0..16)
+            .map(|i| if i >= 8 { 1.0 } else { 0.0 })
+            .collect::<Vec<_>>();
+
+        let outcome = run(
+            ModelKind::TabularLogistic,
+            &Hyperparams::default(),
+            &spec,
+            &x,
+            &y,
+            &DifferentialPrivacy::default(),
+            &TrainControl::default(),
+        )
+        .unwrap();
+        assert_eq!(outcome.trials.len(), 2);
+        assert_eq!(outcome.best_params().l2, 0.0);
+        assert_eq!(outcome.best_params().epochs, 50);
+        assert_eq!(outcome.trials[0].fold_log_loss.len(), 4);
+    }
+
+    #[test]
+    fn ranges_require_random_strategy() {
+        let grid = json::parse(r#"{"space":{"l2":{"min":0.001,"max":1}}}"#).unwrap();
+        assert!(SearchSpec::from_json(&grid).is_err());
+
+        let random = json::parse(
+            r#"{"strategy":"random","trials":4,"space":{"l2":{"min":0.001,"max":1,"log":true}}}"#,
+        )
+        .unwrap();
+        let spec = SearchSpec::from_json(&random).unwrap();
+        let candidates = spec.candidates(&Hyperparams::default()).unwrap();
+        assert_eq!(candidates.len(), 4);
+        assert!(candidates.iter().all(|p| (0.001..=1.0).contains(&p.l2)));
+    }
+}
@@ -1,19 +1,44 @@
 //! Service layer orchestrating dataset ingestion and model training.
 //!
 //! Enforces the model design guardrails defined in the product brief: fixed
-//! model families, fairness gates and differential privacy bounds. Metadata is
-//! captured in-memory for now so the PHP layer can export model cards without
-//! a persistent store.
+//! model families, fairness gates and differential privacy bounds. Every
+//! trained version is persisted through the `ModelRepo`; the in-memory
+//! registry is a cache that is rebuilt from `${DATA_ROOT}/models/` on first use.
 
 use std::collections::HashMap;
-use std::sync::{Mutex, OnceLock};
+use std::sync::{Mutex, MutexGuard, OnceLock};
 
-use crate::common::error::{DeltaError, DeltaResult};
+use crate::common::config::AppCfg;
+use crate::common::error::{DeltaCode, DeltaError, DeltaResult};
 use crate::common::ids::SimpleHash;
+use crate::common::json::{self, Value};
+use crate::common::log;
+use crate::common::sha256;
 use crate::common::time;
 use crate::data::domain::DatasetId;
+use crate::data::service as data_service;
+use crate::evaluation;
+use crate::inference::encoder::Encoder;
+use crate::inference::service as inference_service;
+use crate::inference::tokenizer::WordPieceTokenizer;
 
-use super::domain::{ModelId, ModelKind, ModelMetadata, ModelVersion, TrainConfig, VersionName};
+use super::artefact::{self, ModelArtefact};
+use super::calibration::{self, Calibrator};
+use super::config;
+use super::domain::{
+    CalibrationReport, DifferentialPrivacy, EncoderRef, EvalMetrics, FairnessReport,
+    FeatureImportance, FeatureKind, FeatureSpec, Hyperparams, Lifecycle, Lineage, ModelId,
+    ModelKind, ModelMetadata, ModelRepo, ModelStage, ModelVersion, SearchSummary, TrainConfig,
+    TrainControl, Trial, VersionName, WarmStart, DEFAULT_TEXT_COLUMN,
+};
+use super::embeddings::{self, EmbeddingCache};
+use super::features::{self, FeaturePipeline};
+use super::manifest::{self, ReproducibilityReport, RunManifest, CODE_VERSION, MANIFEST_ASSET};
+use super::repo_fs::FsModelRepo;
+use super::search;
+use super::signing::{self, SigningCfg, SIGNATURE_ASSET};
+use super::tensor::Tensor;
+use super::trainers::{self, Fitted};
 
 const MAX_EPSILON: f32 = 3.0;
 const MAX_DELTA: f32 = 1e-5;
@@ -24,19 +49,33 @@ const MAX_DELTA_PPV: f32 = 0.04;
 #[derive(Default)]
 struct ModelRegistry {
     entries: HashMap<(String, String), ModelVersion>,
-    latest: HashMap<String, String>,
 }
 
 impl ModelRegistry {
+    /// Rebuild the cache from every readable artefact in the repository.
+    fn load(repo: &dyn ModelRepo) -> Self {
+        let mut registry = Self::default();
+        let ids = match repo.list_models() {
+            Ok(ids) => ids,
+            Err(err) => {
+                log::log_json("error", "training", "registry_scan", err.code as u32, 0);
+                return registry;
+            }
+        };
+        for id in ids {
+            match repo.list_versions(&id) {
+                Ok(versions) => versions.into_iter().for_each(|v| registry.insert(v)),
+                Err(err) => log::log_json("warn", "training", "registry_scan", err.code as u32, 0),
+            }
+        }
+        registry
+    }
+
     fn insert(&mut self, model: ModelVersion) {
         let key = (
             model.id.as_str().to_string(),
             model.version.as_str().to_string(),
         );
-        self.latest.insert(
-            model.id.as_str().to_string(),
-            model.version.as_str().to_string(),
-        );
         self.entries.insert(key, model);
     }
 
@@ -45,71 +84,880 @@ impl ModelRegistry {
         self.entries.get(&key).cloned()
     }
 
-    fn latest(&self, id: &ModelId) -> Option<ModelVersion> {
-        let version = self.latest.get(id.as_str())?;
-        let key = (id.as_str().to_string(), version.clone());
-        self.entries.get(&key).cloned()
+    /// Most recently promoted `Production` version of a model.
+    fn production(&self, id: &ModelId) -> Option<ModelVersion> {
+        self.entries
+            .values()
+            .filter(|model| model.id == *id && model.lifecycle.stage == ModelStage::Production)
+            .max_by_key(|model| model.lifecycle.changed_ms)
+            .cloned()
+    }
+
+    fn versions(&self, id: &ModelId) -> Vec<ModelVersion> {
+        let mut versions = self
+            .entries
+            .values()
+            .filter(|model| model.id == *id)
+            .cloned()
+            .collect::<Vec<_>>();
+        versions.sort_by(|a, b| {
+            a.created_ms
+                .cmp(&b.created_ms)
+                .then_with(|| a.version.as_str().cmp(b.version.as_str()))
+        });
+        versions
     }
 }
 
+fn lock_registry() -> DeltaResult<MutexGuard<'static, ModelRegistry>> {
+    registry()
+        .lock()
+        .map_err(|_| DeltaError::internal("model_registry_poisoned"))
+}
+
+fn model_repo() -> &'static FsModelRepo {
+    static REPO: OnceLock<FsModelRepo> = OnceLock::new();
+    REPO.get_or_init(|| FsModelRepo::new(&AppCfg::load()))
+}
+
 fn registry() -> &'static Mutex<ModelRegistry> {
     static REGISTRY: OnceLock<Mutex<ModelRegistry>> = OnceLock::new();
-    REGISTRY.get_or_init(|| Mutex::new(ModelRegistry::default()))
+    REGISTRY.get_or_init(|| Mutex::new(ModelRegistry::load(model_repo())))
 }
 
 /// Train a model for the given dataset.
 pub fn train(dataset: DatasetId, cfg_json: &str) -> DeltaResult<ModelVersion> {
+    train_with_control(dataset, cfg_json, &TrainControl::default())
+}
+
+/// Check a train config without training.
+///
+/// Returns `{"valid":true,"config":{…},"errors":[]}` with the normalised,
+/// fully defaulted config, or `{"valid":false,"config":null,"errors":[…]}`
+/// listing every rejected field. Policy gates (DP and fairness bounds) are
+/// applied by `train`, not here.
+pub fn validate_config(cfg_json: &str) -> String {
+    match TrainConfig::check(cfg_json.to_string()) {
+        Ok(cfg) => format!(
+            "{{\"valid\":true,\"config\":{},\"errors\":[]}}",
+            cfg.normalized()
+        ),
+        Err(issues) => format!(
+            "{{\"valid\":false,\"config\":null,\"errors\":{}}}",
+            config::issues_json(&issues)
+        ),
+    }
+}
+
+/// Train with progress reporting and cooperative cancellation.
+///
+/// With `"warm_start"` the run continues from the parent version: it reuses
+/// the parent's feature pipeline and parameters and is stored as a new
+/// version of the parent's model family. Every version gets a run manifest
+/// (see [`super::manifest`]). Nothing is persisted when the run is cancelled.
+pub fn train_with_control(
+    dataset: DatasetId,
+    cfg_json: &str,
+    control: &TrainControl,
+) -> DeltaResult<ModelVersion> {
+    let run = run_training(&dataset, cfg_json, control, None)?;
+    let model = run.model;
+    model_repo().put_model(&model, &run.artefact)?;
+    sign_artefact(&signing_cfg(), &model)?;
+    if let Some(tokenizer_raw) = &run.tokenizer_raw {
+        model_repo().put_asset(&model.id, &model.version, TOKENIZER_ASSET, tokenizer_raw)?;
+    }
+    model_repo().put_asset(
+        &model.id,
+        &model.version,
+        MANIFEST_ASSET,
+        run.manifest.to_json().as_bytes(),
+    )?;
+    model_repo().put_lifecycle(&model.id, &model.version, &model.lifecycle)?;
+    if !run.trials.is_empty() {
+        model_repo().put_trials(&model.id, &model.version, &run.trials)?;
+    }
+
+    lock_registry()?.insert(model.clone());
+
+    Ok(model)
+}
+
+/// A completed training run that has not been persisted yet.
+struct TrainedRun {
+    model: ModelVersion,
+    artefact: ModelArtefact,
+    trials: Vec<Trial>,
+    /// Vocabulary copied from the encoder of a text head.
+    tokenizer_raw: Option<Vec<u8>>,
+    manifest: RunManifest,
+}
+
+/// Fit the configured model and assemble its artefact and run manifest.
+///
+/// `created_ms` pins the version stamp when an earlier run is reproduced;
+/// otherwise the current time is used once fitting finishes.
+fn run_training(
+    dataset: &DatasetId,
+    cfg_json: &str,
+    control: &TrainControl,
+    created_ms: Option<u128>,
+) -> DeltaResult<TrainedRun> {
     let cfg = TrainConfig::parse(cfg_json.to_string())?;
     enforce_dp(&cfg)?;
     enforce_fairness(&cfg)?;
 
-    let model_id = make_model_id(&dataset, cfg_json, cfg.model_kind());
-    let version = VersionName::new(format!("v{}", time::now_ms()));
-    let artefact_path = format!(
-        "models/{}/{}/model.bin",
-        model_id.as_str(),
-        version.as_str()
-    );
+    let parent = match cfg.warm_start() {
+        Some(spec) => Some(WarmParent::load(spec, &cfg)?),
+        None => None,
+    };
+    let datasets = match (&parent, cfg.warm_start()) {
+        (Some(parent), Some(spec)) if spec.include_parent_data => parent.datasets(dataset)?,
+        _ => vec![dataset.clone()],
+    };
+    let mut records = Vec::new();
+    let mut dataset_hashes = Vec::with_capacity(datasets.len());
+    for id in &datasets {
+        records.extend(data_service::load_records(id)?);
+        dataset_hashes.push(data_service::records_hash(id)?);
+    }
+    let text_source = match cfg.encoder() {
+        Some(encoder) => Some(TextSource::load(encoder)?),
+        None => None,
+    };
+    let (pipeline, x) = match &text_source {
+        Some(source) => (None, source.embeddings(dataset, &records, control)?),
+        None => {
+            let pipeline = match (&parent, cfg.features()) {
+                (Some(parent), _) => parent.pipeline.clone(),
+                (None, Some(specs)) => FeaturePipeline::fit(specs, &records)?,
+                (None, None) => {
+                    let specs = features::infer_specs(&records, &[cfg.label(), "context"]);
+                    FeaturePipeline::fit(&specs, &records)?
+                }
+            };
+            let x = records
+                .iter()
+                .map(|record| pipeline.transform(record))
+                .collect::<DeltaResult<Vec<_>>>()?;
+            (Some(pipeline), x)
+        }
+    };
+    let fit = fit_model(
+        &cfg,
+        parent.as_ref().map(|parent| &parent.fitted),
+        &x,
+        &records,
+        control,
+    )?;
+    control.check()?;
+    let importance = match (&pipeline, fit.importance) {
+        (Some(pipeline), Some((method, values))) => Some(FeatureImportance {
+            method: method.to_string(),
+            features: pipeline.output_names().into_iter().zip(values).collect(),
+        }),
+        _ => None,
+    };
+
+    let model_id = match &parent {
+        Some(parent) => parent.model.id.clone(),
+        None => make_model_id(dataset, cfg_json, cfg.model_kind()),
+    };
+    let created_ms = created_ms.unwrap_or_else(time::now_ms);
+    let version = VersionName::new(format!("v{created_ms}"));
+    let artefact_path = artefact::relative_path(&model_id, &version);
 
-    let model = ModelVersion {
+    let mut model = ModelVersion {
         id: model_id,
         version,
         kind: cfg.model_kind(),
         artefact_path,
+        created_ms,
+        lifecycle: Lifecycle {
+            stage: ModelStage::Candidate,
+            approver: None,
+            changed_ms: created_ms,
+        },
         metadata: ModelMetadata {
             dp: cfg.dp().clone(),
             fairness: cfg.fairness().cloned(),
+            hyperparams: fit.params,
+            search: fit.search,
+            calibration: fit.calibration.as_ref().map(|(_, report)| report.clone()),
+            lineage: Lineage {
+                parent: parent
+                    .as_ref()
+                    .map(|parent| (parent.model.id.clone(), parent.model.version.clone())),
+                period: dataset_period(&datasets)?,
+                datasets: datasets.clone(),
+            },
+            metrics: fit.metrics,
+            importance,
+            card: cfg.card().clone(),
         },
+        checksum: None,
     };
 
-    let mut guard = registry()
-        .lock()
-        .map_err(|_| DeltaError::internal("model_registry_poisoned"))?;
-    guard.insert(model.clone());
+    let mut artefact = ModelArtefact::for_version(&model);
+    artefact.calibration = fit.calibration.map(|(calibrator, _)| calibrator);
+    match &text_source {
+        Some(source) => source.attach_head(&mut artefact, &fit.fitted.weights),
+        None => {
+            if let Some(pipeline) = pipeline {
+                artefact.features = pipeline.manifest();
+                artefact.pipeline = Some(pipeline);
+            }
+            artefact.weights = fit.fitted.weights;
+            artefact.trees = fit.fitted.trees;
+        }
+    }
+    let encoded = artefact.encode();
+    model.checksum = artefact::checksum_hex(&encoded);
 
-    Ok(model)
+    let manifest = RunManifest {
+        model_id: model.id.clone(),
+        version: model.version.clone(),
+        created_ms,
+        dataset: dataset.clone(),
+        datasets,
+        dataset_hash: manifest::combine_hashes(&dataset_hashes),
+        config: cfg_json.to_string(),
+        config_hash: manifest::config_hash(cfg_json),
+        code_version: CODE_VERSION.to_string(),
+        artefact_format: artefact::FORMAT_VERSION,
+        seed: cfg.seed(),
+        artefact_hash: sha256::to_hex(&sha256::sha256(&encoded)),
+    };
+
+    Ok(TrainedRun {
+        model,
+        artefact,
+        trials: fit.trials,
+        tokenizer_raw: text_source.map(|source| source.tokenizer_raw),
+        manifest,
+    })
+}
+
+/// Run manifest stored with a version.
+pub fn run_manifest(id: &ModelId, version: &VersionName) -> DeltaResult<RunManifest> {
+    let model = load_model(id, Some(version))?;
+    let raw = load_asset(&model, MANIFEST_ASSET)?;
+    std::str::from_utf8(&raw)
+        .map_err(|_| DeltaError::invalid("run_manifest_invalid"))
+        .and_then(RunManifest::from_json)
+}
+
+/// Retrain a version from its run manifest and compare the artefacts byte for byte.
+///
+/// Nothing is persisted; the retrained run reuses the manifest's version stamp
+/// so that an identical build yields an identical artefact. Runs trained with
+/// differential privacy are refused, since their noise is not reproducible.
+pub fn verify_reproducible(
+    id: &ModelId,
+    version: &VersionName,
+) -> DeltaResult<ReproducibilityReport> {
+    let model = load_model(id, Some(version))?;
+    let manifest = run_manifest(id, version)?;
+    if TrainConfig::parse(manifest.config.clone())?.dp().enabled {
+        // DP noise comes from fresh entropy; a retrain cannot match it.
+        return Err(DeltaError::invalid("reproducibility_dp_unsupported"));
+    }
+    let stored = model_repo().get_artefact_bytes(&model.id, &model.version)?;
+    let run = run_training(
+        &manifest.dataset,
+        &manifest.config,
+        &TrainControl::default(),
+        Some(manifest.created_ms),
+    )?;
+    let retrained = run.artefact.encode();
+    let report = ReproducibilityReport {
+        model_id: model.id,
+        version: model.version,
+        identical: stored == retrained
+            && sha256::to_hex(&sha256::sha256(&stored)) == manifest.artefact_hash
+            && run.manifest.artefact_hash == manifest.artefact_hash,
+        expected_hash: manifest.artefact_hash,
+        actual_hash: run.manifest.artefact_hash,
+        dataset_hash_matches: run.manifest.dataset_hash == manifest.dataset_hash,
+        config_hash_matches: manifest::config_hash(&manifest.config) == manifest.config_hash,
+        code_version_matches: manifest.code_version == CODE_VERSION,
+    };
+    log::log_json(
+        if report.identical { "info" } else { "warn" },
+        "training",
+        "reproducibility_verified",
+        0,
+        0,
+    );
+    Ok(report)
+}
+
+/// Parent version of a warm-started run and the state the new fit continues from.
+struct WarmParent {
+    model: ModelVersion,
+    pipeline: FeaturePipeline,
+    fitted: Fitted,
 }
 
-/// Load the requested model version or fall back to the latest when no version is provided.
+impl WarmParent {
+    fn load(spec: &WarmStart, cfg: &TrainConfig) -> DeltaResult<Self> {
+        let model = load_model(&spec.model_id, Some(&spec.version))?;
+        if model.kind != cfg.model_kind() {
+            return Err(DeltaError::invalid("warm_start_kind_mismatch"));
+        }
+        let artefact = load_artefact(&model)?;
+        // Only artefacts with a fitted pipeline can map new records onto the
+        // parent's feature space.
+        let pipeline = artefact
+            .pipeline
+            .ok_or_else(|| DeltaError::invalid("warm_start_manifest_mismatch"))?;
+        if let Some(specs) = cfg.features() {
+            let manifest = pipeline.manifest();
+            let same = specs.len() == manifest.len()
+                && specs.iter().zip(&manifest).all(|(a, b)| a.name == b.name);
+            if !same {
+                return Err(DeltaError::invalid("warm_start_manifest_mismatch"));
+            }
+        }
+        Ok(Self {
+            model,
+            pipeline,
+            fitted: Fitted {
+                weights: artefact.weights,
+                trees: artefact.trees,
+            },
+        })
+    }
+
+    /// The parent's training datasets followed by `dataset`, without duplicates.
+    fn datasets(&self, dataset: &DatasetId) -> DeltaResult<Vec<DatasetId>> {
+        let mut datasets = self.model.metadata.lineage.datasets.clone();
+        if datasets.is_empty() {
+            return Err(DeltaError::invalid("warm_start_parent_data_unknown"));
+        }
+        if !datasets.contains(dataset) {
+            datasets.push(dataset.clone());
+        }
+        Ok(datasets)
+    }
+}
+
+/// Name of the vocabulary file stored next to text model artefacts.
+const TOKENIZER_ASSET: &str = "tokenizer.json";
+
+/// Frozen encoder, with its tokenizer, used as the feature extractor of a text head.
+struct TextSource {
+    reference: EncoderRef,
+    artefact: ModelArtefact,
+    encoder: Encoder,
+    tokenizer: WordPieceTokenizer,
+    tokenizer_raw: Vec<u8>,
+}
+
+impl TextSource {
+    fn load(reference: &EncoderRef) -> DeltaResult<Self> {
+        let model = load_model(&reference.model_id, Some(&reference.version))?;
+        if model.kind != ModelKind::TextMiniLm {
+            return Err(DeltaError::invalid("train_cfg_encoder_invalid"));
+        }
+        let artefact = load_artefact(&model)?;
+        let tokenizer_raw = load_asset(&model, TOKENIZER_ASSET)?;
+        let tokenizer = std::str::from_utf8(&tokenizer_raw)
+            .map_err(|_| DeltaError::invalid("tokenizer_invalid"))
+            .and_then(WordPieceTokenizer::from_json)?;
+        Ok(Self {
+            reference: reference.clone(),
+            encoder: Encoder::from_artefact(&artefact)?,
+            artefact,
+            tokenizer,
+            tokenizer_raw,
+        })
+    }
+
+    /// Embeddings of the text column, read from or written to the per-dataset cache.
+    fn embeddings(
+        &self,
+        dataset: &DatasetId,
+        records: &[Value],
+        control: &TrainControl,
+    ) -> DeltaResult<Vec<Vec<f32>>> {
+        let cache = EmbeddingCache::new(&AppCfg::load());
+        let reference = &self.reference;
+        if let Some(rows) = cache.get(dataset, reference) {
+            if rows.len() == records.len() {
+                return Ok(rows);
+            }
+        }
+        let rows = embeddings::embed_records(
+            &self.encoder,
+            &self.tokenizer,
+            records,
+            &reference.column,
+            control,
+        )?;
+        cache.put(dataset, reference, &rows)?;
+        Ok(rows)
+    }
+
+    /// Copy the encoder into `artefact` and store the fitted logistic head as its classifier.
+    fn attach_head(&self, artefact: &mut ModelArtefact, weights: &[f32]) {
+        let reference = &self.reference;
+        let hidden = self.encoder.config().hidden;
+        artefact.features = vec![FeatureSpec::new(&reference.column, FeatureKind::Text)];
+        artefact.tensors = self
+            .artefact
+            .tensors
+            .iter()
+            .filter(|tensor| !tensor.name.starts_with("head."))
+            .cloned()
+            .collect();
+        let (bias, head) = weights.split_first().unwrap_or((&0.0, &[]));
+        artefact
+            .tensors
+            .push(Tensor::f32("head.weight", vec![1, hidden], head.to_vec()));
+        artefact
+            .tensors
+            .push(Tensor::f32("head.bias", vec![1], vec![*bias]));
+        for (key, value) in &self.artefact.metadata {
+            if key.starts_with("encoder.") {
+                artefact.set_meta(key.clone(), value.clone());
+            }
+        }
+        artefact.set_meta(
+            "encoder.source",
+            format!(
+                "{}/{}",
+                reference.model_id.as_str(),
+                reference.version.as_str()
+            ),
+        );
+    }
+}
+
+/// Result of fitting the configured model family, including any search trials.
+struct FitResult {
+    fitted: Fitted,
+    params: Hyperparams,
+    search: Option<SearchSummary>,
+    trials: Vec<Trial>,
+    calibration: Option<(Calibrator, CalibrationReport)>,
+    metrics: Option<EvalMetrics>,
+    /// Importance method and one value per input dimension.
+    importance: Option<(&'static str, Vec<f64>)>,
+}
+
+/// Run the optional hyperparameter search, then fit the final model.
+///
+/// Without calibration every record is used for fitting; with calibration the
+/// holdout split is kept out of both the search and the final fit. `init`
+/// holds the parent parameters of a warm-started run.
+fn fit_model(
+    cfg: &TrainConfig,
+    init: Option<&Fitted>,
+    x: &[Vec<f32>],
+    records: &[Value],
+    control: &TrainControl,
+) -> DeltaResult<FitResult> {
+    let kind = match cfg.model_kind() {
+        // Text heads are logistic regressions over the frozen sentence embeddings.
+        ModelKind::TextMiniLm if cfg.encoder().is_some() => ModelKind::TabularLogistic,
+        kind => kind,
+    };
+    if kind == ModelKind::TextMiniLm {
+        // Without an encoder there is nothing to fit a head on.
+        if cfg.search().is_some() {
+            return Err(DeltaError::invalid("search_unsupported_model_kind"));
+        }
+        if cfg.calibration().is_some() {
+            return Err(DeltaError::invalid("calibration_unsupported_model_kind"));
+        }
+        return Ok(FitResult {
+            fitted: Fitted::default(),
+            params: cfg.params().clone(),
+            search: None,
+            trials: Vec::new(),
+            calibration: None,
+            metrics: None,
+            importance: None,
+        });
+    }
+
+    let labels = trainers::labels(records, cfg.label())?;
+    let (train_rows, holdout_rows) = match cfg.calibration() {
+        Some(spec) => spec.split(records.len())?,
+        None => ((0..records.len()).collect(), Vec::new()),
+    };
+    let select = |rows: &[usize]| -> (Vec<Vec<f32>>, Vec<f32>) {
+        rows.iter().map(|i| (x[*i].clone(), labels[*i])).unzip()
+    };
+    let (x_train, y) = select(&train_rows);
+    let x = x_train.as_slice();
+
+    let (params, summary, trials) = match cfg.search() {
+        Some(spec) => {
+            let outcome = search::run(kind, cfg.params(), spec, x, &y, cfg.dp(), control)?;
+            (
+                outcome.best_params().clone(),
+                Some(outcome.summary(spec)),
+                outcome.trials,
+            )
+        }
+        None => (cfg.params().clone(), None, Vec::new()),
+    };
+    let fitted = trainers::fit_warm(kind, init, x, &y, &params, cfg.dp(), control, "fit")?;
+    let importance = match kind {
+        ModelKind::TabularGradientBoosting => {
+            ("gain", trainers::gain_importance(&fitted, x, &y, params.l2))
+        }
+        _ => (
+            "coefficient",
+            fitted.weights.iter().skip(1).map(|w| *w as f64).collect(),
+        ),
+    };
+
+    let (calibration, metrics) = match cfg.calibration() {
+        Some(spec) => {
+            let (x_holdout, y_holdout) = select(&holdout_rows);
+            let raw = x_holdout
+                .iter()
+                .map(|row| fitted.predict(row))
+                .collect::<Vec<_>>();
+            let calibrator = Calibrator::fit(spec.method, &raw, &y_holdout)?;
+            let calibrated = raw.iter().map(|p| calibrator.apply(*p)).collect::<Vec<_>>();
+            let report = calibration::report(&calibrator, &calibrated, &y_holdout);
+            let metrics = eval_metrics("holdout", &y_holdout, &calibrated);
+            (Some((calibrator, report)), metrics)
+        }
+        None => {
+            let p = x.iter().map(|row| fitted.predict(row)).collect::<Vec<_>>();
+            (None, eval_metrics("train", &y, &p))
+        }
+    };
+
+    Ok(FitResult {
+        fitted,
+        params,
+        search: summary,
+        trials,
+        calibration,
+        metrics: Some(metrics),
+        importance: Some(importance),
+    })
+}
+
+fn eval_metrics(split: &str, y: &[f32], p: &[f32]) -> EvalMetrics {
+    EvalMetrics {
+        split: split.to_string(),
+        rows: y.len() as u32,
+        accuracy: trainers::accuracy(y, p),
+        log_loss: trainers::log_loss(y, p),
+        auc: trainers::auc(y, p),
+    }
+}
+
+/// Earliest and latest ingest time of the datasets a run read.
+fn dataset_period(datasets: &[DatasetId]) -> DeltaResult<Option<(u128, u128)>> {
+    let times = datasets
+        .iter()
+        .map(|id| data_service::dataset(id).map(|dataset| dataset.created_ms))
+        .collect::<DeltaResult<Vec<_>>>()?;
+    Ok(times.iter().min().copied().zip(times.iter().max().copied()))
+}
+
+/// Load the requested model version, or the current `Production` version when none is given.
+///
+/// Cache misses are resolved against the repository so versions written by
+/// other processes become visible without a restart.
 pub fn load_model(id: &ModelId, version: Option<&VersionName>) -> DeltaResult<ModelVersion> {
-    let guard = registry()
-        .lock()
-        .map_err(|_| DeltaError::internal("model_registry_poisoned"))?;
-    let model = match version {
-        Some(ver) if !ver.as_str().is_empty() => guard.get(id, ver),
-        _ => guard.latest(id),
+    let model = resolve_model(id, version)?;
+    verify_signature(&model)?;
+    Ok(model)
+}
+
+fn resolve_model(id: &ModelId, version: Option<&VersionName>) -> DeltaResult<ModelVersion> {
+    let mut guard = lock_registry()?;
+    match version {
+        Some(ver) if !ver.as_str().is_empty() => {
+            if let Some(model) = guard.get(id, ver) {
+                return Ok(model);
+            }
+            let model = model_repo().get_model(id, ver)?;
+            guard.insert(model.clone());
+            Ok(model)
+        }
+        _ => {
+            refresh_lifecycles(&mut guard, id)?;
+            guard
+                .production(id)
+                .ok_or_else(|| DeltaError::model_missing("no_production_version"))
+        }
+    }
+}
+
+/// Bring the cached stages of `id` up to date with `lifecycle.log` on disk.
+///
+/// Another PHP-FPM worker may have promoted or archived a version, or trained
+/// a new one, since this process filled its cache.
+fn refresh_lifecycles(guard: &mut ModelRegistry, id: &ModelId) -> DeltaResult<()> {
+    let repo = model_repo();
+    for version in repo.version_names(id)? {
+        let lifecycle = repo.read_lifecycle(id, &version)?;
+        match guard.get(id, &version) {
+            Some(model) if model.lifecycle == lifecycle => {}
+            Some(mut model) => {
+                model.lifecycle = lifecycle;
+                guard.insert(model);
+            }
+            None => match repo.read_version(id, &version) {
+                Ok(model) => guard.insert(model),
+                Err(err) => {
+                    log::log_json("warn", "training", "artefact_skipped", err.code as u32, 0)
+                }
+            },
+        }
+    }
+    Ok(())
+}
+
+fn signing_cfg() -> SigningCfg {
+    SigningCfg::from_app(&AppCfg::load())
+}
+
+/// Write `model.sig` for the stored artefact; without a signing key the
+/// artefact stays unsigned and a warning is logged.
+fn sign_artefact(cfg: &SigningCfg, model: &ModelVersion) -> DeltaResult<()> {
+    let bytes = model_repo().get_artefact_bytes(&model.id, &model.version)?;
+    match signing::sign(cfg, &bytes) {
+        Some(signature) => model_repo().put_asset(
+            &model.id,
+            &model.version,
+            SIGNATURE_ASSET,
+            signature.as_bytes(),
+        ),
+        None => {
+            log::log_json("warn", "training", "artefact_written_unsigned", 0, 0);
+            Ok(())
+        }
+    }
+}
+
+/// Sign a stored version again with the current `DELTA1_SIGNING_KEY`, e.g.
+/// after rotating keys. Cached copies of the artefact are dropped so the next
+/// request reads the re-signed file.
+pub fn resign_model(id: &ModelId, version: &VersionName) -> DeltaResult<()> {
+    let cfg = signing_cfg();
+    if cfg.signing_key.is_none() {
+        return Err(DeltaError::invalid("signing_key_missing"));
+    }
+    let model = resolve_model(id, Some(version))?;
+    sign_artefact(&cfg, &model)?;
+    inference_service::evict_artefacts(id, Some(version));
+    Ok(())
+}
+
+/// Check the stored artefact's signature under the configured keys and policy.
+///
+/// Versions without a stored artefact count as unsigned.
+pub fn verify_signature(model: &ModelVersion) -> DeltaResult<()> {
+    verify_signature_with(&signing_cfg(), model)
+}
+
+fn verify_signature_with(cfg: &SigningCfg, model: &ModelVersion) -> DeltaResult<()> {
+    match model_repo().get_artefact_bytes(&model.id, &model.version) {
+        Ok(artefact) => verify_bytes(cfg, model, &artefact),
+        Err(err) if err.code == DeltaCode::ModelMissing => signing::verify(cfg, &[], None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Check `artefact`, the bytes about to be used, against the stored signature.
+fn verify_bytes(cfg: &SigningCfg, model: &ModelVersion, artefact: &[u8]) -> DeltaResult<()> {
+    let signature = match model_repo().get_asset(&model.id, &model.version, SIGNATURE_ASSET) {
+        Ok(signature) => Some(signature),
+        Err(err) if err.code == DeltaCode::ModelMissing => None,
+        Err(err) => return Err(err),
+    };
+    signing::verify(cfg, artefact, signature.as_deref())
+}
+
+/// Read the stored artefact of a model version, e.g. to replay its feature pipeline.
+pub fn load_artefact(model: &ModelVersion) -> DeltaResult<ModelArtefact> {
+    model_repo().get_artefact(&model.id, &model.version)
+}
+
+/// Read the stored artefact for serving: the signature is checked on the
+/// very bytes that are decoded, so a file replaced after the version was
+/// activated is not served under an enforcing policy.
+pub fn load_verified_artefact(model: &ModelVersion) -> DeltaResult<ModelArtefact> {
+    load_verified_artefact_with(&signing_cfg(), model)
+}
+
+fn load_verified_artefact_with(
+    cfg: &SigningCfg,
+    model: &ModelVersion,
+) -> DeltaResult<ModelArtefact> {
+    let bytes = model_repo().get_artefact_bytes(&model.id, &model.version)?;
+    verify_bytes(cfg, model, &bytes)?;
+    ModelArtefact::decode(&bytes)
+}
+
+/// Auxiliary file stored next to a version's artefact (e.g. `tokenizer.json`).
+pub fn load_asset(model: &ModelVersion, name: &str) -> DeltaResult<Vec<u8>> {
+    model_repo().get_asset(&model.id, &model.version, name)
+}
+
+/// Register a pretrained text encoder, such as converted MiniLM weights, with its vocabulary.
+///
+/// The artefact's `TNSR` tensors must build a valid [`Encoder`]. The stored
+/// version starts as a `Candidate` and can be referenced as `"encoder"` when
+/// training text heads.
+pub fn import_encoder(source: ModelArtefact, tokenizer_json: &str) -> DeltaResult<ModelVersion> {
+    if source.kind != ModelKind::TextMiniLm {
+        return Err(DeltaError::invalid("import_kind_invalid"));
+    }
+    let config = Encoder::from_artefact(&source)?.config();
+    let tokenizer = WordPieceTokenizer::from_json(tokenizer_json)?;
+    if tokenizer.max_length() > config.max_positions || tokenizer.vocab_size() > config.vocab_size {
+        return Err(DeltaError::invalid("tokenizer_encoder_mismatch"));
+    }
+
+    let mut hasher = SimpleHash::new();
+    hasher.update(&source.encode());
+    hasher.update(tokenizer_json.as_bytes());
+    let id = ModelId::new(format!(
+        "{}-{}",
+        ModelKind::TextMiniLm.as_str(),
+        hasher.finish_hex()
+    ));
+    let created_ms = time::now_ms();
+    let version = VersionName::new(format!("v{created_ms}"));
+    let mut model = ModelVersion {
+        artefact_path: artefact::relative_path(&id, &version),
+        id,
+        version,
+        kind: ModelKind::TextMiniLm,
+        created_ms,
+        lifecycle: Lifecycle {
+            stage: ModelStage::Candidate,
+            approver: None,
+            changed_ms: created_ms,
+        },
+        metadata: ModelMetadata::default(),
+        checksum: None,
     };
 
-    model.ok_or_else(|| DeltaError::model_missing("model_version"))
+    let mut artefact = ModelArtefact::for_version(&model);
+    artefact.features = if source.features.is_empty() {
+        vec![FeatureSpec::new(DEFAULT_TEXT_COLUMN, FeatureKind::Text)]
+    } else {
+        source.features
+    };
+    artefact.tensors = source.tensors;
+    for (key, value) in source.metadata {
+        if key.starts_with("encoder.") {
+            artefact.set_meta(key, value);
+        }
+    }
+    model.checksum = artefact::checksum_hex(&artefact.encode());
+    model_repo().put_model(&model, &artefact)?;
+    sign_artefact(&signing_cfg(), &model)?;
+    model_repo().put_asset(
+        &model.id,
+        &model.version,
+        TOKENIZER_ASSET,
+        tokenizer_json.as_bytes(),
+    )?;
+    model_repo().put_lifecycle(&model.id, &model.version, &model.lifecycle)?;
+    lock_registry()?.insert(model.clone());
+    Ok(model)
 }
 
-/// Export a compact model card JSON for auditability.
+/// Hyperparameter search trials recorded for a version; empty when no search ran.
+pub fn search_trials(id: &ModelId, version: &VersionName) -> DeltaResult<Vec<Trial>> {
+    model_repo().get_trials(id, version)
+}
+
+/// Move a version through the approval workflow.
+///
+/// `Approved` and `Production` require a named approver; `Production` also
+/// requires a passing holdout evaluation and archives the previously served
+/// version.
+pub fn promote_model(
+    id: &ModelId,
+    version: &VersionName,
+    stage: ModelStage,
+    approver: Option<&str>,
+) -> DeltaResult<ModelVersion> {
+    let approver = approver.map(str::trim).filter(|name| !name.is_empty());
+    if let Some(name) = approver {
+        if name.chars().any(|c| c.is_control() || c == ';' || c == '=') {
+            return Err(DeltaError::invalid("approver_invalid"));
+        }
+    }
+    if stage.requires_approver() && approver.is_none() {
+        return Err(DeltaError::policy_denied("approver_required"));
+    }
+
+    let mut model = load_model(id, Some(version))?;
+    if !model.lifecycle.stage.can_transition_to(stage) {
+        return Err(DeltaError::invalid("stage_transition_invalid"));
+    }
+    if stage == ModelStage::Production && !evaluation::service::evaluate(&model)?.passed {
+        return Err(DeltaError::policy_denied("evaluation_failed"));
+    }
+
+    let previous = match stage {
+        ModelStage::Production => {
+            let mut guard = lock_registry()?;
+            refresh_lifecycles(&mut guard, id)?;
+            guard.production(id)
+        }
+        _ => None,
+    };
+
+    let lifecycle = Lifecycle {
+        stage,
+        approver: approver.map(str::to_string),
+        changed_ms: time::now_ms(),
+    };
+    model_repo().put_lifecycle(id, version, &lifecycle)?;
+    model.lifecycle = lifecycle;
+    lock_registry()?.insert(model.clone());
+    if stage == ModelStage::Archived {
+        inference_service::evict_artefacts(id, Some(version));
+    }
+
+    if let Some(mut retired) = previous.filter(|prev| prev.version != *version) {
+        let lifecycle = Lifecycle {
+            stage: ModelStage::Archived,
+            approver: approver.map(str::to_string),
+            changed_ms: time::now_ms(),
+        };
+        model_repo().put_lifecycle(&retired.id, &retired.version, &lifecycle)?;
+        inference_service::evict_artefacts(&retired.id, Some(&retired.version));
+        retired.lifecycle = lifecycle;
+        lock_registry()?.insert(retired);
+    }
+
+    Ok(model)
+}
+
+/// List the known versions of a model, oldest first.
+pub fn list_versions(id: &ModelId) -> DeltaResult<Vec<ModelVersion>> {
+    let mut guard = lock_registry()?;
+    for model in model_repo().list_versions(id)? {
+        guard.insert(model);
+    }
+    Ok(guard.versions(id))
+}
+
+/// Export the model card JSON for the most recent version, whatever its stage.
+///
+/// `card_hash` is the SHA-256 of the canonical card without that field, so an
+/// archived card can be checked for later edits.
 pub fn export_model_card(id: &ModelId) -> DeltaResult<String> {
-    let guard = registry()
-        .lock()
-        .map_err(|_| DeltaError::internal("model_registry_poisoned"))?;
-    let model = guard
-        .latest(id)
+    let model = list_versions(id)?
+        .pop()
         .ok_or_else(|| DeltaError::model_missing("model_version"))?;
 
     let fairness = model
@@ -124,45 +972,151 @@ pub fn export_model_card(id: &ModelId) -> DeltaResult<String> {
         })
         .unwrap_or_else(|| "{}".to_string());
 
+    let approver = model
+        .lifecycle
+        .approver
+        .as_deref()
+        .map(|name| format!("\"{}\"", crate::common::json::escape(name)))
+        .unwrap_or_else(|| "null".to_string());
+
+    let search = model
+        .metadata
+        .search
+        .as_ref()
+        .map(|s| {
+            format!(
+                "{{\"strategy\":\"{}\",\"folds\":{},\"trials\":{},\"best_trial\":{},\"metric\":\"log_loss\",\"best_log_loss\":{:.4},\"best_accuracy\":{:.4}}}",
+                crate::common::json::escape(&s.strategy),
+                s.folds,
+                s.trials,
+                s.best_trial,
+                s.best_log_loss,
+                s.best_accuracy
+            )
+        })
+        .unwrap_or_else(|| "null".to_string());
+
+    let lineage = &model.metadata.lineage;
+    let parent = lineage
+        .parent
+        .as_ref()
+        .map(|(id, version)| {
+            format!(
+                "{{\"model_id\":\"{}\",\"version\":\"{}\"}}",
+                crate::common::json::escape(id.as_str()),
+                crate::common::json::escape(version.as_str())
+            )
+        })
+        .unwrap_or_else(|| "null".to_string());
+    let datasets = lineage
+        .datasets
+        .iter()
+        .map(|id| format!("\"{}\"", crate::common::json::escape(id.as_str())))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let period = lineage
+        .period
+        .map(|(from, to)| format!("{{\"from_ms\":{from},\"to_ms\":{to}}}"))
+        .unwrap_or_else(|| "null".to_string());
+
+    let features = model
+        .metadata
+        .importance
+        .as_ref()
+        .map(|importance| {
+            importance
+                .features
+                .iter()
+                .map(|(name, value)| {
+                    format!(
+                        "{{\"name\":\"{}\",\"{}\":{:.6}}}",
+                        json::escape(name),
+                        json::escape(&importance.method),
+                        value
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .unwrap_or_default();
+
+    let metrics = model
+        .metadata
+        .metrics
+        .as_ref()
+        .map(|m| {
+            format!(
+                "{{\"split\":\"{}\",\"rows\":{},\"accuracy\":{:.4},\"log_loss\":{:.4},\"auc\":{:.4}}}",
+                json::escape(&m.split),
+                m.rows,
+                m.accuracy,
+                m.log_loss,
+                m.auc
+            )
+        })
+        .unwrap_or_else(|| "null".to_string());
+
+    let info = &model.metadata.card;
+    let quoted = |value: Option<&str>| {
+        value
+            .map(|value| format!("\"{}\"", json::escape(value)))
+            .unwrap_or_else(|| "null".to_string())
+    };
+
     let card = format!(
-        "{{\"model_id\":\"{}\",\"version\":\"{}\",\"kind\":\"{}\",\"artefact\":\"{}\",\"dp\":{{\"enabled\":{},\"epsilon\":{:.4},\"delta\":{:.6},\"clip\":{:.4},\"noise_multiplier\":{:.4}}},\"fairness\":{}}}",
+        "{{\"model_id\":\"{}\",\"version\":\"{}\",\"kind\":\"{}\",\"stage\":\"{}\",\"approver\":{},\"artefact\":\"{}\",\"data\":{{\"datasets\":[{}],\"period\":{}}},\"features\":[{}],\"metrics\":{},\"dp\":{{\"enabled\":{},\"epsilon\":{:.4},\"delta\":{:.6},\"clip\":{:.4},\"noise_multiplier\":{:.4}}},\"fairness\":{},\"hyperparameters\":{},\"search\":{},\"lineage\":{{\"parent\":{},\"datasets\":[{}]}},\"risk\":{},\"limitations\":{},\"contact\":{}}}",
         crate::common::json::escape(model.id.as_str()),
         crate::common::json::escape(model.version.as_str()),
-        crate::common::json::escape(model_kind_label(model.kind)),
+        crate::common::json::escape(model.kind.as_str()),
+        model.lifecycle.stage.as_str(),
+        approver,
         crate::common::json::escape(&model.artefact_path),
+        datasets,
+        period,
+        features,
+        metrics,
         if model.metadata.dp.enabled { "true" } else { "false" },
         model.metadata.dp.epsilon,
         model.metadata.dp.delta,
         model.metadata.dp.clip,
         model.metadata.dp.noise_multiplier,
-        fairness
+        fairness,
+        model.metadata.hyperparams.to_json(),
+        search,
+        parent,
+        datasets,
+        quoted(info.risk.map(|risk| risk.as_str())),
+        json::build_string_array(&info.limitations),
+        quoted(info.contact.as_deref())
     );
 
-    Ok(card)
+    // The hash covers the canonical form of everything before `card_hash`.
+    let canonical = json::parse(&card)
+        .map_err(|_| DeltaError::internal("model_card_invalid"))?
+        .canonical();
+    let hash = sha256::to_hex(&sha256::sha256(canonical.as_bytes()));
+    Ok(format!(
+        "{},\"card_hash\":\"{}\"}}",
+        &card[..card.len() - 1],
+        hash
+    ))
 }
 
 fn make_model_id(dataset: &DatasetId, cfg_json: &str, kind: ModelKind) -> ModelId {
     let mut hasher = SimpleHash::new();
     hasher.update(dataset.as_str().as_bytes());
     hasher.update(cfg_json.as_bytes());
-    hasher.update(model_kind_label(kind).as_bytes());
-    ModelId::new(format!(
-        "{}-{}",
-        model_kind_label(kind),
-        hasher.finish_hex()
-    ))
+    hasher.update(kind.as_str().as_bytes());
+    ModelId::new(format!("{}-{}", kind.as_str(), hasher.finish_hex()))
 }
 
-fn model_kind_label(kind: ModelKind) -> &'static str {
-    match kind {
-        ModelKind::TabularLogistic => "tabular-logreg",
-        ModelKind::TabularGradientBoosting => "tabular-gbdt",
-        ModelKind::TextMiniLm => "text-minilm",
-    }
+fn enforce_dp(cfg: &TrainConfig) -> DeltaResult<()> {
+    check_dp(cfg.dp())
 }
 
-fn enforce_dp(cfg: &TrainConfig) -> DeltaResult<()> {
-    let dp = cfg.dp();
+/// Validate differential privacy settings against the design bounds.
+pub(crate) fn check_dp(dp: &DifferentialPrivacy) -> DeltaResult<()> {
     if !dp.enabled {
         return Ok(());
     }
@@ -184,7 +1138,12 @@ fn enforce_dp(cfg: &TrainConfig) -> DeltaResult<()> {
 }
 
 fn enforce_fairness(cfg: &TrainConfig) -> DeltaResult<()> {
-    match cfg.fairness() {
+    check_fairness(cfg.fairness())
+}
+
+/// Validate a fairness report against the subgroup gap thresholds.
+pub(crate) fn check_fairness(report: Option<&FairnessReport>) -> DeltaResult<()> {
+    match report {
         Some(report) => {
             check_fairness_delta(report.delta_tpr, MAX_DELTA_TPR, "delta_tpr_exceeded")?;
             check_fairness_delta(report.delta_fpr, MAX_DELTA_FPR, "delta_fpr_exceeded")?;
@@ -207,9 +1166,9 @@ fn check_fairness_delta(value: f32, bound: f32, code: &'static str) -> DeltaResu
 /// Helper used by tests to clear the in-memory registry.
 #[cfg(test)]
 pub(crate) fn reset_registry() {
+    crate::common::config::use_test_data_root();
     if let Ok(mut reg) = registry().lock() {
         reg.entries.clear();
-        reg.latest.clear();
     }
 }
 
@@ -238,4 +1197,549 @@ mod tests {
             DeltaError::policy_denied("dp_epsilon_exceeded").code as u32
         );
     }
+
+    const VALID_CFG: &str = "{\"fairness\":{\"delta_tpr\":0.01,\"delta_fpr\":0.01,\"delta_ppv\":0.01},\"dp\":{\"enabled\":false}}";
+
+    /// Small dataset whose content (and thus model id) is unique per test.
+    fn dataset(name: &str) -> DatasetId {
+        let tagged = format!("{{\"amount\":1,\"country\":\"{name}\",\"label\":0}}");
+        crate::data::service::ingest_fixture(
+            name,
+            &[
+                "{\"amount\":12.5,\"country\":\"NL\",\"label\":1}",
+                "{\"amount\":80,\"country\":\"BE\",\"label\":0}",
+                "{\"amount\":null,\"country\":\"NL\",\"label\":1}",
+                &tagged,
+            ],
+        )
+    }
+
+    #[test]
+    fn grid_search_trials_are_persisted_and_summarised() {
+        reset_registry();
+        let dataset = crate::data::service::ingest_fixture(
+            "search",
+            &[
+                "{\"amount\":1,\"label\":0}",
+                "{\"amount\":2,\"label\":0}",
+                "{\"amount\":3,\"label\":0}",
+                "{\"amount\":7,\"label\":1}",
+                "{\"amount\":8,\"label\":1}",
+                "{\"amount\":9,\"label\":1}",
+            ],
+        );
+        let cfg = "{\"dp\":{\"enabled\":false},\"model_kind\":\"tabular_gbdt\",\"fairness\":{\"delta_tpr\":0.01,\"delta_fpr\":0.01,\"delta_ppv\":0.01},\"params\":{\"n_trees\":10},\"search\":{\"folds\":3,\"space\":{\"max_depth\":[1,2],\"learning_rate\":[0.1,0.3]}}}";
+        let model = train(dataset, cfg).unwrap();
+
+        let trials = search_trials(&model.id, &model.version).unwrap();
+        assert_eq!(trials.len(), 4);
+        assert!(trials.iter().all(|t| t.fold_log_loss.len() == 3));
+        let summary = model.metadata.search.clone().unwrap();
+        let best = &trials[summary.best_trial as usize];
+        assert_eq!(model.metadata.hyperparams, best.params);
+        assert_eq!(model.metadata.hyperparams.n_trees, 10);
+
+        let card = export_model_card(&model.id).unwrap();
+        assert!(card.contains("\"search\":{\"strategy\":\"grid\",\"folds\":3,\"trials\":4"));
+        let artefact = load_artefact(&model).unwrap();
+        assert_eq!(artefact.trees.len(), 10);
+    }
+
+    #[test]
+    fn calibration_is_fitted_on_the_holdout_and_reported() {
+        reset_registry();
+        let lines = (0..40)
+            .map(|i| {
+                format!(
+                    "{{\"amount\":{i},\"label\":{}}}",
+                    (i * 7 % 10 < 4 + i / 10) as u8
+                )
+            })
+            .collect::<Vec<_>>();
+        let lines = lines.iter().map(String::as_str).collect::<Vec<_>>();
+        let records = crate::data::service::ingest_fixture("calibration", &lines);
+        let cfg = "{\"dp\":{\"enabled\":false},\"fairness\":{\"delta_tpr\":0.01,\"delta_fpr\":0.01,\"delta_ppv\":0.01},\"params\":{\"epochs\":50},\"calibration\":{\"method\":\"isotonic\",\"holdout\":0.25}}";
+        let model = train(records, cfg).unwrap();
+
+        let report = model.metadata.calibration.clone().unwrap();
+        assert_eq!(
+            (report.method.as_str(), report.holdout_rows),
+            ("isotonic", 10)
+        );
+        assert_eq!(report.bins.iter().map(|b| b.count).sum::<u32>(), 10);
+        assert!(load_artefact(&model).unwrap().calibration.is_some());
+        let card = evaluation::service::evaluate(&model).unwrap().metrics_card;
+        assert!(card.contains("\"calibration\":{\"method\":\"isotonic\",\"holdout_rows\":10"));
+
+        let too_small = "{\"dp\":{\"enabled\":false},\"fairness\":{\"delta_tpr\":0.01,\"delta_fpr\":0.01,\"delta_ppv\":0.01},\"calibration\":{\"method\":\"platt\"}}";
+        let err = train(dataset("calibration-small"), too_small).unwrap_err();
+        assert_eq!(err.msg, "calibration_holdout_too_small");
+    }
+
+    #[test]
+    fn text_heads_train_on_cached_encoder_embeddings() {
+        reset_registry();
+        let encoder = import_encoder(
+            crate::inference::encoder::test_artefact(8, 0),
+            "{\"model\":{\"vocab\":[\"[PAD]\",\"[UNK]\",\"[CLS]\",\"[SEP]\",\"good\",\"bad\",\"very\",\"service\"]},\"truncation\":{\"max_length\":16}}",
+        )
+        .unwrap();
+        let records = crate::data::service::ingest_fixture(
+            "text-head",
+            &[
+                "{\"review\":\"good service\",\"label\":1}",
+                "{\"review\":\"very good\",\"label\":1}",
+                "{\"review\":\"bad service\",\"label\":0}",
+                "{\"review\":\"very bad\",\"label\":0}",
+            ],
+        );
+        let cfg = format!(
+            "{{\"dp\":{{\"enabled\":false}},\"model_kind\":\"text_minilm\",\"fairness\":{{\"delta_tpr\":0.01,\"delta_fpr\":0.01,\"delta_ppv\":0.01}},\"params\":{{\"epochs\":300,\"learning_rate\":0.5}},\"encoder\":{{\"model_id\":\"{}\",\"version\":\"{}\",\"text_column\":\"review\"}}}}",
+            encoder.id.as_str(),
+            encoder.version.as_str()
+        );
+        let model = train(records.clone(), &cfg).unwrap();
+
+        let reference = TrainConfig::parse(cfg.clone())
+            .unwrap()
+            .encoder()
+            .cloned()
+            .unwrap();
+        let cached = EmbeddingCache::new(&AppCfg::load()).get(&records, &reference);
+        assert_eq!(cached.map(|rows| rows.len()), Some(4));
+
+        let artefact = load_artefact(&model).unwrap();
+        assert_eq!(artefact.features[0].name, "review");
+        assert!(load_asset(&model, TOKENIZER_ASSET).is_ok());
+        let head = Encoder::from_artefact(&artefact).unwrap();
+        let tokenizer = WordPieceTokenizer::from_json(
+            std::str::from_utf8(&load_asset(&model, TOKENIZER_ASSET).unwrap()).unwrap(),
+        )
+        .unwrap();
+        let good = head.classify(&tokenizer.encode("very good").ids).unwrap();
+        let bad = head.classify(&tokenizer.encode("very bad").ids).unwrap();
+        assert!(good.score() > bad.score());
+
+        let err = train(
+            records.clone(),
+            &cfg.replace(encoder.version.as_str(), "v-missing"),
+        )
+        .unwrap_err();
+        assert_eq!(err.code as u32, DeltaError::model_missing("").code as u32);
+        // Without an encoder there is nothing to train a text head on.
+        let bare = "{\"dp\":{\"enabled\":false},\"model_kind\":\"text_minilm\",\"fairness\":{\"delta_tpr\":0.01,\"delta_fpr\":0.01,\"delta_ppv\":0.01}}";
+        assert_eq!(train(records, bare).unwrap_err().msg, "encoder_missing");
+    }
+
+    #[test]
+    fn warm_start_extends_the_parent_and_records_lineage() {
+        reset_registry();
+        let base = dataset("warm-base");
+        let refresh = dataset("warm-refresh");
+        let cfg = "{\"dp\":{\"enabled\":false},\"model_kind\":\"tabular_gbdt\",\"fairness\":{\"delta_tpr\":0.01,\"delta_fpr\":0.01,\"delta_ppv\":0.01},\"params\":{\"n_trees\":4}}";
+        let parent = train(base.clone(), cfg).unwrap();
+        assert_eq!(parent.metadata.lineage.datasets, vec![base.clone()]);
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let warm = format!(
+            "{{\"dp\":{{\"enabled\":false}},\"model_kind\":\"tabular_gbdt\",\"fairness\":{{\"delta_tpr\":0.01,\"delta_fpr\":0.01,\"delta_ppv\":0.01}},\"params\":{{\"n_trees\":4}},\"warm_start\":{{\"model_id\":\"{}\",\"version\":\"{}\",\"include_parent_data\":true}}}}",
+            parent.id.as_str(),
+            parent.version.as_str()
+        );
+        let child = train(refresh.clone(), &warm).unwrap();
+        assert_eq!(child.id, parent.id);
+        assert_ne!(child.version, parent.version);
+        assert_eq!(
+            child.metadata.lineage.parent,
+            Some((parent.id.clone(), parent.version.clone()))
+        );
+        assert_eq!(child.metadata.lineage.datasets, vec![base, refresh.clone()]);
+
+        let parent_artefact = load_artefact(&parent).unwrap();
+        let child_artefact = load_artefact(&child).unwrap();
+        assert_eq!(child_artefact.trees.len(), 8);
+        assert_eq!(child_artefact.trees[..4], parent_artefact.trees[..]);
+        assert_eq!(child_artefact.pipeline, parent_artefact.pipeline);
+
+        let card = export_model_card(&parent.id).unwrap();
+        assert!(card.contains(&format!(
+            "\"lineage\":{{\"parent\":{{\"model_id\":\"{}\",\"version\":\"{}\"}}",
+            parent.id.as_str(),
+            parent.version.as_str()
+        )));
+
+        let mismatch = warm.replace("tabular_gbdt", "tabular_logreg");
+        let err = train(refresh, &mismatch).unwrap_err();
+        assert_eq!(err.msg, "warm_start_kind_mismatch");
+    }
+
+    #[test]
+    fn run_manifests_allow_byte_for_byte_retraining() {
+        reset_registry();
+        let records = dataset("reproduce");
+        let model = train(records.clone(), VALID_CFG).unwrap();
+
+        let manifest = run_manifest(&model.id, &model.version).unwrap();
+        assert_eq!(manifest.dataset, records);
+        assert_eq!(manifest.datasets, vec![records.clone()]);
+        assert_eq!(manifest.created_ms, model.created_ms);
+        assert_eq!(manifest.seed, 42);
+        let stored = model_repo()
+            .get_artefact_bytes(&model.id, &model.version)
+            .unwrap();
+        assert_eq!(
+            manifest.artefact_hash,
+            sha256::to_hex(&sha256::sha256(&stored))
+        );
+
+        let report = verify_reproducible(&model.id, &model.version).unwrap();
+        assert!(report.identical, "{}", report.to_json());
+        assert!(report.dataset_hash_matches && report.config_hash_matches);
+        assert_eq!(report.actual_hash, report.expected_hash);
+
+        let altered = RunManifest {
+            config: VALID_CFG.replace("\"dp\"", "\"params\":{\"epochs\":3},\"dp\""),
+            ..manifest
+        };
+        model_repo()
+            .put_asset(
+                &model.id,
+                &model.version,
+                MANIFEST_ASSET,
+                altered.to_json().as_bytes(),
+            )
+            .unwrap();
+        let report = verify_reproducible(&model.id, &model.version).unwrap();
+        assert!(!report.identical && !report.config_hash_matches);
+    }
+
+    #[test]
+    fn strict_configs_reject_typos_and_echo_defaults() {
+        reset_registry();
+        let typo = VALID_CFG.replace("\"dp\"", "\"model_kind\":\"tabular_gbt\",\"dp\"");
+        let err = train(dataset("strict"), &typo).unwrap_err();
+        assert_eq!(err.msg, "train_cfg_model_kind_unknown");
+        assert_eq!(
+            validate_config(&typo),
+            "{\"valid\":false,\"config\":null,\"errors\":[{\"field\":\"model_kind\",\"error\":\"train_cfg_model_kind_unknown\"}]}"
+        );
+
+        let report = validate_config(VALID_CFG);
+        assert!(report.starts_with("{\"valid\":true,\"config\":{\"calibration\":null,"));
+        assert!(report.contains("\"model_kind\":\"tabular_logreg\""));
+
+        let lenient = typo.replace("\"dp\"", "\"strict\":false,\"dp\"");
+        assert!(train(dataset("lenient"), &lenient).is_ok());
+    }
+
+    #[test]
+    fn model_card_reports_features_metrics_and_governance() {
+        reset_registry();
+        let dataset = crate::data::service::ingest_fixture(
+            "card",
+            &[
+                "{\"amount\":1,\"channel\":\"web\",\"label\":0}",
+                "{\"amount\":2,\"channel\":\"app\",\"label\":0}",
+                "{\"amount\":8,\"channel\":\"web\",\"label\":1}",
+                "{\"amount\":9,\"channel\":\"app\",\"label\":1}",
+            ],
+        );
+        let cfg = "{\"dp\":{\"enabled\":false},\"fairness\":{\"delta_tpr\":0.01,\"delta_fpr\":0.01,\"delta_ppv\":0.01},\"card\":{\"risk\":\"limited\",\"limitations\":[\"sensitive to missing amount\"],\"contact\":\"ml-oversight@example.org\"}}";
+        let model = train(dataset.clone(), cfg).unwrap();
+
+        let card = export_model_card(&model.id).unwrap();
+        let root = json::parse(&card).unwrap();
+        let data = root.get("data").unwrap();
+        assert_eq!(
+            data.get("datasets").unwrap().as_array().unwrap()[0].as_str(),
+            Some(dataset.as_str())
+        );
+        assert!(data.get("period").unwrap().get("from_ms").is_some());
+        let features = root.get("features").unwrap().as_array().unwrap();
+        let amount = features
+            .iter()
+            .find(|f| f.get("name").and_then(Value::as_str) == Some("amount"))
+            .unwrap();
+        assert!(amount.get("coefficient").unwrap().as_f64().unwrap() > 0.0);
+        let metrics = root.get("metrics").unwrap();
+        assert_eq!(metrics.get("split").unwrap().as_str(), Some("train"));
+        assert_eq!(metrics.get("auc").unwrap().as_f64(), Some(1.0));
+        assert_eq!(root.get("risk").unwrap().as_str(), Some("limited"));
+        assert_eq!(
+            root.get("limitations").unwrap().as_array().unwrap().len(),
+            1
+        );
+        assert_eq!(
+            root.get("contact").unwrap().as_str(),
+            Some("ml-oversight@example.org")
+        );
+
+        let hash = root.get("card_hash").unwrap().as_str().unwrap().to_string();
+        let Value::Object(members) = root else {
+            panic!("card is not an object");
+        };
+        let unhashed = Value::Object(
+            members
+                .into_iter()
+                .filter(|(key, _)| key != "card_hash")
+                .collect(),
+        );
+        assert_eq!(
+            sha256::to_hex(&sha256::sha256(unhashed.canonical().as_bytes())),
+            hash
+        );
+    }
+
+    #[test]
+    fn signatures_are_checked_against_trusted_keys() {
+        reset_registry();
+        let model = train(dataset("signed"), VALID_CFG).unwrap();
+        let enforce = |signing: Option<&str>, trusted: &[&str]| SigningCfg {
+            signing_key: signing.map(|key| key.as_bytes().to_vec()),
+            trusted_keys: trusted.iter().map(|key| key.as_bytes().to_vec()).collect(),
+            policy: signing::SignaturePolicy::Enforce,
+        };
+        let trainer = enforce(Some("train-key"), &[]);
+        if load_asset(&model, SIGNATURE_ASSET).is_err() {
+            let err = verify_signature_with(&trainer, &model).unwrap_err();
+            assert_eq!(err.msg, "artefact_unsigned");
+        }
+
+        sign_artefact(&trainer, &model).unwrap();
+        assert!(verify_signature_with(&enforce(None, &["train-key"]), &model).is_ok());
+        let err = verify_signature_with(&enforce(None, &["other-key"]), &model).unwrap_err();
+        assert_eq!(err.msg, "artefact_signature_untrusted");
+        assert_eq!(err.code as u32, DeltaCode::PolicyDenied as u32);
+
+        // Serving checks the bytes it decodes, not the file seen at activation.
+        let verifier = enforce(None, &["train-key"]);
+        let mut swapped = load_verified_artefact_with(&verifier, &model).unwrap();
+        swapped
+            .weights
+            .iter_mut()
+            .for_each(|weight| *weight = -*weight);
+        model_repo().put_model(&model, &swapped).unwrap();
+        let err = load_verified_artefact_with(&verifier, &model).unwrap_err();
+        assert_eq!(err.msg, "artefact_signature_invalid");
+    }
+
+    #[test]
+    fn trained_versions_survive_registry_rebuild() {
+        reset_registry();
+        let dataset = dataset("persist");
+        let model = train(dataset.clone(), VALID_CFG).unwrap();
+        assert!(model.checksum.is_some());
+
+        let rebuilt = ModelRegistry::load(model_repo());
+        let restored = rebuilt.get(&model.id, &model.version).unwrap();
+        assert_eq!(restored.created_ms, model.created_ms);
+        assert_eq!(restored.checksum, model.checksum);
+    }
+
+    #[test]
+    fn artefacts_are_replaced_atomically_inside_the_model_root() {
+        reset_registry();
+        let model = train(dataset("atomic"), VALID_CFG).unwrap();
+        let artefact = load_artefact(&model).unwrap();
+        model_repo().put_model(&model, &artefact).unwrap();
+        let dir = std::path::PathBuf::from(AppCfg::load().data_root)
+            .join("models")
+            .join(model.id.as_str())
+            .join(model.version.as_str());
+        let leftovers = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+            .count();
+        assert_eq!(leftovers, 0);
+        assert_eq!(load_artefact(&model).unwrap(), artefact);
+
+        let mut escaping = model.clone();
+        escaping.id = ModelId::new("../escape");
+        let err = model_repo().put_model(&escaping, &artefact).unwrap_err();
+        assert_eq!(err.msg, "model_id_invalid");
+        escaping.id = model.id.clone();
+        escaping.version = VersionName::new("v1/../../x");
+        let err = model_repo().put_model(&escaping, &artefact).unwrap_err();
+        assert_eq!(err.msg, "model_version_invalid");
+        let err = load_model(&ModelId::new(".."), None).unwrap_err();
+        assert_eq!(err.code as u32, DeltaCode::InvalidInput as u32);
+    }
+
+    #[test]
+    fn load_model_falls_back_to_disk_and_lists_in_creation_order() {
+        reset_registry();
+        let dataset = dataset("history");
+        let first = train(dataset.clone(), VALID_CFG).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let second = train(dataset.clone(), VALID_CFG).unwrap();
+        reset_registry();
+
+        let loaded = load_model(&first.id, Some(&first.version)).unwrap();
+        assert_eq!(loaded.version, first.version);
+
+        let versions = list_versions(&first.id).unwrap();
+        let names = versions
+            .iter()
+            .map(|v| v.version.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec![first.version, second.version]);
+    }
+
+    /// Config with a calibration holdout, so versions carry evaluation evidence.
+    const EVALUATED_CFG: &str = "{\"dp\":{\"enabled\":false},\"fairness\":{\"delta_tpr\":0.01,\"delta_fpr\":0.01,\"delta_ppv\":0.01},\"params\":{\"epochs\":50},\"calibration\":{\"method\":\"isotonic\",\"holdout\":0.5}}";
+
+    /// Separable dataset large enough for a holdout that passes evaluation.
+    fn evaluated_dataset(name: &str) -> DatasetId {
+        let lines = (0..80)
+            .map(|i| {
+                format!(
+                    "{{\"amount\":{i},\"tag\":\"{name}\",\"label\":{}}}",
+                    u8::from(i >= 40)
+                )
+            })
+            .collect::<Vec<_>>();
+        let lines = lines.iter().map(String::as_str).collect::<Vec<_>>();
+        crate::data::service::ingest_fixture(name, &lines)
+    }
+
+    #[test]
+    fn production_requires_a_passing_holdout_evaluation() {
+        reset_registry();
+        let unevaluated = train(dataset("unevaluated"), VALID_CFG).unwrap();
+        promote_model(
+            &unevaluated.id,
+            &unevaluated.version,
+            ModelStage::Approved,
+            Some("alice"),
+        )
+        .unwrap();
+        let err = promote_model(
+            &unevaluated.id,
+            &unevaluated.version,
+            ModelStage::Production,
+            Some("bob"),
+        )
+        .unwrap_err();
+        assert_eq!(err.msg, "evaluation_failed");
+        assert_eq!(err.code as u32, DeltaCode::PolicyDenied as u32);
+        let card = evaluation::service::evaluate(&unevaluated)
+            .unwrap()
+            .metrics_card;
+        assert!(card.contains("\"holdout\":false"));
+
+        let model = train(evaluated_dataset("evaluated"), EVALUATED_CFG).unwrap();
+        let suite = evaluation::service::evaluate(&model).unwrap();
+        assert!(suite.passed, "{}", suite.metrics_card);
+        let strict = evaluation::EvalThresholds {
+            min_auc: 0.6,
+            max_ece: 0.1,
+            min_rows: 1000,
+        };
+        assert!(
+            !evaluation::service::evaluate_with(&model, &strict)
+                .unwrap()
+                .passed
+        );
+    }
+
+    #[test]
+    fn default_load_resolves_production_not_latest() {
+        reset_registry();
+        let dataset = evaluated_dataset("lifecycle");
+        let served = train(dataset.clone(), EVALUATED_CFG).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let newer = train(dataset.clone(), EVALUATED_CFG).unwrap();
+
+        let err = load_model(&served.id, None).unwrap_err();
+        assert_eq!(err.msg, "no_production_version");
+
+        let err = promote_model(
+            &served.id,
+            &served.version,
+            ModelStage::Production,
+            Some("alice"),
+        )
+        .unwrap_err();
+        assert_eq!(err.msg, "stage_transition_invalid");
+        let err =
+            promote_model(&served.id, &served.version, ModelStage::Approved, None).unwrap_err();
+        assert_eq!(err.msg, "approver_required");
+
+        promote_model(
+            &served.id,
+            &served.version,
+            ModelStage::Approved,
+            Some("alice"),
+        )
+        .unwrap();
+        promote_model(
+            &served.id,
+            &served.version,
+            ModelStage::Production,
+            Some("bob"),
+        )
+        .unwrap();
+        assert_eq!(
+            load_model(&served.id, None).unwrap().version,
+            served.version
+        );
+
+        promote_model(
+            &newer.id,
+            &newer.version,
+            ModelStage::Approved,
+            Some("alice"),
+        )
+        .unwrap();
+        promote_model(
+            &newer.id,
+            &newer.version,
+            ModelStage::Production,
+            Some("bob"),
+        )
+        .unwrap();
+        reset_registry();
+
+        let current = load_model(&served.id, None).unwrap();
+        assert_eq!(current.version, newer.version);
+        assert_eq!(current.lifecycle.approver.as_deref(), Some("bob"));
+        let retired = load_model(&served.id, Some(&served.version)).unwrap();
+        assert_eq!(retired.lifecycle.stage, ModelStage::Archived);
+    }
+
+    #[test]
+    fn default_load_sees_promotions_from_another_worker() {
+        let dataset = evaluated_dataset("other_worker");
+        let trained = train(dataset, EVALUATED_CFG).unwrap();
+        let err = load_model(&trained.id, None).unwrap_err();
+        assert_eq!(err.msg, "no_production_version");
+
+        // A second handle stands in for another PHP-FPM worker sharing DATA_ROOT.
+        let other = FsModelRepo::new(&AppCfg::load());
+        let stage = |stage| Lifecycle {
+            stage,
+            approver: Some("carol".to_string()),
+            changed_ms: time::now_ms(),
+        };
+        other
+            .put_lifecycle(&trained.id, &trained.version, &stage(ModelStage::Approved))
+            .unwrap();
+        other
+            .put_lifecycle(
+                &trained.id,
+                &trained.version,
+                &stage(ModelStage::Production),
+            )
+            .unwrap();
+        let served = load_model(&trained.id, None).unwrap();
+        assert_eq!(served.version, trained.version);
+        assert_eq!(served.lifecycle.approver.as_deref(), Some("carol"));
+
+        other
+            .put_lifecycle(&trained.id, &trained.version, &stage(ModelStage::Archived))
+            .unwrap();
+        let err = load_model(&trained.id, None).unwrap_err();
+        assert_eq!(err.msg, "no_production_version");
+    }
 }
@@ -0,0 +1,196 @@
+//! Artefact signatures, checked whenever a version is loaded or activated.
+//!
+//! Training signs the exact `model.bin` bytes with HMAC-SHA256 under
+//! `DELTA1_SIGNING_KEY` and stores the result next to the artefact as
+//! `model.sig`:
+//!
+//! ```json
+//! {"algorithm": "hmac-sha256", "key_id": "3f2a…", "signature": "9c41…"}
+//! ```
+//!
+//! `key_id` is the first 16 hex characters of the SHA-256 of the key, so the
+//! verifier can pick the matching secret from `DELTA1_TRUSTED_KEYS` (the
+//! signing key itself is always trusted). `DELTA1_SIGNATURE_POLICY` decides
+//! what happens to unsigned, untrusted or tampered artefacts: `enforce`
+//! refuses them with `PolicyDenied`, `warn` (the default) logs and continues;
+//! any other value is treated as `enforce`.
+//!
+//! TODO: Switch to Ed25519 key pairs (model-design.md) so verifiers do not
+//! need the signing secret.
+
+use crate::common::config::AppCfg;
+use crate::common::error::{DeltaCode, DeltaError, DeltaResult};
+use crate::common::json::{self, Value};
+use crate::common::log;
+use crate::common::sha256::{constant_time_eq, hmac_sha256, sha256, to_hex};
+
+/// Name of the signature file stored next to each artefact.
+pub const SIGNATURE_ASSET: &str = "model.sig";
+
+const ALGORITHM: &str = "hmac-sha256";
+const KEY_ID_LEN: usize = 16;
+
+/// What to do with artefacts whose signature does not check out.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum SignaturePolicy {
+    Enforce,
+    #[default]
+    Warn,
+}
+
+impl SignaturePolicy {
+    /// Parse `DELTA1_SIGNATURE_POLICY` case-insensitively. A value that is
+    /// neither `enforce` nor `warn` is most likely a typo for the stricter
+    /// setting, so it enforces and logs an error instead of quietly warning.
+    pub fn parse(raw: &str) -> Self {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "enforce" => Self::Enforce,
+            "warn" => Self::Warn,
+            _ => {
+                log::log_json_ctx(
+                    "error",
+                    "training",
+                    "signature_policy_unknown",
+                    DeltaCode::InvalidInput as u32,
+                    0,
+                    &[("value", raw)],
+                );
+                Self::Enforce
+            }
+        }
+    }
+}
+
+/// Signing and verification keys of the running process.
+#[derive(Clone, Debug, Default)]
+pub struct SigningCfg {
+    pub signing_key: Option<Vec<u8>>,
+    pub trusted_keys: Vec<Vec<u8>>,
+    pub policy: SignaturePolicy,
+}
+
+impl SigningCfg {
+    pub fn from_app(cfg: &AppCfg) -> Self {
+        Self {
+            signing_key: cfg.signing_key.as_ref().map(|key| key.as_bytes().to_vec()),
+            trusted_keys: cfg
+                .trusted_keys
+                .iter()
+                .map(|key| key.as_bytes().to_vec())
+                .collect(),
+            policy: SignaturePolicy::parse(&cfg.signature_policy),
+        }
+    }
+
+    fn trusted(&self) -> impl Iterator<Item = &[u8]> {
+        self.signing_key
+            .iter()
+            .chain(&self.trusted_keys)
+            .map(Vec::as_slice)
+    }
+}
+
+/// Short public identifier of a key.
+pub fn key_id(key: &[u8]) -> String {
+    to_hex(&sha256(key))[..KEY_ID_LEN].to_string()
+}
+
+/// Signature file contents for `artefact`, or `None` without a signing key.
+pub fn sign(cfg: &SigningCfg, artefact: &[u8]) -> Option<String> {
+    let key = cfg.signing_key.as_ref()?;
+    Some(format!(
+        "{{\"algorithm\":\"{ALGORITHM}\",\"key_id\":\"{}\",\"signature\":\"{}\"}}",
+        key_id(key),
+        to_hex(&hmac_sha256(key, artefact))
+    ))
+}
+
+/// Check `signature` (the stored signature file, if any) against `artefact`.
+///
+/// Under [`SignaturePolicy::Warn`] every failure is logged and accepted.
+pub fn verify(cfg: &SigningCfg, artefact: &[u8], signature: Option<&[u8]>) -> DeltaResult<()> {
+    let outcome = match signature {
+        None => Err("artefact_unsigned"),
+        Some(raw) => check(cfg, artefact, raw),
+    };
+    match outcome {
+        Ok(()) => Ok(()),
+        Err(code) if cfg.policy == SignaturePolicy::Enforce => {
+            log::log_json("error", "training", code, 0, 0);
+            Err(DeltaError::policy_denied(code))
+        }
+        Err(code) => {
+            log::log_json("warn", "training", code, 0, 0);
+            Ok(())
+        }
+    }
+}
+
+fn check(cfg: &SigningCfg, artefact: &[u8], raw: &[u8]) -> Result<(), &'static str> {
+    let invalid = "artefact_signature_invalid";
+    let doc = std::str::from_utf8(raw)
+        .ok()
+        .and_then(|text| json::parse(text).ok())
+        .ok_or(invalid)?;
+    let field = |key: &str| doc.get(key).and_then(Value::as_str).ok_or(invalid);
+    if field("algorithm")? != ALGORITHM {
+        return Err(invalid);
+    }
+    let key = cfg
+        .trusted()
+        .find(|key| key_id(key) == field("key_id").unwrap_or_default())
+        .ok_or("artefact_signature_untrusted")?;
+    let expected = to_hex(&hmac_sha256(key, artefact));
+    if constant_time_eq(expected.as_bytes(), field("signature")?.as_bytes()) {
+        Ok(())
+    } else {
+        Err(invalid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg(signing: Option<&str>, trusted: &[&str], policy: SignaturePolicy) -> SigningCfg {
+        SigningCfg {
+            signing_key: signing.map(|key| key.as_bytes().to_vec()),
+            trusted_keys: trusted.iter().map(|key| key.as_bytes().to_vec()).collect(),
+            policy,
+        }
+    }
+
+    #[test]
+    fn trusted_signatures_verify_and_failures_follow_the_policy() {
+        let artefact = b"DELTA1 artefact bytes";
+        let signer = cfg(Some("train-key"), &[], SignaturePolicy::Enforce);
+        let signature = sign(&signer, artefact).unwrap();
+        let serving = cfg(None, &["other", "train-key"], SignaturePolicy::Enforce);
+        assert!(verify(&serving, artefact, Some(signature.as_bytes())).is_ok());
+
+        let code = |cfg: &SigningCfg, bytes: &[u8], sig: Option<&[u8]>| {
+            verify(cfg, bytes, sig).err().map(|err| err.msg)
+        };
+        assert_eq!(
+            code(&serving, b"tampered", Some(signature.as_bytes())),
+            Some("artefact_signature_invalid")
+        );
+        assert_eq!(code(&serving, artefact, None), Some("artefact_unsigned"));
+        let stranger = cfg(None, &["other"], SignaturePolicy::Enforce);
+        assert_eq!(
+            code(&stranger, artefact, Some(signature.as_bytes())),
+            Some("artefact_signature_untrusted")
+        );
+        let lenient = cfg(None, &[], SignaturePolicy::Warn);
+        assert!(verify(&lenient, artefact, None).is_ok());
+        assert!(sign(&lenient, artefact).is_none());
+    }
+
+    #[test]
+    fn policies_parse_case_insensitively_and_unknown_values_enforce() {
+        assert_eq!(SignaturePolicy::parse("Warn"), SignaturePolicy::Warn);
+        assert_eq!(SignaturePolicy::parse("ENFORCE"), SignaturePolicy::Enforce);
+        assert_eq!(SignaturePolicy::parse("enforced"), SignaturePolicy::Enforce);
+        assert_eq!(SignaturePolicy::parse(""), SignaturePolicy::Enforce);
+    }
+}
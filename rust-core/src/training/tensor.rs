@@ -0,0 +1,231 @@
+//! Named weight tensors stored in the artefact `TNSR` section.
+//!
+//! Tensors keep their on-disk representation so artefacts round-trip byte for
+//! byte; [`Tensor::to_f32`] dequantises for computation.
+//!
+//! ```text
+//! tensor  name    u16-prefixed UTF-8
+//!         dtype   u8   0 = f32, 1 = int8, 2 = q4
+//!         rank    u8   followed by `rank` u32 dimensions
+//!         data    f32:  element count × f32
+//!                 int8: f32 scale, then element count × i8 (value = q * scale)
+//!                 q4:   per block of 32 elements an f32 scale and 16 bytes of
+//!                       packed nibbles, low nibble first (value = (q - 8) * scale)
+//! ```
+
+use crate::common::error::DeltaResult;
+
+use super::artefact::{corrupt, put_f32, put_str16, put_u32, Reader};
+
+/// Elements sharing one scale in the Q4 layout.
+pub const Q4_BLOCK: usize = 32;
+const MAX_RANK: usize = 4;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TensorData {
+    F32(Vec<f32>),
+    /// Symmetric per-tensor quantisation.
+    Int8 {
+        scale: f32,
+        values: Vec<i8>,
+    },
+    /// Symmetric 4-bit quantisation with one scale per [`Q4_BLOCK`] elements.
+    Q4 {
+        scales: Vec<f32>,
+        packed: Vec<u8>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tensor {
+    pub name: String,
+    pub shape: Vec<usize>,
+    pub data: TensorData,
+}
+
+impl Tensor {
+    pub fn f32(name: impl Into<String>, shape: Vec<usize>, values: Vec<f32>) -> Self {
+        Self {
+            name: name.into(),
+            shape,
+            data: TensorData::F32(values),
+        }
+    }
+
+    /// Element count implied by the shape.
+    pub fn len(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn dtype(&self) -> &'static str {
+        match self.data {
+            TensorData::F32(_) => "f32",
+            TensorData::Int8 { .. } => "int8",
+            TensorData::Q4 { .. } => "q4",
+        }
+    }
+
+    /// Re-encode the values as int8 with a single scale.
+    pub fn quantize_int8(&self) -> Self {
+        let values = self.to_f32();
+        let max = values.iter().fold(0.0f32, |m, v| m.max(v.abs()));
+        let scale = if max > 0.0 { max / 127.0 } else { 1.0 };
+        Self {
+            name: self.name.clone(),
+            shape: self.shape.clone(),
+            data: TensorData::Int8 {
+                scale,
+                values: values
+                    .iter()
+                    .map(|v| (v / scale).round().clamp(-127.0, 127.0) as i8)
+                    .collect(),
+            },
+        }
+    }
+
+    /// Re-encode the values as Q4 blocks.
+    pub fn quantize_q4(&self) -> Self {
+        let values = self.to_f32();
+        let mut scales = Vec::with_capacity(values.len().div_ceil(Q4_BLOCK));
+        let mut packed = vec![0u8; values.len().div_ceil(Q4_BLOCK) * Q4_BLOCK / 2];
+        for (block, chunk) in values.chunks(Q4_BLOCK).enumerate() {
+            let max = chunk.iter().fold(0.0f32, |m, v| m.max(v.abs()));
+            let scale = if max > 0.0 { max / 7.0 } else { 1.0 };
+            scales.push(scale);
+            for (i, v) in chunk.iter().enumerate() {
+                let q = ((v / scale).round().clamp(-8.0, 7.0) as i32 + 8) as u8;
+                let index = block * Q4_BLOCK + i;
+                packed[index / 2] |= if index.is_multiple_of(2) { q } else { q << 4 };
+            }
+        }
+        Self {
+            name: self.name.clone(),
+            shape: self.shape.clone(),
+            data: TensorData::Q4 { scales, packed },
+        }
+    }
+
+    /// Dequantised values in row-major order.
+    pub fn to_f32(&self) -> Vec<f32> {
+        match &self.data {
+            TensorData::F32(values) => values.clone(),
+            TensorData::Int8 { scale, values } => {
+                values.iter().map(|q| *q as f32 * scale).collect()
+            }
+            TensorData::Q4 { scales, packed } => (0..self.len())
+                .map(|index| {
+                    let byte = packed[index / 2];
+                    let q = if index.is_multiple_of(2) {
+                        byte & 0x0f
+                    } else {
+                        byte >> 4
+                    };
+                    (q as i32 - 8) as f32 * scales[index / Q4_BLOCK]
+                })
+                .collect(),
+        }
+    }
+
+    pub(crate) fn encode(&self, out: &mut Vec<u8>) {
+        put_str16(out, &self.name);
+        out.push(match self.data {
+            TensorData::F32(_) => 0,
+            TensorData::Int8 { .. } => 1,
+            TensorData::Q4 { .. } => 2,
+        });
+        out.push(self.shape.len() as u8);
+        for dim in &self.shape {
+            put_u32(out, *dim as u32);
+        }
+        match &self.data {
+            TensorData::F32(values) => values.iter().for_each(|v| put_f32(out, *v)),
+            TensorData::Int8 { scale, values } => {
+                put_f32(out, *scale);
+                out.extend(values.iter().map(|q| *q as u8));
+            }
+            TensorData::Q4 { scales, packed } => {
+                for (block, scale) in scales.iter().enumerate() {
+                    put_f32(out, *scale);
+                    out.extend_from_slice(
+                        &packed[block * Q4_BLOCK / 2..(block + 1) * Q4_BLOCK / 2],
+                    );
+                }
+            }
+        }
+    }
+
+    pub(crate) fn decode(section: &mut Reader<'_>) -> DeltaResult<Self> {
+        let name = section.str16()?;
+        let dtype = section.u8()?;
+        let rank = section.u8()? as usize;
+        if rank == 0 || rank > MAX_RANK {
+            return Err(corrupt("artefact_tensor_invalid"));
+        }
+        let mut shape = Vec::with_capacity(rank);
+        for _ in 0..rank {
+            shape.push(section.u32()? as usize);
+        }
+        let len = shape
+            .iter()
+            .try_fold(1usize, |acc, dim| acc.checked_mul(*dim))
+            .filter(|len| *len <= section.remaining())
+            .ok_or_else(|| corrupt("artefact_tensor_invalid"))?;
+
+        let data = match dtype {
+            0 => {
+                let mut values = Vec::with_capacity(len);
+                for _ in 0..len {
+                    values.push(section.f32()?);
+                }
+                TensorData::F32(values)
+            }
+            1 => TensorData::Int8 {
+                scale: section.f32()?,
+                values: section.take(len)?.iter().map(|b| *b as i8).collect(),
+            },
+            2 => {
+                let blocks = len.div_ceil(Q4_BLOCK);
+                let mut scales = Vec::with_capacity(blocks);
+                let mut packed = Vec::with_capacity(blocks * Q4_BLOCK / 2);
+                for _ in 0..blocks {
+                    scales.push(section.f32()?);
+                    packed.extend_from_slice(section.take(Q4_BLOCK / 2)?);
+                }
+                TensorData::Q4 { scales, packed }
+            }
+            _ => return Err(corrupt("artefact_tensor_dtype_unknown")),
+        };
+        Ok(Self { name, shape, data })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantised_tensors_roundtrip_and_stay_close() {
+        let values = (0..40)
+            .map(|i| (i as f32 - 20.0) / 10.0)
+            .collect::<Vec<_>>();
+        let tensor = Tensor::f32("w", vec![4, 10], values.clone());
+        for (quantised, tolerance) in [(tensor.quantize_int8(), 0.01), (tensor.quantize_q4(), 0.15)]
+        {
+            let mut bytes = Vec::new();
+            quantised.encode(&mut bytes);
+            let mut reader = Reader::new(&bytes);
+            let decoded = Tensor::decode(&mut reader).unwrap();
+            reader.finish().unwrap();
+            assert_eq!(decoded, quantised);
+            let restored = decoded.to_f32();
+            assert!(values
+                .iter()
+                .zip(&restored)
+                .all(|(a, b)| (a - b).abs() <= tolerance));
+        }
+    }
+}
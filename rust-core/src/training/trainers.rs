@@ -0,0 +1,720 @@
+//! Deterministic trainers for the tabular model families.
+//!
+//! Both trainers fit binary classifiers on the vectors produced by the
+//! feature pipeline and store their parameters in the artefact layout:
+//!
+//! * logistic regression: `weights = [intercept, w_1, .., w_n]`, fitted with
+//!   full-batch gradient descent and an L2 penalty;
+//! * gradient boosting: `weights = [base_margin]` plus regression trees on the
+//!   logistic loss, leaf values already scaled by the learning rate.
+//!
+//! A sample goes to the left child when `x[feature] < threshold`.
+//!
+//! [`fit_warm`] continues from a previous fit: logistic regression starts from
+//! the parent weights, gradient boosting keeps the parent trees and adds
+//! `n_trees` new rounds on top of their margin.
+//!
+//! With differential privacy enabled, logistic regression (and with it the
+//! text heads) trains with DP-SGD: every example's gradient is clipped to
+//! `dp.clip` and each step adds Gaussian noise with standard deviation
+//! `dp.noise_multiplier * dp.clip` to the summed gradient. The noise is drawn
+//! from fresh OS entropy, so DP runs are not byte-for-byte reproducible.
+//! Gradient boosting has no private variant yet and rejects `dp.enabled`.
+
+use crate::common::error::{DeltaError, DeltaResult};
+use crate::common::json::Value;
+
+use super::domain::{DifferentialPrivacy, Hyperparams, ModelKind, TrainControl, Tree, TreeNode};
+use super::search::SplitMix64;
+
+const MIN_LEAF: usize = 1;
+const EPS: f64 = 1e-7;
+
+/// Parameters produced by a trainer, ready to be copied into an artefact.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Fitted {
+    pub weights: Vec<f32>,
+    pub trees: Vec<Tree>,
+}
+
+impl Fitted {
+    /// Probability of the positive class for one feature vector.
+    pub fn predict(&self, row: &[f32]) -> f32 {
+        predict_proba(&self.weights, &self.trees, row)
+    }
+}
+
+/// Probability of the positive class given stored weights and trees.
+pub fn predict_proba(weights: &[f32], trees: &[Tree], row: &[f32]) -> f32 {
+    sigmoid(margin(weights, trees, row))
+}
+
+fn margin(weights: &[f32], trees: &[Tree], row: &[f32]) -> f32 {
+    let mut margin = weights.first().copied().unwrap_or_default();
+    for (w, x) in weights.iter().skip(1).zip(row) {
+        margin += w * x;
+    }
+    for tree in trees {
+        margin += tree_value(tree, row);
+    }
+    margin
+}
+
+/// Per-feature contribution to the margin of `row`: `wᵢ·xᵢ` for linear
+/// weights, and for trees each leaf value shared equally among the features
+/// split on along the path that reached it.
+pub fn contributions(weights: &[f32], trees: &[Tree], row: &[f32]) -> Vec<f32> {
+    let mut out = row
+        .iter()
+        .zip(weights.iter().skip(1).chain(std::iter::repeat(&0.0)))
+        .map(|(x, w)| w * x)
+        .collect::<Vec<_>>();
+    let mut path = Vec::new();
+    for tree in trees {
+        path.clear();
+        let mut index = 0usize;
+        let mut leaf = None;
+        for _ in 0..tree.nodes.len() {
+            let Some(node) = tree.nodes.get(index) else {
+                break;
+            };
+            if node.is_leaf() {
+                leaf = Some(node.value);
+                break;
+            }
+            path.push(node.feature as usize);
+            let x = row.get(node.feature as usize).copied().unwrap_or_default();
+            index = if x < node.threshold {
+                node.left
+            } else {
+                node.right
+            } as usize;
+        }
+        if let Some(value) = leaf.filter(|_| !path.is_empty()) {
+            let share = value / path.len() as f32;
+            for &feature in &path {
+                if let Some(slot) = out.get_mut(feature) {
+                    *slot += share;
+                }
+            }
+        }
+    }
+    out
+}
+
+fn tree_value(tree: &Tree, row: &[f32]) -> f32 {
+    let mut index = 0usize;
+    // Depth is bounded by the node count; the guard protects against cycles in foreign artefacts.
+    for _ in 0..tree.nodes.len() {
+        let node = match tree.nodes.get(index) {
+            Some(node) => node,
+            None => break,
+        };
+        if node.is_leaf() {
+            return node.value;
+        }
+        let x = row.get(node.feature as usize).copied().unwrap_or_default();
+        index = if x < node.threshold {
+            node.left
+        } else {
+            node.right
+        } as usize;
+    }
+    0.0
+}
+
+/// Extract binary labels (`0`/`1` numbers or booleans) from the training records.
+pub fn labels(records: &[Value], label: &str) -> DeltaResult<Vec<f32>> {
+    records
+        .iter()
+        .map(|record| match record.get(label) {
+            Some(Value::Bool(flag)) => Ok(if *flag { 1.0 } else { 0.0 }),
+            Some(Value::Number(n)) if *n == 0.0 || *n == 1.0 => Ok(*n as f32),
+            None | Some(Value::Null) => Err(DeltaError::invalid("label_missing")),
+            Some(_) => Err(DeltaError::invalid("label_invalid")),
+        })
+        .collect()
+}
+
+/// Fit a model of the given kind on `x`/`y`, reporting every epoch under `phase`.
+pub fn fit(
+    kind: ModelKind,
+    x: &[Vec<f32>],
+    y: &[f32],
+    params: &Hyperparams,
+    control: &TrainControl,
+    phase: &'static str,
+) -> DeltaResult<Fitted> {
+    let dp = DifferentialPrivacy::default();
+    fit_warm(kind, None, x, y, params, &dp, control, phase)
+}
+
+/// Like [`fit`], optionally continuing from the parameters of a parent model
+/// and applying `dp` when it is enabled.
+#[allow(clippy::too_many_arguments)]
+pub fn fit_warm(
+    kind: ModelKind,
+    init: Option<&Fitted>,
+    x: &[Vec<f32>],
+    y: &[f32],
+    params: &Hyperparams,
+    dp: &DifferentialPrivacy,
+    control: &TrainControl,
+    phase: &'static str,
+) -> DeltaResult<Fitted> {
+    if x.is_empty() || x.len() != y.len() {
+        return Err(DeltaError::invalid("dataset_empty"));
+    }
+    params.validate()?;
+    let dp = dp.enabled.then_some(dp);
+    match kind {
+        ModelKind::TabularLogistic => fit_logistic(init, x, y, params, dp, control, phase),
+        ModelKind::TabularGradientBoosting if dp.is_some() => {
+            Err(DeltaError::policy_denied("dp_unsupported_model_kind"))
+        }
+        ModelKind::TabularGradientBoosting => fit_gbdt(init, x, y, params, control, phase),
+        ModelKind::TextMiniLm => Err(DeltaError::invalid("trainer_unsupported_model_kind")),
+    }
+}
+
+fn fit_logistic(
+    init: Option<&Fitted>,
+    x: &[Vec<f32>],
+    y: &[f32],
+    params: &Hyperparams,
+    dp: Option<&DifferentialPrivacy>,
+    control: &TrainControl,
+    phase: &'static str,
+) -> DeltaResult<Fitted> {
+    let dims = x[0].len();
+    let n = x.len() as f32;
+    let mut weights = match init {
+        Some(parent) if parent.weights.len() == dims + 1 && parent.trees.is_empty() => {
+            parent.weights.clone()
+        }
+        Some(_) => return Err(DeltaError::invalid("warm_start_manifest_mismatch")),
+        None => vec![0.0f32; dims + 1],
+    };
+    let mut grad = vec![0.0f32; dims + 1];
+    let mut example = vec![0.0f32; dims + 1];
+    let mut noise = dp.map(|_| SplitMix64::new(noise_seed()));
+
+    for epoch in 0..params.epochs {
+        control.check()?;
+        grad.iter_mut().for_each(|g| *g = 0.0);
+        let mut loss = 0.0;
+        for (row, target) in x.iter().zip(y) {
+            let p = predict_proba(&weights, &[], row);
+            loss += sample_loss(*target, p);
+            example_gradient(&mut example, p - target, row);
+            if let Some(dp) = dp {
+                clip_gradient(&mut example, dp.clip);
+            }
+            for (g, e) in grad.iter_mut().zip(&example) {
+                *g += e;
+            }
+        }
+        if let (Some(dp), Some(rng)) = (dp, noise.as_mut()) {
+            let std_dev = (dp.noise_multiplier * dp.clip) as f64;
+            for g in grad.iter_mut() {
+                *g += (rng.gaussian() * std_dev) as f32;
+            }
+        }
+        weights[0] -= params.learning_rate * grad[0] / n;
+        for (w, g) in weights[1..].iter_mut().zip(&grad[1..]) {
+            *w -= params.learning_rate * (g / n + params.l2 * *w);
+        }
+        control.report(phase, epoch + 1, params.epochs, loss / x.len() as f64);
+    }
+
+    Ok(Fitted {
+        weights,
+        trees: Vec::new(),
+    })
+}
+
+/// Log-loss gradient of one example, `err * [1, x_1, .., x_n]`.
+fn example_gradient(out: &mut [f32], err: f32, row: &[f32]) {
+    out[0] = err;
+    for (g, v) in out[1..].iter_mut().zip(row) {
+        *g = err * v;
+    }
+}
+
+/// Scale `gradient` down to an L2 norm of at most `clip`.
+fn clip_gradient(gradient: &mut [f32], clip: f32) {
+    let norm = gradient.iter().map(|g| g * g).sum::<f32>().sqrt();
+    if norm > clip {
+        let scale = clip / norm;
+        gradient.iter_mut().for_each(|g| *g *= scale);
+    }
+}
+
+/// Seed for DP noise from OS entropy; it must not be predictable from the run.
+fn noise_seed() -> u64 {
+    use std::io::Read;
+
+    let mut bytes = [0u8; 8];
+    if std::fs::File::open("/dev/urandom")
+        .and_then(|mut file| file.read_exact(&mut bytes))
+        .is_ok()
+    {
+        return u64::from_le_bytes(bytes);
+    }
+    use std::hash::{BuildHasher, Hasher};
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u128(crate::common::time::now_ms());
+    hasher.finish()
+}
+
+fn fit_gbdt(
+    init: Option<&Fitted>,
+    x: &[Vec<f32>],
+    y: &[f32],
+    params: &Hyperparams,
+    control: &TrainControl,
+    phase: &'static str,
+) -> DeltaResult<Fitted> {
+    let n = x.len();
+    let (base, mut trees) = match init {
+        Some(parent) if parent.weights.len() == 1 => (parent.weights[0], parent.trees.clone()),
+        Some(_) => return Err(DeltaError::invalid("warm_start_manifest_mismatch")),
+        None => {
+            let positive = y.iter().map(|v| *v as f64).sum::<f64>() / n as f64;
+            let prior = positive.clamp(EPS, 1.0 - EPS);
+            ((prior / (1.0 - prior)).ln() as f32, Vec::new())
+        }
+    };
+    if trees
+        .iter()
+        .flat_map(|tree| &tree.nodes)
+        .any(|node| !node.is_leaf() && node.feature as usize >= x[0].len())
+    {
+        return Err(DeltaError::invalid("warm_start_manifest_mismatch"));
+    }
+
+    let mut margins = x
+        .iter()
+        .map(|row| margin(&[base], &trees, row))
+        .collect::<Vec<_>>();
+    trees.reserve(params.n_trees as usize);
+    let mut grad = vec![0.0f64; n];
+    let mut hess = vec![0.0f64; n];
+
+    for round in 0..params.n_trees {
+        control.check()?;
+        for i in 0..n {
+            let p = sigmoid(margins[i]) as f64;
+            grad[i] = p - y[i] as f64;
+            hess[i] = (p * (1.0 - p)).max(EPS);
+        }
+        let mut nodes = Vec::new();
+        let grower = Grower {
+            x,
+            grad: &grad,
+            hess: &hess,
+            params,
+        };
+        grower.grow(&mut nodes, (0..n).collect(), 0);
+        let tree = Tree { nodes };
+        for (m, row) in margins.iter_mut().zip(x) {
+            *m += tree_value(&tree, row);
+        }
+        trees.push(tree);
+        let loss = margins
+            .iter()
+            .zip(y)
+            .map(|(m, t)| sample_loss(*t, sigmoid(*m)))
+            .sum::<f64>();
+        control.report(phase, round + 1, params.n_trees, loss / n as f64);
+    }
+
+    Ok(Fitted {
+        weights: vec![base],
+        trees,
+    })
+}
+
+struct Grower<'a> {
+    x: &'a [Vec<f32>],
+    grad: &'a [f64],
+    hess: &'a [f64],
+    params: &'a Hyperparams,
+}
+
+impl Grower<'_> {
+    /// Append the subtree for `rows` to `nodes` and return its root index.
+    fn grow(&self, nodes: &mut Vec<TreeNode>, rows: Vec<usize>, depth: u32) -> u32 {
+        let lambda = self.params.l2 as f64;
+        let g: f64 = rows.iter().map(|&i| self.grad[i]).sum();
+        let h: f64 = rows.iter().map(|&i| self.hess[i]).sum();
+        let index = nodes.len();
+        let value = (-g / (h + lambda)) as f32 * self.params.learning_rate;
+        nodes.push(TreeNode::leaf(value));
+
+        if depth >= self.params.max_depth || rows.len() < 2 * MIN_LEAF {
+            return index as u32;
+        }
+        let (feature, threshold) = match self.best_split(&rows, g, h) {
+            Some(split) => split,
+            None => return index as u32,
+        };
+
+        let (left_rows, right_rows): (Vec<usize>, Vec<usize>) = rows
+            .into_iter()
+            .partition(|&i| self.value(i, feature) < threshold);
+        let left = self.grow(nodes, left_rows, depth + 1);
+        let right = self.grow(nodes, right_rows, depth + 1);
+        nodes[index] = TreeNode {
+            feature: feature as u32,
+            threshold,
+            left,
+            right,
+            value: 0.0,
+        };
+        index as u32
+    }
+
+    fn value(&self, row: usize, feature: usize) -> f32 {
+        self.x[row].get(feature).copied().unwrap_or_default()
+    }
+
+    /// Exact greedy split search; ties keep the lowest feature index and threshold.
+    fn best_split(&self, rows: &[usize], g: f64, h: f64) -> Option<(usize, f32)> {
+        let lambda = self.params.l2 as f64;
+        let parent = g * g / (h + lambda);
+        let dims = self.x[rows[0]].len();
+        let mut best: Option<(f64, usize, f32)> = None;
+        let mut sorted = rows.to_vec();
+
+        for feature in 0..dims {
+            sorted.sort_by(|a, b| {
+                self.value(*a, feature)
+                    .total_cmp(&self.value(*b, feature))
+                    .then(a.cmp(b))
+            });
+            let (mut gl, mut hl) = (0.0, 0.0);
+            for k in 0..sorted.len() - 1 {
+                gl += self.grad[sorted[k]];
+                hl += self.hess[sorted[k]];
+                let (lo, hi) = (
+                    self.value(sorted[k], feature),
+                    self.value(sorted[k + 1], feature),
+                );
+                if lo == hi || k + 1 < MIN_LEAF || sorted.len() - k - 1 < MIN_LEAF {
+                    continue;
+                }
+                let (gr, hr) = (g - gl, h - hl);
+                let gain = gl * gl / (hl + lambda) + gr * gr / (hr + lambda) - parent;
+                if gain > 1e-9 && best.is_none_or(|(b, _, _)| gain > b) {
+                    // Adjacent floats can round the midpoint down onto `lo`.
+                    let mid = lo + (hi - lo) / 2.0;
+                    best = Some((gain, feature, if mid > lo { mid } else { hi }));
+                }
+            }
+        }
+        best.map(|(_, feature, threshold)| (feature, threshold))
+    }
+}
+
+/// Mean binary cross-entropy of predicted probabilities.
+pub fn log_loss(y: &[f32], p: &[f32]) -> f64 {
+    if y.is_empty() {
+        return 0.0;
+    }
+    let total: f64 = y.iter().zip(p).map(|(t, p)| sample_loss(*t, *p)).sum();
+    total / y.len() as f64
+}
+
+fn sample_loss(target: f32, p: f32) -> f64 {
+    let p = (p as f64).clamp(EPS, 1.0 - EPS);
+    let t = target as f64;
+    -(t * p.ln() + (1.0 - t) * (1.0 - p).ln())
+}
+
+/// Share of predictions on the correct side of 0.5.
+pub fn accuracy(y: &[f32], p: &[f32]) -> f64 {
+    if y.is_empty() {
+        return 0.0;
+    }
+    let hits = y
+        .iter()
+        .zip(p)
+        .filter(|(t, p)| (**p >= 0.5) == (**t >= 0.5))
+        .count();
+    hits as f64 / y.len() as f64
+}
+
+/// Area under the ROC curve, counting tied scores as half; 0.5 when only
+/// one class is present.
+pub fn auc(y: &[f32], p: &[f32]) -> f64 {
+    let mut order = (0..y.len().min(p.len())).collect::<Vec<_>>();
+    order.sort_by(|a, b| p[*a].total_cmp(&p[*b]));
+    let positives = order.iter().filter(|i| y[**i] >= 0.5).count();
+    let negatives = order.len() - positives;
+    if positives == 0 || negatives == 0 {
+        return 0.5;
+    }
+    // Mann-Whitney U with average ranks over runs of equal scores.
+    let mut rank_sum = 0.0;
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && p[order[end]] == p[order[start]] {
+            end += 1;
+        }
+        let rank = (start + end + 1) as f64 / 2.0;
+        let hits = order[start..end].iter().filter(|i| y[**i] >= 0.5).count();
+        rank_sum += rank * hits as f64;
+        start = end;
+    }
+    let pairs = (positives * negatives) as f64;
+    (rank_sum - (positives * (positives + 1)) as f64 / 2.0) / pairs
+}
+
+/// Total split gain per input dimension of boosted trees on `x`.
+///
+/// The trees are replayed in order from the base margin, scoring each split
+/// with the gradient statistics of the rows that reach it, as during fitting.
+pub fn gain_importance(fitted: &Fitted, x: &[Vec<f32>], y: &[f32], l2: f32) -> Vec<f64> {
+    let lambda = l2 as f64;
+    let dims = x.first().map(Vec::len).unwrap_or_default();
+    let mut gains = vec![0.0; dims];
+    let mut margins = x
+        .iter()
+        .map(|row| margin(&fitted.weights, &[], row))
+        .collect::<Vec<_>>();
+    for tree in &fitted.trees {
+        let mut sums = vec![(0.0f64, 0.0f64); tree.nodes.len()];
+        for ((row, m), t) in x.iter().zip(&margins).zip(y) {
+            let p = sigmoid(*m) as f64;
+            let (g, h) = (p - *t as f64, (p * (1.0 - p)).max(EPS));
+            let mut index = 0usize;
+            for _ in 0..tree.nodes.len() {
+                let Some(node) = tree.nodes.get(index) else {
+                    break;
+                };
+                sums[index].0 += g;
+                sums[index].1 += h;
+                if node.is_leaf() {
+                    break;
+                }
+                let value = row.get(node.feature as usize).copied().unwrap_or_default();
+                index = if value < node.threshold {
+                    node.left
+                } else {
+                    node.right
+                } as usize;
+            }
+        }
+        let score = |(g, h): (f64, f64)| g * g / (h + lambda);
+        for (index, node) in tree.nodes.iter().enumerate() {
+            if node.is_leaf() {
+                continue;
+            }
+            let (Some(left), Some(right)) =
+                (sums.get(node.left as usize), sums.get(node.right as usize))
+            else {
+                continue;
+            };
+            if let Some(gain) = gains.get_mut(node.feature as usize) {
+                *gain += (score(*left) + score(*right) - score(sums[index])).max(0.0);
+            }
+        }
+        for (m, row) in margins.iter_mut().zip(x) {
+            *m += tree_value(tree, row);
+        }
+    }
+    gains
+}
+
+fn sigmoid(z: f32) -> f32 {
+    1.0 / (1.0 + (-z).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn separable() -> (Vec<Vec<f32>>, Vec<f32>) {
+        let x = (0..20).map(|i| vec![(i as f32 - 9.5) / 10.0]).collect();
+        let y = (0..20).map(|i| if i >= 10 { 1.0 } else { 0.0 }).collect();
+        (x, y)
+    }
+
+    #[test]
+    fn contributions_weigh_inputs_by_the_model() {
+        // A large input with a small weight contributes less than a small
+        // input with a large weight.
+        let linear = contributions(&[0.5, 0.1, 4.0], &[], &[10.0, 0.5]);
+        assert_eq!(linear, vec![1.0, 2.0]);
+
+        let tree = Tree {
+            nodes: vec![
+                TreeNode {
+                    feature: 1,
+                    threshold: 0.0,
+                    left: 1,
+                    right: 2,
+                    value: 0.0,
+                },
+                TreeNode::leaf(-0.4),
+                TreeNode::leaf(0.6),
+            ],
+        };
+        let boosted = contributions(&[0.1], std::slice::from_ref(&tree), &[9.0, 1.0]);
+        assert_eq!(boosted, vec![0.0, 0.6]);
+    }
+
+    #[test]
+    fn both_trainers_learn_a_threshold() {
+        let (x, y) = separable();
+        for kind in [
+            ModelKind::TabularLogistic,
+            ModelKind::TabularGradientBoosting,
+        ] {
+            let fitted = fit(
+                kind,
+                &x,
+                &y,
+                &Hyperparams::default(),
+                &TrainControl::default(),
+                "fit",
+            )
+            .unwrap();
+            let p = x.iter().map(|row| fitted.predict(row)).collect::<Vec<_>>();
+            assert_eq!(accuracy(&y, &p), 1.0, "{kind:?}");
+            assert!(log_loss(&y, &p) < 0.5, "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn training_is_deterministic() {
+        let (x, y) = separable();
+        let params = Hyperparams::default();
+        let control = TrainControl::default();
+        let a = fit(
+            ModelKind::TabularGradientBoosting,
+            &x,
+            &y,
+            &params,
+            &control,
+            "fit",
+        )
+        .unwrap();
+        let b = fit(
+            ModelKind::TabularGradientBoosting,
+            &x,
+            &y,
+            &params,
+            &control,
+            "fit",
+        )
+        .unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn auc_ranks_scores_and_gain_follows_the_informative_feature() {
+        assert_eq!(auc(&[0.0, 0.0, 1.0, 1.0], &[0.1, 0.4, 0.35, 0.8]), 0.75);
+        assert_eq!(auc(&[0.0, 1.0], &[0.5, 0.5]), 0.5);
+        assert_eq!(auc(&[1.0, 1.0], &[0.2, 0.9]), 0.5);
+
+        let (x, y) = separable();
+        // A constant second column never splits and must get no gain.
+        let x = x
+            .into_iter()
+            .map(|row| vec![row[0], 1.0])
+            .collect::<Vec<_>>();
+        let params = Hyperparams::default();
+        let fitted = fit(
+            ModelKind::TabularGradientBoosting,
+            &x,
+            &y,
+            &params,
+            &TrainControl::default(),
+            "fit",
+        )
+        .unwrap();
+        let gains = gain_importance(&fitted, &x, &y, params.l2);
+        assert!(gains[0] > 0.0);
+        assert_eq!(gains[1], 0.0);
+    }
+
+    #[test]
+    fn reports_progress_and_stops_when_cancelled() {
+        let (x, y) = separable();
+        let params = Hyperparams {
+            epochs: 5,
+            ..Hyperparams::default()
+        };
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = events.clone();
+        let control = TrainControl::with_sink(move |event| {
+            seen.lock().unwrap().push((event.epoch, event.total_epochs));
+        });
+        fit(ModelKind::TabularLogistic, &x, &y, &params, &control, "fit").unwrap();
+        assert_eq!(events.lock().unwrap().last(), Some(&(5, 5)));
+
+        control.cancel();
+        let err = fit(ModelKind::TabularLogistic, &x, &y, &params, &control, "fit").unwrap_err();
+        assert_eq!(err.msg, "training_cancelled");
+        assert_eq!(err.code, crate::common::error::DeltaCode::InvalidInput);
+    }
+
+    #[test]
+    fn differential_privacy_clips_and_perturbs_the_logistic_fit() {
+        let (x, y) = separable();
+        let params = Hyperparams::default();
+        let control = TrainControl::default();
+        let dp = DifferentialPrivacy {
+            enabled: true,
+            epsilon: 3.0,
+            delta: 1e-5,
+            clip: 0.5,
+            noise_multiplier: 1.1,
+        };
+        let plain = fit(ModelKind::TabularLogistic, &x, &y, &params, &control, "fit").unwrap();
+        let private = fit_warm(
+            ModelKind::TabularLogistic,
+            None,
+            &x,
+            &y,
+            &params,
+            &dp,
+            &control,
+            "fit",
+        )
+        .unwrap();
+        assert_ne!(plain, private);
+        assert!(private.weights.iter().all(|w| w.is_finite()));
+
+        // Per-example gradients never exceed the clip norm.
+        let mut gradient = vec![0.0; 3];
+        for row in [[40.0, -3.0], [0.1, 0.2], [-900.0, 5.0]] {
+            example_gradient(&mut gradient, 0.9, &row);
+            clip_gradient(&mut gradient, dp.clip);
+            let norm = gradient.iter().map(|g| g * g).sum::<f32>().sqrt();
+            assert!(norm <= dp.clip + 1e-6, "{norm}");
+        }
+
+        let err = fit_warm(
+            ModelKind::TabularGradientBoosting,
+            None,
+            &x,
+            &y,
+            &params,
+            &dp,
+            &control,
+            "fit",
+        )
+        .unwrap_err();
+        assert_eq!(
+            (err.code, err.msg),
+            (
+                crate::common::error::DeltaCode::PolicyDenied,
+                "dp_unsupported_model_kind"
+            )
+        );
+    }
+}